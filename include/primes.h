@@ -0,0 +1,39 @@
+/*
+ * C interface to the primes crate, from its `ffi` feature.
+ *
+ * Build the library with
+ *
+ *     cargo rustc --release --features ffi --crate-type cdylib
+ *
+ * and link with -lprimes. Every function is exact over the whole uint64_t range.
+ */
+
+#ifndef PRIMES_H
+#define PRIMES_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Whether n is prime. */
+bool primes_is_prime(uint64_t n);
+
+/* The smallest prime greater than n, or 0 if there is none that fits in a uint64_t. */
+uint64_t primes_next_prime(uint64_t n);
+
+/*
+ * Writes the prime factors of n, in ascending order and with repeats, to out, and returns how
+ * many there are. At most len factors are written; a buffer of 64 is always enough. out may be
+ * NULL when len is 0. Both 0 and 1 have no factors.
+ */
+size_t primes_factorize(uint64_t n, uint64_t *out, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* PRIMES_H */
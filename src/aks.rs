@@ -0,0 +1,135 @@
+/*!
+The Agrawal–Kayal–Saxena primality test, for teaching.
+
+AKS was the first primality test proven to be deterministic, unconditional and polynomial-time all
+at once, but it is far slower in practice than trial division for anything a `u64` can hold, let
+alone the Miller–Rabin test the rest of the crate uses. It is here for studying: every step is a
+public function, and the polynomial arithmetic modulo `(X^r - 1, n)` that does the real work is
+exposed as `Poly`, so each stage can be timed or traced on its own.
+
+```
+use primes::aks;
+
+assert!(aks::is_prime(97));
+assert!(!aks::is_prime(91));
+assert!(aks::is_perfect_power(343));
+```
+*/
+
+use crate::arith::{add_mod, gcd, mul_mod};
+use crate::{euler_phi, multiplicative_order};
+
+/// A polynomial with coefficients modulo `n`, reduced modulo `X^r - 1`: the ring AKS works in.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Poly {
+    // The coefficient of `X^i` is at index `i`, for `i` below `r`.
+    coeffs: Vec<u64>,
+    n: u64,
+}
+
+impl Poly {
+    /**
+    The polynomial with the given coefficients, lowest degree first, modulo `n` and
+    `X^r - 1` where `r` is the number of coefficients.
+
+    Panics if `coeffs` is empty or `n` is zero.
+    **/
+    pub fn new(mut coeffs: Vec<u64>, n: u64) -> Poly {
+        assert!(!coeffs.is_empty() && n > 0, "need r > 0 and n > 0");
+        coeffs.iter_mut().for_each(|c| *c %= n);
+        Poly { coeffs, n }
+    }
+
+    /// `X^e + a`, modulo `X^r - 1` and `n`.
+    pub fn monomial_plus(e: u64, a: u64, r: usize, n: u64) -> Poly {
+        let mut coeffs = vec![0; r];
+        coeffs[(e % r as u64) as usize] += 1;
+        coeffs[0] += a % n;
+        Poly::new(coeffs, n)
+    }
+
+    /// The coefficients, lowest degree first.
+    pub fn coeffs(&self) -> &[u64] {
+        &self.coeffs
+    }
+
+    /// The product of `self` and `other`, which have to share `r` and `n`. Schoolbook, so it
+    /// takes `r²` multiplications.
+    pub fn mul(&self, other: &Poly) -> Poly {
+        let r = self.coeffs.len();
+        assert!(r == other.coeffs.len() && self.n == other.n, "polynomials from different rings");
+        let mut out = vec![0u64; r];
+        for (i, &a) in self.coeffs.iter().enumerate().filter(|&(_, &a)| a != 0) {
+            for (j, &b) in other.coeffs.iter().enumerate() {
+                let k = (i + j) % r;
+                out[k] = add_mod(out[k], mul_mod(a, b, self.n), self.n);
+            }
+        }
+        Poly { coeffs: out, n: self.n }
+    }
+
+    /// `self` to the power `e`, by repeated squaring.
+    pub fn pow(&self, mut e: u64) -> Poly {
+        let mut base = self.clone();
+        let mut acc = Poly::monomial_plus(0, 0, self.coeffs.len(), self.n);
+        while e > 0 {
+            if e & 1 == 1 {
+                acc = acc.mul(&base);
+            }
+            e >>= 1;
+            if e > 0 {
+                base = base.mul(&base);
+            }
+        }
+        acc
+    }
+}
+
+/// Whether `n` is `a^b` for some integers `a` and `b > 1`, the first thing AKS rules out.
+pub fn is_perfect_power(n: u64) -> bool {
+    n > 3
+        && (2..64).any(|b| {
+            let a = (n as f64).powf(1.0 / f64::from(b)).round() as u64;
+            // The floating-point root is close; check its neighbours exactly.
+            (a.saturating_sub(1)..=a + 1).any(|a| a.checked_pow(b) == Some(n))
+        })
+}
+
+/// The smallest `r` coprime to `n` for which the order of `n` modulo `r` exceeds `log₂(n)²`.
+/// AKS shows one exists below about `log₂(n)^5`, and in practice it is far smaller.
+pub fn find_r(n: u64) -> u64 {
+    let bits = u64::from(64 - n.leading_zeros());
+    (2..)
+        .find(|&r| multiplicative_order(n % r, r).is_some_and(|k| k > bits * bits))
+        .unwrap()
+}
+
+/// Whether `(X + a)^n ≡ X^n + a` modulo `X^r - 1` and `n`, the congruence every prime `n`
+/// satisfies for every `a`.
+pub fn congruence_holds(n: u64, r: u64, a: u64) -> bool {
+    let r = r as usize;
+    Poly::monomial_plus(1, a, r, n).pow(n) == Poly::monomial_plus(n, a, r, n)
+}
+
+/**
+Whether `n` is prime, by the AKS test.
+
+In order: perfect powers are composite; `r` is found with `find_r`; any `a` up to `r` that shares
+a factor with `n` shows it's composite; if `n ≤ r` it's prime; and otherwise it's prime exactly
+when `congruence_holds` for every `a` up to `√φ(r) log₂ n`. Each congruence takes about
+`r² log₂ n` multiplications, so even three-digit `n` take far longer than by trial division.
+**/
+pub fn is_prime(n: u64) -> bool {
+    if n < 2 || is_perfect_power(n) {
+        return false;
+    }
+    let r = find_r(n);
+    if (2..=r.min(n - 1)).any(|a| gcd(a, n) > 1) {
+        return false;
+    }
+    if n <= r {
+        return true;
+    }
+    let limit = ((euler_phi(r) as f64).sqrt() * (n as f64).log2()).floor() as u64;
+    (1..=limit).all(|a| congruence_holds(n, r, a))
+}
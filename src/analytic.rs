@@ -0,0 +1,201 @@
+//! Sums of real-valued functions over the primes: Chebyshev's θ and ψ, the prime zeta function and
+//! Brun's sum; and the smooth approximations to π(x) and the `n`th prime that they are measured
+//! against.
+
+use crate::range::sieve_range;
+
+/**
+A running floating-point sum, so the sums in this module can trade speed for precision.
+
+`f64` itself is the fast choice. Past a few billion terms its rounding error grows large enough to
+matter, and `DoubleDouble` keeps about twice as many bits at a few times the cost per term.
+**/
+pub trait Accumulator: Default {
+    /// Adds `x` to the sum.
+    fn add(&mut self, x: f64);
+
+    /// The sum so far, rounded to the nearest `f64`.
+    fn value(&self) -> f64;
+}
+
+impl Accumulator for f64 {
+    fn add(&mut self, x: f64) {
+        *self += x;
+    }
+
+    fn value(&self) -> f64 {
+        *self
+    }
+}
+
+/**
+A sum kept as an unevaluated pair `hi + lo` of `f64`s, which carries about 106 bits of precision.
+
+Each addition captures the rounding error of `hi + x` exactly (Knuth's two-sum) and folds it into
+`lo`, so the error of the total stays near one rounding of the final result instead of growing with
+the number of terms.
+**/
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DoubleDouble {
+    hi: f64,
+    lo: f64,
+}
+
+impl Accumulator for DoubleDouble {
+    fn add(&mut self, x: f64) {
+        let s = self.hi + x;
+        let b = s - self.hi;
+        let err = (self.hi - (s - b)) + (x - b);
+        let lo = self.lo + err;
+        self.hi = s + lo;
+        self.lo = lo - (self.hi - s);
+    }
+
+    fn value(&self) -> f64 {
+        self.hi + self.lo
+    }
+}
+
+/// Chebyshev's θ(x), the sum of `ln p` over the primes `p` up to `x`, summed in an `f64`.
+pub fn chebyshev_theta(x: u64) -> f64 {
+    chebyshev_theta_with::<f64>(x)
+}
+
+/// Chebyshev's θ(x), summed in the accumulator `A`.
+pub fn chebyshev_theta_with<A: Accumulator>(x: u64) -> f64 {
+    let mut acc = A::default();
+    sieve_range(0..x.saturating_add(1), |p| {
+        acc.add((p as f64).ln());
+        true
+    });
+    acc.value()
+}
+
+/// Chebyshev's ψ(x), the sum of `ln p` over the prime powers `p^k` up to `x`, summed in an `f64`.
+pub fn chebyshev_psi(x: u64) -> f64 {
+    chebyshev_psi_with::<f64>(x)
+}
+
+/// Chebyshev's ψ(x), summed in the accumulator `A`.
+pub fn chebyshev_psi_with<A: Accumulator>(x: u64) -> f64 {
+    let mut acc = A::default();
+    sieve_range(0..x.saturating_add(1), |p| {
+        // Each prime contributes once for every power of it up to `x`.
+        let mut k = 1;
+        let mut q = p;
+        while let Some(next) = q.checked_mul(p).filter(|&next| next <= x) {
+            q = next;
+            k += 1;
+        }
+        acc.add(f64::from(k) * (p as f64).ln());
+        true
+    });
+    acc.value()
+}
+
+/**
+The prime zeta function `P(s)`, the sum of `p^-s` over all primes, cut off after the primes up to
+`x` and summed in an `f64`.
+
+For `s` above 1 the tail past `x` is roughly `x^(1-s) / ((s - 1) ln x)`; `P(2)` ≈ 0.452247.
+**/
+pub fn prime_zeta(s: f64, x: u64) -> f64 {
+    prime_zeta_with::<f64>(s, x)
+}
+
+/// The prime zeta function `P(s)` up to `x`, summed in the accumulator `A`.
+pub fn prime_zeta_with<A: Accumulator>(s: f64, x: u64) -> f64 {
+    let mut acc = A::default();
+    sieve_range(0..x.saturating_add(1), |p| {
+        acc.add((p as f64).powf(-s));
+        true
+    });
+    acc.value()
+}
+
+/**
+The partial Brun sum up to `x`: `1/p + 1/(p + 2)` summed over the twin primes `p`, `p + 2` up to
+`x`, in an `f64`.
+
+The full sum converges to Brun's constant, about 1.902, but so slowly that partial sums are still
+well short of it at any `x` within reach.
+**/
+pub fn brun_sum(x: u64) -> f64 {
+    brun_sum_with::<f64>(x)
+}
+
+/// The partial Brun sum up to `x`, summed in the accumulator `A`.
+pub fn brun_sum_with<A: Accumulator>(x: u64) -> f64 {
+    let mut acc = A::default();
+    let mut prev = 0;
+    sieve_range(0..x.saturating_add(1), |p| {
+        if prev != 0 && p == prev + 2 {
+            acc.add(1.0 / prev as f64);
+            acc.add(1.0 / p as f64);
+        }
+        prev = p;
+        true
+    });
+    acc.value()
+}
+
+/// The Euler–Mascheroni constant γ.
+const EULER_GAMMA: f64 = 0.577_215_664_901_532_9;
+
+/**
+The logarithmic integral li(x), the approximation to π(x) from the prime number theorem, or 0 for
+`x` below 2.
+
+li(x) overestimates π(x) everywhere it has been computed, by about `√x / ln x`: for `x = 10^9` it
+gives 50,849,234.9 against the true 50,847,534. It is evaluated by Ramanujan's series, which
+converges quickly for every `u64`.
+**/
+pub fn prime_pi_approx(x: u64) -> f64 {
+    if x < 2 {
+        return 0.0;
+    }
+    li(x as f64)
+}
+
+/**
+An approximation to the `n`th prime, counting 2 as the first: the `x` with li(x) = `n`.
+
+This is found by Newton's method from `n ln n`. It tends to be a little low, by 2% at the
+thousandth prime and 0.05% at the millionth. For guaranteed bounds, use `nth_prime_bounds`.
+
+Panics if `n` is zero.
+**/
+pub fn nth_prime_approx(n: u64) -> f64 {
+    assert!(n > 0, "primes are counted from 1");
+    let target = n as f64;
+    let mut x = (target * target.ln()).max(2.0);
+    for _ in 0..100 {
+        // li'(x) = 1 / ln x
+        let step = (li(x) - target) * x.ln();
+        x = (x - step).max(2.0);
+        if step.abs() <= x * 1e-15 {
+            break;
+        }
+    }
+    x
+}
+
+// Ramanujan's series for li(x), for x > 1.
+fn li(x: f64) -> f64 {
+    let l = x.ln();
+    let (mut sum, mut term, mut inner) = (0.0, 1.0, 0.0);
+    for k in 1..200 {
+        let n = f64::from(k);
+        // term = (-1)^(n-1) (ln x)^n / (n! 2^(n-1)), and inner = 1 + 1/3 + 1/5 + ... up to 1/n.
+        term *= if k == 1 { l } else { -l / (2.0 * n) };
+        if k % 2 == 1 {
+            inner += 1.0 / n;
+        }
+        let next = sum + term * inner;
+        if next == sum {
+            break;
+        }
+        sum = next;
+    }
+    EULER_GAMMA + l.ln() + x.sqrt() * sum
+}
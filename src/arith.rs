@@ -0,0 +1,372 @@
+//! Word-level modular arithmetic, shared by the rest of the crate and exposed for reuse.
+//!
+//! On 64-bit targets products are reduced through `u128`. Elsewhere (or with the `no-u128`
+//! feature), where 128-bit division is emulated and slow, a double-word schoolbook product is
+//! reduced one bit at a time instead.
+
+use std::convert::TryFrom;
+
+/// The greatest common divisor of `a` and `b`.
+pub(crate) fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// `a + b mod m`, for `a` and `b` already below `m`, without overflowing.
+pub(crate) fn add_mod(a: u64, b: u64, m: u64) -> u64 {
+    let (s, over) = a.overflowing_add(b);
+    if over || s >= m {
+        s.wrapping_sub(m)
+    } else {
+        s
+    }
+}
+
+/// `a^e mod m`, by repeated squaring. `m` must be nonzero.
+pub fn mod_pow(mut a: u64, mut e: u64, m: u64) -> u64 {
+    let mut r = 1 % m;
+    a %= m;
+    while e > 0 {
+        if e & 1 == 1 {
+            r = mul_mod(r, a, m);
+        }
+        a = mul_mod(a, a, m);
+        e >>= 1;
+    }
+    r
+}
+
+/// The inverse of `a` modulo `m`: the `x` in `0..m` with `a * x ≡ 1 (mod m)`, or `None` if `a`
+/// and `m` share a factor (or `m` is 0).
+pub fn mod_inv(a: u64, m: u64) -> Option<u64> {
+    if m == 0 {
+        return None;
+    }
+    // Extended Euclid, keeping only the coefficients of `a`.
+    let (mut r0, mut r1) = (i128::from(m), i128::from(a % m));
+    let (mut t0, mut t1) = (0i128, 1i128);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (t0, t1) = (t1, t0 - q * t1);
+    }
+    if r0 != 1 {
+        return None;
+    }
+    Some(t0.rem_euclid(i128::from(m)) as u64)
+}
+
+/// The greatest common divisor of `a` and `b`, for moduli past `u64` such as squares of primes.
+pub(crate) fn gcd_wide(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// `a + b mod m`, for `a` and `b` already below `m`, without overflowing.
+pub(crate) fn add_mod_wide(a: u128, b: u128, m: u128) -> u128 {
+    if a >= m - b {
+        a - (m - b)
+    } else {
+        a + b
+    }
+}
+
+/// `a * b mod m`, directly while the product fits in a `u128` and a bit at a time past that.
+pub(crate) fn mul_mod_wide(a: u128, b: u128, m: u128) -> u128 {
+    let (mut a, mut b) = (a % m, b % m);
+    if m <= 1 << 64 {
+        return a * b % m;
+    }
+    let mut r = 0;
+    while b > 0 {
+        if b & 1 == 1 {
+            r = add_mod_wide(r, a, m);
+        }
+        a = add_mod_wide(a, a, m);
+        b >>= 1;
+    }
+    r
+}
+
+/// `a^e mod m`, by repeated squaring with `mul_mod_wide`. `m` must be nonzero.
+pub(crate) fn pow_mod_wide(mut a: u128, mut e: u128, m: u128) -> u128 {
+    let mut r = 1 % m;
+    while e > 0 {
+        if e & 1 == 1 {
+            r = mul_mod_wide(r, a, m);
+        }
+        a = mul_mod_wide(a, a, m);
+        e >>= 1;
+    }
+    r
+}
+
+/// The primes that Miller–Rabin is run to beyond 64 bits: every composite below 3.3 × 10^24
+/// fails for one of them, and no larger one is known to pass them all.
+const WIDE_BASES: [u128; 13] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41];
+
+/// Whether `n` is prime: exactly up to 3.3 × 10^24, by Miller–Rabin to `WIDE_BASES`, and probably
+/// past that.
+pub(crate) fn is_prime_wide(n: u128) -> bool {
+    if let Ok(n) = u64::try_from(n) {
+        return is_prime_mr(n);
+    }
+    if WIDE_BASES.iter().any(|&b| n.is_multiple_of(b)) {
+        return false;
+    }
+    let d = (n - 1) >> (n - 1).trailing_zeros();
+    WIDE_BASES.iter().all(|&b| {
+        let mut x = pow_mod_wide(b, d, n);
+        let mut e = d;
+        if x == 1 {
+            return true;
+        }
+        while e != n - 1 {
+            if x == n - 1 {
+                return true;
+            }
+            x = mul_mod_wide(x, x, n);
+            e <<= 1;
+        }
+        false
+    })
+}
+
+/**
+The terms `U_n` and `V_n` of the Lucas sequences with parameters `p` and `q`, modulo `modulus`.
+
+The sequences start `U_0 = 0, U_1 = 1` and `V_0 = 2, V_1 = p`, and both follow
+`X_(k+1) = p X_k - q X_(k-1)`. With `p = 1, q = -1` they are the Fibonacci and Lucas numbers, and
+with `p = 2, q = -1` the Pell numbers and their companions, whose terms give the solutions of
+`x² - 2y² = ±1`. For a prime `n` not dividing `2q`, where `D = p² - 4q` has Jacobi symbol
+`(D/n) = -1`, `U_(n+1)` is divisible by `n`; that is the Lucas probable prime test.
+
+The pair `(U_k, U_(k+1))` is doubled once for each bit of `n`, so this takes about `log2(n)` steps,
+and nothing is ever divided by 2, so any modulus works, even or odd. `modulus` must be nonzero.
+
+```
+use primes::lucas_uv;
+
+// The 90th Fibonacci and Lucas numbers.
+let (f, l) = lucas_uv(1, -1, 90, u64::MAX);
+assert_eq!((f, l), (2_880_067_194_370_816_120, 6_440_026_026_380_244_498));
+// U_(n+1) ≡ 0 (mod n) for the prime 1_000_003, where 5 is not a square.
+assert_eq!(lucas_uv(1, -1, 1_000_004, 1_000_003).0, 0);
+```
+**/
+pub fn lucas_uv(p: i64, q: i64, n: u64, modulus: u64) -> (u64, u64) {
+    let m = modulus;
+    let p = i128::from(p).rem_euclid(i128::from(m)) as u64;
+    let q = i128::from(q).rem_euclid(i128::from(m)) as u64;
+    let sub = |a: u64, b: u64| add_mod(a, (m - b) % m, m);
+    // `(U_k, U_(k+1))`, for `k` the bits of `n` read so far.
+    let (mut u, mut u1) = (0, 1 % m);
+    for bit in (0..64 - n.leading_zeros()).rev() {
+        // U_2k = U_k (2 U_(k+1) - p U_k), and U_(2k+1) = U_(k+1)² - q U_k².
+        let v = sub(add_mod(u1, u1, m), mul_mod(p, u, m));
+        let u2 = mul_mod(u, v, m);
+        let u21 = sub(mul_mod(u1, u1, m), mul_mod(q, mul_mod(u, u, m), m));
+        (u, u1) = if n >> bit & 1 == 1 {
+            (u21, sub(mul_mod(p, u21, m), mul_mod(q, u2, m)))
+        } else {
+            (u2, u21)
+        };
+    }
+    // V_n = 2 U_(n+1) - p U_n.
+    (u, sub(add_mod(u1, u1, m), mul_mod(p, u, m)))
+}
+
+/**
+Whether `n` is prime, by a Miller–Rabin test to the prime bases up to 37, which has no false
+positives anywhere in the `u64` range.
+
+This is a `const fn` so that constants can be checked at compile time, which rules out `mul_mod`;
+products always go through `u128` here, even with the `no-u128` feature.
+**/
+pub(crate) const fn is_prime_mr(n: u64) -> bool {
+    const BASES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if n < 2 {
+        return false;
+    }
+    let mut i = 0;
+    while i < BASES.len() {
+        if n.is_multiple_of(BASES[i]) {
+            return n == BASES[i];
+        }
+        i += 1;
+    }
+
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    let mut i = 0;
+    while i < BASES.len() {
+        let mut x = const_pow_mod(BASES[i], d, n);
+        if x != 1 {
+            // Squaring has to reach -1 within `s - 1` steps; reaching 1 first means it never will.
+            let mut r = 1;
+            while x != n - 1 {
+                if r == s {
+                    return false;
+                }
+                x = const_mul_mod(x, x, n);
+                r += 1;
+            }
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn const_mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    (a as u128 * b as u128 % m as u128) as u64
+}
+
+const fn const_pow_mod(mut a: u64, mut e: u64, m: u64) -> u64 {
+    let mut r = 1;
+    while e > 0 {
+        if e & 1 == 1 {
+            r = const_mul_mod(r, a, m);
+        }
+        a = const_mul_mod(a, a, m);
+        e >>= 1;
+    }
+    r
+}
+
+/**
+Arithmetic modulo a fixed odd modulus, with the division in each product replaced by shifts.
+
+Numbers are first converted into Montgomery form with `to_montgomery`, multiplied and raised to
+powers as many times as needed, and converted back with `from_montgomery`. Sums and differences
+work in Montgomery form as usual. Set-up costs a few multiplications, so this pays off for long
+runs of operations modulo the same number, such as exponentiations.
+**/
+#[derive(Clone, Copy, Debug)]
+pub struct Montgomery {
+    m: u64,
+    // `-m^-1 mod 2^64`.
+    m_neg_inv: u64,
+    // `2^128 mod m`, to convert into Montgomery form.
+    r2: u64,
+}
+
+impl Montgomery {
+    /// A context for arithmetic modulo `m`.
+    ///
+    /// Panics if `m` is even.
+    pub fn new(m: u64) -> Montgomery {
+        assert!(m % 2 == 1, "Montgomery arithmetic needs an odd modulus");
+        // Each Newton step doubles the number of correct low bits, starting from 3.
+        let mut inv = m;
+        for _ in 0..5 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(m.wrapping_mul(inv)));
+        }
+        let r = (u64::MAX % m + 1) % m;
+        Montgomery {
+            m,
+            m_neg_inv: inv.wrapping_neg(),
+            r2: mul_mod(r, r, m),
+        }
+    }
+
+    /// The modulus.
+    pub fn modulus(&self) -> u64 {
+        self.m
+    }
+
+    /// `a` in Montgomery form.
+    pub fn to_montgomery(&self, a: u64) -> u64 {
+        self.mul(a % self.m, self.r2)
+    }
+
+    /// The ordinary value of `x`, which is in Montgomery form.
+    pub fn from_montgomery(&self, x: u64) -> u64 {
+        self.redc(0, x)
+    }
+
+    /// The product of `x` and `y`, both in Montgomery form, in Montgomery form.
+    pub fn mul(&self, x: u64, y: u64) -> u64 {
+        let (hi, lo) = mul_wide(x, y);
+        self.redc(hi, lo)
+    }
+
+    /// `x^e`, with `x` and the result in Montgomery form.
+    pub fn pow(&self, mut x: u64, mut e: u64) -> u64 {
+        let mut r = self.to_montgomery(1);
+        while e > 0 {
+            if e & 1 == 1 {
+                r = self.mul(r, x);
+            }
+            x = self.mul(x, x);
+            e >>= 1;
+        }
+        r
+    }
+
+    // Montgomery reduction: `(hi * 2^64 + lo) / 2^64 mod m`, for inputs below `m * 2^64`.
+    fn redc(&self, hi: u64, lo: u64) -> u64 {
+        let u = lo.wrapping_mul(self.m_neg_inv);
+        let (uh, ul) = mul_wide(u, self.m);
+        // `lo + ul` is 0 mod 2^64 by construction, so it only contributes a carry.
+        let carry = u64::from(lo.overflowing_add(ul).1);
+        let (t, over1) = hi.overflowing_add(uh);
+        let (t, over2) = t.overflowing_add(carry);
+        if over1 || over2 || t >= self.m {
+            t.wrapping_sub(self.m)
+        } else {
+            t
+        }
+    }
+}
+
+/// `a * b mod m`, without overflowing.
+#[cfg(all(target_pointer_width = "64", not(feature = "no-u128")))]
+pub(crate) fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    (u128::from(a) * u128::from(b) % u128::from(m)) as u64
+}
+
+/// `a * b mod m`, without overflowing.
+#[cfg(not(all(target_pointer_width = "64", not(feature = "no-u128"))))]
+pub(crate) fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    let (hi, lo) = mul_wide(a, b);
+
+    // Shift the low word into the reduced high word one bit at a time.
+    let mut r = hi % m;
+    for i in (0..64).rev() {
+        let carry = r >> 63;
+        r = (r << 1) | ((lo >> i) & 1);
+        if carry == 1 || r >= m {
+            r = r.wrapping_sub(m);
+        }
+    }
+    r
+}
+
+/// The full 128-bit product of `a` and `b`, as `(high, low)` words.
+#[cfg(all(target_pointer_width = "64", not(feature = "no-u128")))]
+fn mul_wide(a: u64, b: u64) -> (u64, u64) {
+    let p = u128::from(a) * u128::from(b);
+    ((p >> 64) as u64, p as u64)
+}
+
+/// The full 128-bit product of `a` and `b`, as `(high, low)` words.
+#[cfg(not(all(target_pointer_width = "64", not(feature = "no-u128"))))]
+fn mul_wide(a: u64, b: u64) -> (u64, u64) {
+    const MASK: u64 = 0xffff_ffff;
+    let (a1, a0) = (a >> 32, a & MASK);
+    let (b1, b0) = (b >> 32, b & MASK);
+
+    let p00 = a0 * b0;
+    let p01 = a0 * b1;
+    let p10 = a1 * b0;
+    let p11 = a1 * b1;
+
+    let mid = (p00 >> 32) + (p01 & MASK) + (p10 & MASK);
+    let lo = (mid << 32) | (p00 & MASK);
+    let hi = p11 + (p01 >> 32) + (p10 >> 32) + (mid >> 32);
+    (hi, lo)
+}
@@ -0,0 +1,156 @@
+use std::ops::Index;
+use std::slice::SliceIndex;
+
+use crate::{initial_primes, PrimeSetBasics, LARGEST_U64_PRIME};
+
+/**
+A prime generator, using the Sieve of Atkin over everything up to a fixed limit.
+
+Instead of striking off multiples, the Sieve of Atkin counts the solutions of three quadratic forms
+(`4x² + y²`, `3x² + y²` and `3x² - y²`, each for its own residues modulo 12): the numbers with an
+odd count are prime or have a square factor, and the squares of the primes found then clear the
+rest. That takes `O(n)` operations to reach `n`, against `O(n log log n)` for Eratosthenes.
+
+It works best given its limit up front, with `AtkinSieve::with_limit`, since it holds a bit for
+every number up to there while it sieves. Asking for primes past the limit starts over with a limit
+at least twice as high, so the work done over a run stays within a small multiple of a single sieve
+up to the end. Like the other backends it implements `PrimeSet`, so benchmarks can swap it in.
+**/
+#[derive(Clone)]
+pub struct AtkinSieve {
+    lst: Vec<u64>,
+    // Every prime up to here is in `lst`.
+    limit: u64,
+}
+
+impl AtkinSieve {
+    /// A new prime generator, primed with 2 and 3, or with the first 10,000 primes under the
+    /// `table` feature.
+    pub fn new() -> AtkinSieve {
+        let lst = initial_primes().to_vec();
+        let limit = *lst.last().unwrap();
+        AtkinSieve { lst, limit }
+    }
+
+    /// A prime generator that has already found every prime up to `limit`.
+    pub fn with_limit(limit: u64) -> AtkinSieve {
+        let mut pset = AtkinSieve::new();
+        pset.sieve_to(limit);
+        pset
+    }
+
+    /// How far the sieve has reached: every prime up to here has been found.
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    // Sieves everything up to `limit` afresh, replacing the primes found so far.
+    fn sieve_to(&mut self, limit: u64) {
+        let limit = limit.min(LARGEST_U64_PRIME);
+        if limit <= self.limit {
+            return;
+        }
+        let mut bits = vec![0u64; (limit / 64 + 1) as usize];
+        let flip = |bits: &mut [u64], n: u64| bits[(n / 64) as usize] ^= 1 << (n % 64);
+        let is_set = |bits: &[u64], n: u64| bits[(n / 64) as usize] & (1 << (n % 64)) != 0;
+
+        let mut x = 1;
+        while x * x <= limit {
+            let (x4, x3) = (4 * x * x, 3 * x * x);
+            let mut y = 1;
+            while x4 + y * y <= limit {
+                let n = x4 + y * y;
+                if n % 12 == 1 || n % 12 == 5 {
+                    flip(&mut bits, n);
+                }
+                y += 1;
+            }
+            let mut y = 1;
+            while x3 + y * y <= limit {
+                let n = x3 + y * y;
+                if n % 12 == 7 {
+                    flip(&mut bits, n);
+                }
+                y += 1;
+            }
+            // `3x² - y²` for `y` from `x - 1` down, so that it grows, and stops at the limit.
+            let mut y = x - 1;
+            while y >= 1 && x3 - y * y <= limit {
+                let n = x3 - y * y;
+                if n % 12 == 11 {
+                    flip(&mut bits, n);
+                }
+                y -= 1;
+            }
+            x += 1;
+        }
+
+        // The odd counts also catch numbers with a square factor; strike out multiples of p².
+        let mut r = 5;
+        while r * r <= limit {
+            if is_set(&bits, r) {
+                let sq = r * r;
+                let mut m = sq;
+                while m <= limit {
+                    bits[(m / 64) as usize] &= !(1 << (m % 64));
+                    m += sq;
+                }
+            }
+            r += 2;
+        }
+
+        self.lst.clear();
+        self.lst.extend([2, 3].iter().filter(|&&p| p <= limit));
+        self.lst.extend((5..=limit).step_by(2).filter(|&n| is_set(&bits, n)));
+        self.limit = limit;
+    }
+}
+
+impl Default for AtkinSieve {
+    fn default() -> AtkinSieve {
+        AtkinSieve::new()
+    }
+}
+
+impl PrimeSetBasics for AtkinSieve {
+    /// Sieves again, to at least twice the limit, until at least one more prime is found.
+    ///
+    /// Panics if the largest prime that fits in a `u64` has already been found.
+    fn expand(&mut self) {
+        let len = self.lst.len();
+        while self.lst.len() == len {
+            assert!(
+                self.limit < LARGEST_U64_PRIME,
+                "no primes above the largest prime that fits in a u64"
+            );
+            self.sieve_to(self.limit.saturating_mul(2).max(1 << 16));
+        }
+    }
+
+    /// Returns all primes found so far as a slice.
+    fn list(&self) -> &[u64] {
+        &self.lst[..]
+    }
+
+    /// Sieves again, once, to a little past `n` (or twice the limit, if further), so that a prime
+    /// at or above `n` is found.
+    fn expand_to(&mut self, n: u64) {
+        let n = n.min(LARGEST_U64_PRIME);
+        if self.lst.last().is_some_and(|&p| p >= n) {
+            return;
+        }
+        // Past `n` by more than any gap between primes that a bit vector this size could reach.
+        self.sieve_to(n.saturating_add(n / 16 + 1024).max(self.limit.saturating_mul(2)));
+        while self.lst.last().is_none_or(|&p| p < n) {
+            self.expand();
+        }
+    }
+}
+
+// Indexes the primes found so far, by position (`pset[3]`) or by range (`pset[..10]`).
+impl<I: SliceIndex<[u64]>> Index<I> for AtkinSieve {
+    type Output = I::Output;
+    fn index(&self, index: I) -> &I::Output {
+        &self.lst[index]
+    }
+}
@@ -0,0 +1,152 @@
+//! Products of many primes or moduli, reduced or combined without materializing them in full.
+
+use std::ops::Range;
+
+use crate::arith::mul_mod;
+use crate::range::sieve_range;
+
+#[cfg(feature = "bigint")]
+use num_bigint::{BigInt, BigUint, Sign};
+#[cfg(feature = "bigint")]
+use num_integer::Integer;
+#[cfg(feature = "bigint")]
+use num_rational::Ratio;
+
+/// The product of all primes up to and including `bound` (the primorial `bound#`), modulo `m`.
+pub fn primorial_mod(bound: u64, m: u64) -> u64 {
+    product_of_primes_in_mod(0..bound.saturating_add(1), m)
+}
+
+/// The product of all primes in `range`, modulo `m`.
+pub fn product_of_primes_in_mod(range: Range<u64>, m: u64) -> u64 {
+    assert!(m != 0, "modulus must be nonzero");
+    let mut acc = 1 % m;
+    sieve_range(range, |p| {
+        acc = mul_mod(acc, p % m, m);
+        // Once the product is 0, it stays 0.
+        acc != 0
+    });
+    acc
+}
+
+/**
+For each value, the gcd of that value with the product of all the others.
+
+Any result other than 1 means the value shares a factor with another value in the list, which is
+how RSA moduli generated with the same prime are found in bulk. This uses Bernstein's product and
+remainder trees, so it is quasi-linear in the total size of the input rather than quadratic.
+
+A 0 shares every factor: its result is the product of the others (0 if there is another 0), and
+every other value's result is the value itself.
+**/
+#[cfg(feature = "bigint")]
+pub fn batch_gcd(values: &[BigUint]) -> Vec<BigUint> {
+    if values.is_empty() {
+        return Vec::new();
+    }
+    let zero = BigUint::ZERO;
+    let zeros = values.iter().filter(|&x| *x == zero).count();
+    if zeros > 0 {
+        let others: BigUint = values.iter().filter(|&x| *x != zero).product();
+        return values
+            .iter()
+            .map(|x| match (*x == zero, zeros) {
+                (false, _) => x.clone(),
+                (true, 1) => others.clone(),
+                (true, _) => BigUint::ZERO,
+            })
+            .collect();
+    }
+
+    // Reduce the full product modulo the square of each value.
+    let tree = product_tree(values);
+    let squares: Vec<Vec<BigUint>> = tree
+        .iter()
+        .map(|level| level.iter().map(|x| x * x).collect())
+        .collect();
+    let rems = remainder_tree(tree.last().unwrap()[0].clone(), &squares);
+
+    rems.iter()
+        .zip(values)
+        .map(|(r, x)| (r / x).gcd(x))
+        .collect()
+}
+
+/**
+The product tree of `values`: the values themselves, then the products of adjacent pairs, and so on
+up to a single root holding the product of everything.
+
+Returns the levels from the leaves up. An odd node out at any level is carried up unchanged.
+**/
+#[cfg(feature = "bigint")]
+pub fn product_tree(values: &[BigUint]) -> Vec<Vec<BigUint>> {
+    let mut tree = vec![values.to_vec()];
+    while tree.last().unwrap().len() > 1 {
+        let next = tree
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| pair.iter().product())
+            .collect();
+        tree.push(next);
+    }
+    tree
+}
+
+/**
+`n` modulo every leaf of a product tree, as built by `product_tree`.
+
+This reduces `n` by the root and then by each level on the way down, so every step works with
+numbers about the size of the modulus rather than the size of `n`.
+**/
+#[cfg(feature = "bigint")]
+pub fn remainder_tree(n: BigUint, tree: &[Vec<BigUint>]) -> Vec<BigUint> {
+    let mut rems = vec![n];
+    for level in tree.iter().rev() {
+        rems = level
+            .iter()
+            .enumerate()
+            .map(|(i, x)| &rems[i / 2] % x)
+            .collect();
+    }
+    rems
+}
+
+/**
+The exact value of the Euler product of `factor(p)` over every prime `p` up to and including
+`bound`, where each factor is given as a `(numerator, denominator)` pair.
+
+For instance `|p| (p - 1, p)` gives `Π (1 - 1/p)`. The numerators and denominators are each
+multiplied up with a product tree, and the fraction is reduced once at the end, so this stays
+quick even when the result runs to many thousands of digits.
+
+Panics if any denominator is zero.
+**/
+#[cfg(feature = "bigint")]
+pub fn euler_product_exact<F, N, D>(bound: u64, mut factor: F) -> Ratio<BigInt>
+where
+    F: FnMut(u64) -> (N, D),
+    N: Into<BigInt>,
+    D: Into<BigInt>,
+{
+    let (mut nums, mut dens) = (Vec::new(), Vec::new());
+    let mut negative = false;
+    sieve_range(0..bound.saturating_add(1), |p| {
+        let (num, den) = factor(p);
+        let (num, den) = (num.into(), den.into());
+        negative ^= (num.sign() == Sign::Minus) ^ (den.sign() == Sign::Minus);
+        nums.push(num.magnitude().clone());
+        dens.push(den.magnitude().clone());
+        true
+    });
+
+    let product = |values: &[BigUint]| match product_tree(values).last() {
+        Some(root) if !values.is_empty() => root[0].clone(),
+        _ => BigUint::from(1u32),
+    };
+    let sign = if negative { Sign::Minus } else { Sign::Plus };
+    Ratio::new(
+        BigInt::from_biguint(sign, product(&nums)),
+        BigInt::from(product(&dens)),
+    )
+}
@@ -0,0 +1,167 @@
+//! The `primes` command: lists, counts and checks primes, and factors numbers.
+
+use std::env;
+use std::io::{self, BufWriter, Write};
+use std::process;
+
+use primes::{fill_primes, is_prime_const, prime_pi, Factorizer, PrimePredicate};
+
+const USAGE: &str = "\
+usage: primes list --below N [--from M]   print the primes from M (default 0) up to N
+            [--filter F]...               only those that are all of: twin, safe, sophie-germain,
+                                          palindromic, or AmodQ (A mod Q)
+       primes factor N...                 print the prime factors of each N
+       primes check N...                  say whether each N is prime
+       primes pi N                        count the primes up to N
+
+Numbers can be written with underscores or in scientific notation, as in 1_000_000 or 1e9.";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("list") => list(&args[1..]),
+        Some("factor") => each_number(&args[1..], factor),
+        Some("check") => each_number(&args[1..], check),
+        Some("pi") => pi(&args[1..]),
+        Some("-h") | Some("--help") | Some("help") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(Failure::Usage(String::from("expected a subcommand"))),
+    };
+    match result {
+        Ok(()) => {}
+        // Whoever was reading, such as `head`, has had enough.
+        Err(Failure::Io(e)) if e.kind() == io::ErrorKind::BrokenPipe => {}
+        Err(Failure::Io(e)) => {
+            eprintln!("primes: {}", e);
+            process::exit(1);
+        }
+        Err(Failure::Usage(message)) => {
+            eprintln!("primes: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    }
+}
+
+enum Failure {
+    Usage(String),
+    Io(io::Error),
+}
+
+impl From<io::Error> for Failure {
+    fn from(e: io::Error) -> Failure {
+        Failure::Io(e)
+    }
+}
+
+// A number from the command line: digits, optionally with underscores, or `<digits>e<digits>`.
+fn parse_number(s: &str) -> Result<u64, Failure> {
+    let bad = || Failure::Usage(format!("not a number from 0 to 2^64 - 1: {}", s));
+    let digits: String = s.chars().filter(|&c| c != '_').collect();
+    match digits.split_once(['e', 'E']) {
+        None => digits.parse().map_err(|_| bad()),
+        Some((mantissa, exponent)) => {
+            let mantissa: u64 = mantissa.parse().map_err(|_| bad())?;
+            let exponent: u32 = exponent.parse().map_err(|_| bad())?;
+            10u64
+                .checked_pow(exponent)
+                .and_then(|scale| mantissa.checked_mul(scale))
+                .ok_or_else(bad)
+        }
+    }
+}
+
+// Streams the primes in the range a bufferful at a time, so output starts straight away and stops
+// as soon as the reader goes away.
+fn list(args: &[String]) -> Result<(), Failure> {
+    let (mut from, mut below, mut filter) = (0, None, None);
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or_else(|| Failure::Usage(format!("{} needs a value", flag)));
+        match flag.as_str() {
+            "--below" => below = Some(parse_number(value?)?),
+            "--from" => from = parse_number(value?)?,
+            "--filter" => {
+                let pred = parse_predicate(value?)?;
+                filter = Some(match filter {
+                    Some(f) => pred.and(f),
+                    None => pred,
+                });
+            }
+            _ => return Err(Failure::Usage(format!("unknown option {}", flag))),
+        }
+    }
+    let below = below.ok_or_else(|| Failure::Usage(String::from("list needs --below")))?;
+
+    let mut out = BufWriter::new(io::stdout().lock());
+    let mut buf = vec![0; 1 << 16];
+    while from < below {
+        let (count, next) = fill_primes(from..below, &mut buf);
+        for &p in &buf[..count] {
+            if filter.as_ref().is_none_or(|f: &PrimePredicate| f.matches(p)) {
+                writeln!(out, "{}", p)?;
+            }
+        }
+        from = next;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+// A property for `list --filter`, by its name or as `AmodQ`.
+fn parse_predicate(s: &str) -> Result<PrimePredicate, Failure> {
+    let bad = || Failure::Usage(format!("unknown filter {}", s));
+    Ok(match s {
+        "twin" => PrimePredicate::Twin,
+        "safe" => PrimePredicate::SafePrime,
+        "sophie-germain" => PrimePredicate::SophieGermain,
+        "palindromic" => PrimePredicate::Palindromic,
+        _ => {
+            let (a, q) = s.split_once("mod").ok_or_else(bad)?;
+            let (a, q) = (parse_number(a)?, parse_number(q)?);
+            if q == 0 {
+                return Err(bad());
+            }
+            PrimePredicate::Mod(a, q)
+        }
+    })
+}
+
+fn each_number(args: &[String], f: fn(u64) -> String) -> Result<(), Failure> {
+    if args.is_empty() {
+        return Err(Failure::Usage(String::from("expected at least one number")));
+    }
+    let mut out = io::stdout().lock();
+    for arg in args {
+        writeln!(out, "{}", f(parse_number(arg)?))?;
+    }
+    Ok(())
+}
+
+// In the same format as coreutils' `factor`.
+fn factor(n: u64) -> String {
+    let found = Factorizer::complete().factor(n);
+    let mut line = format!("{}:", n);
+    for p in found.primes {
+        line += &format!(" {}", p);
+    }
+    line
+}
+
+fn check(n: u64) -> String {
+    if is_prime_const(n) {
+        format!("{} is prime", n)
+    } else {
+        format!("{} is not prime", n)
+    }
+}
+
+fn pi(args: &[String]) -> Result<(), Failure> {
+    let n = match args {
+        [n] => parse_number(n)?,
+        _ => return Err(Failure::Usage(String::from("pi takes one number"))),
+    };
+    writeln!(io::stdout().lock(), "{}", prime_pi(n))?;
+    Ok(())
+}
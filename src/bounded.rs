@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+
+use crate::arith::is_prime_mr;
+use crate::range::sieve_odd;
+use crate::{PrimeSet, PrimeSetBasics, Sieve, LARGEST_U64_PRIME};
+
+/// Width of the blocks of numbers that are sieved, cached and evicted as a unit.
+const BLOCK: u64 = 1 << 20;
+
+/**
+A prime generator that keeps at most a fixed amount of memory of primes, for long-running
+processes where a cache that only ever grows isn't an option.
+
+The numbers are sieved in blocks of about a million. Up to `max_bytes` worth of those blocks stay
+cached; when a new one is needed the least recently used ones are dropped, and sieved again if
+they're ever wanted back. The count of primes in each block is kept for good, so indexes stay
+exact after eviction, and so are the small primes needed to sieve the blocks, up to the square
+root of the furthest one. Neither is counted against `max_bytes`: together they take about eight
+bytes per million numbers covered, plus the small primes themselves.
+
+The most recently used block is always kept, however small `max_bytes` is.
+
+```
+use primes::BoundedPrimeSet;
+
+let mut pset = BoundedPrimeSet::new(1 << 20);
+assert_eq!(pset.get(1_000_000), 15_485_867);
+assert!(pset.memory_used() <= 1 << 20);
+assert_eq!(pset.find(15_485_864), (1_000_000, 15_485_867));
+assert_eq!(pset.get(0), 2);
+```
+**/
+#[derive(Clone)]
+pub struct BoundedPrimeSet {
+    max_bytes: usize,
+    // The odd primes needed to sieve every block found so far.
+    head: Sieve,
+    // The number of primes below the start of each block found so far, plus one past the last.
+    counts: Vec<usize>,
+    // The cached blocks by number, with the time each was last used.
+    resident: HashMap<usize, (Vec<u64>, u64)>,
+    used: usize,
+    clock: u64,
+}
+
+pub struct BoundedIter<'a> {
+    set: &'a mut BoundedPrimeSet,
+    n: usize,
+}
+
+impl BoundedPrimeSet {
+    /// A new prime generator that caches at most `max_bytes` of primes.
+    pub fn new(max_bytes: usize) -> BoundedPrimeSet {
+        BoundedPrimeSet {
+            max_bytes,
+            head: Sieve::new(),
+            counts: vec![0],
+            resident: HashMap::new(),
+            used: 0,
+            clock: 0,
+        }
+    }
+
+    /// The cap on cached primes, in bytes.
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// The bytes of primes cached right now.
+    pub fn memory_used(&self) -> usize {
+        self.used
+    }
+
+    /// Number of primes found so far, including those that have been evicted.
+    pub fn len(&self) -> usize {
+        *self.counts.last().unwrap()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the nth prime, sieving (or sieving again) whatever it takes.
+    pub fn get(&mut self, index: usize) -> u64 {
+        while index >= self.len() {
+            self.explore();
+        }
+        // The last block that starts at or before the prime.
+        let b = self.counts.partition_point(|&c| c <= index) - 1;
+        let first = self.counts[b];
+        self.block(b)[index - first]
+    }
+
+    /// Find the next largest prime from a number.
+    ///
+    /// Returns `(idx, prime)`. Panics if `n` is past `LARGEST_U64_PRIME`.
+    pub fn find(&mut self, n: u64) -> (usize, u64) {
+        assert!(n <= LARGEST_U64_PRIME, "no prime at or above {} fits in a u64", n);
+        let mut b = (n / BLOCK) as usize;
+        loop {
+            while b + 1 >= self.counts.len() {
+                self.explore();
+            }
+            let first = self.counts[b];
+            let block = self.block(b);
+            let i = block.partition_point(|&p| p < n);
+            if let Some(&p) = block.get(i) {
+                return (first + i, p);
+            }
+            b += 1;
+        }
+    }
+
+    /// Whether `n` is prime. Numbers in blocks that have been found are looked up, sieving the
+    /// block again if it was evicted; others are tested by Miller–Rabin, which doesn't need any
+    /// more blocks found.
+    pub fn is_prime(&mut self, n: u64) -> bool {
+        let b = (n / BLOCK) as usize;
+        if b + 1 < self.counts.len() {
+            self.block(b).binary_search(&n).is_ok()
+        } else {
+            is_prime_mr(n)
+        }
+    }
+
+    /// Iterator over all primes, starting with 2.
+    pub fn iter(&mut self) -> BoundedIter<'_> {
+        BoundedIter { set: self, n: 0 }
+    }
+
+    // Sieves the block after the last one found, and counts its primes.
+    fn explore(&mut self) {
+        let b = self.counts.len() - 1;
+        let count = self.block(b).len();
+        self.counts.push(self.counts[b] + count);
+    }
+
+    // The primes in block `b`, which has to be found already or be the next one, from the cache or
+    // sieved afresh.
+    fn block(&mut self, b: usize) -> &[u64] {
+        self.clock += 1;
+        if !self.resident.contains_key(&b) {
+            let primes = self.sieve(b);
+            self.used += primes.len() * 8;
+            self.resident.insert(b, (primes, 0));
+            self.evict(b);
+        }
+        let (primes, used) = self.resident.get_mut(&b).unwrap();
+        *used = self.clock;
+        primes
+    }
+
+    // Drops the least recently used blocks other than `keep` until the cache is under its cap.
+    fn evict(&mut self, keep: usize) {
+        while self.used > self.max_bytes && self.resident.len() > 1 {
+            let (&oldest, _) = self
+                .resident
+                .iter()
+                .filter(|&(&b, _)| b != keep)
+                .min_by_key(|&(_, &(_, used))| used)
+                .unwrap();
+            let (primes, _) = self.resident.remove(&oldest).unwrap();
+            self.used -= primes.len() * 8;
+        }
+    }
+
+    fn sieve(&mut self, b: usize) -> Vec<u64> {
+        let lo = b as u64 * BLOCK;
+        let hi = lo.saturating_add(BLOCK);
+        self.head.find((hi - 1).isqrt());
+
+        let mut primes = if lo == 0 { vec![2] } else { Vec::new() };
+        sieve_odd(&self.head.list()[1..], lo..hi, |p| {
+            primes.push(p);
+            true
+        });
+        primes.shrink_to_fit();
+        primes
+    }
+}
+
+impl<'a> Iterator for BoundedIter<'a> {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        self.n += 1;
+        Some(self.set.get(self.n - 1))
+    }
+}
@@ -0,0 +1,126 @@
+//! Proven bounds on prime counts, for when an exact count is too expensive.
+
+use std::sync::OnceLock;
+
+use crate::range::sieve_range;
+use crate::{is_prime, prime_pi};
+
+/// Below this, counting exactly is cheap, and above it Dusart's upper bound holds.
+const EXACT_BELOW: u64 = 355_991;
+
+/// Below this index, finding the prime exactly is cheap, and above it Dusart's upper bound holds.
+const EXACT_NTH_BELOW: u64 = 39_017;
+
+/// Everything below `EXACT_NTH_BELOW` primes lies below this.
+const EXACT_NTH_RANGE: u64 = 470_000;
+
+// The primes below `EXACT_NTH_RANGE`, which answer both kinds of bound exactly below the cutoffs.
+// They are sieved on first use and kept.
+fn small_primes() -> &'static [u32] {
+    static SMALL: OnceLock<Vec<u32>> = OnceLock::new();
+    SMALL.get_or_init(|| {
+        let mut found = Vec::new();
+        sieve_range(0..EXACT_NTH_RANGE, |p| {
+            found.push(p as u32);
+            true
+        });
+        found
+    })
+}
+
+/**
+Proven lower and upper bounds on π(x), the number of primes less than or equal to `x`.
+
+Above a few hundred thousand this uses Dusart's inequalities
+
+```text
+x/ln x (1 + 1/ln x + 2/ln² x) ≤ π(x) ≤ x/ln x (1 + 1/ln x + 2.51/ln² x)
+```
+
+widened slightly to cover floating-point error; below that, the bounds are both the exact count.
+Either way this does no more than a small fixed amount of work.
+**/
+pub fn pi_bounds(x: u64) -> (u64, u64) {
+    if x < EXACT_BELOW {
+        let count = small_primes().partition_point(|&p| u64::from(p) <= x) as u64;
+        return (count, count);
+    }
+
+    let xf = x as f64;
+    let l = xf.ln();
+    let base = xf / l * (1.0 + 1.0 / l);
+    let lower = base + 2.0 * xf / (l * l * l);
+    let upper = base + 2.51 * xf / (l * l * l);
+    (widen_down(lower), widen_up(upper))
+}
+
+/**
+Proven lower and upper bounds on p_k, the `k`th prime, counting 2 as the first.
+
+Above the first few tens of thousands of primes this uses Dusart's inequalities
+
+```text
+k (ln k + ln ln k - 1) ≤ p_k ≤ k (ln k + ln ln k - 0.9484)
+```
+
+widened slightly to cover floating-point error; below that, the bounds are both the exact prime.
+Either way this does no more than a small fixed amount of work.
+
+Panics if `k` is zero.
+**/
+pub fn nth_prime_bounds(k: u64) -> (u64, u64) {
+    assert!(k > 0, "primes are counted from 1");
+    if k < EXACT_NTH_BELOW {
+        let p = u64::from(small_primes()[k as usize - 1]);
+        return (p, p);
+    }
+
+    let kf = k as f64;
+    let l = kf.ln() + kf.ln().ln();
+    (widen_down(kf * (l - 1.0)), widen_up(kf * (l - 0.9484)))
+}
+
+/**
+Checks that `p` is the `k`th prime, counting 2 as the first.
+
+Claims that fall outside `nth_prime_bounds(k)`, or whose `π(p)` bounds leave out `k`, are
+rejected without counting anything, as are composite `p`. Only a claim that passes all of that is
+confirmed by counting the primes below `p`, so checking a wrong table is usually cheap.
+
+That count starts from the exact value of π at the lower bound from `nth_prime_bounds(k)`, found
+by `prime_pi`, and only the window from there up to `p` is sieved.
+**/
+pub fn verify_nth_prime(k: u64, p: u64) -> bool {
+    if k == 0 {
+        return false;
+    }
+    let (start, end) = nth_prime_bounds(k);
+    if p < start || p > end {
+        return false;
+    }
+    let (lo, hi) = pi_bounds(p);
+    if k < lo || k > hi || !is_prime(p) {
+        return false;
+    }
+    if lo == hi || start == end {
+        return true;
+    }
+
+    // The primes below `start` are all before the `k`th, so only the ones from there to `p` are
+    // left to count.
+    let mut count = prime_pi(start - 1);
+    sieve_range(start..p, |_| {
+        count += 1;
+        count < k
+    });
+    count == k - 1
+}
+
+// Rounds a bound that was computed in floating point outwards, by more than its rounding error.
+pub(crate) fn widen_down(v: f64) -> u64 {
+    (v * (1.0 - 1e-12) - 1.0).floor() as u64
+}
+
+pub(crate) fn widen_up(v: f64) -> u64 {
+    (v * (1.0 + 1e-12) + 1.0).ceil() as u64
+}
@@ -0,0 +1,99 @@
+use std::cell::RefCell;
+
+use crate::shared::{find_uncached, next_capped, nth_uncached, CACHE_LIMIT, CACHE_PRIMES};
+use crate::{PrimeSet, TrialDivision};
+
+/**
+A prime set that can be queried, and grown, through a shared reference.
+
+`PrimeSet::iter()` needs `&mut self` because iterating may have to find more primes. This wraps a
+prime set in a `RefCell` instead, so that read-heavy code can pass `&CachedPrimes` around freely
+and still expand the cache on demand. The cell is only borrowed for the duration of each call, so
+several iterators can be alive at once. With the `tiny` feature the cache stops growing at 2^16,
+as `SharedPrimeSet`'s does.
+
+This isn't `Sync`; see `SharedPrimeSet` for use across threads.
+**/
+#[derive(Default, Clone)]
+pub struct CachedPrimes<P = TrialDivision> {
+    set: RefCell<P>,
+}
+
+pub struct CachedIter<'a, P> {
+    cache: &'a CachedPrimes<P>,
+    n: usize,
+    last: u64,
+}
+
+impl CachedPrimes {
+    /// A new cache, backed by a new `TrialDivision`.
+    pub fn new() -> CachedPrimes {
+        CachedPrimes::default()
+    }
+}
+
+impl<P: PrimeSet> CachedPrimes<P> {
+    /// Number of primes found so far.
+    pub fn len(&self) -> usize {
+        self.set.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.set.borrow().is_empty()
+    }
+
+    /// Get the nth prime, even if we haven't found it yet.
+    pub fn get(&self, index: usize) -> u64 {
+        if let Some(p) = self.set.borrow().get_found(index) {
+            return p;
+        }
+        if CACHE_PRIMES.is_some_and(|cap| index >= cap) {
+            return nth_uncached(index);
+        }
+        self.set.borrow_mut().get(index)
+    }
+
+    /// Find the next largest prime from a number.
+    ///
+    /// Returns `(idx, prime)`
+    pub fn find(&self, n: u64) -> (usize, u64) {
+        if let Some(found) = self.set.borrow().find_vec(n) {
+            return found;
+        }
+        if CACHE_LIMIT.is_some_and(|limit| n >= limit) {
+            return find_uncached(n);
+        }
+        self.set.borrow_mut().find(n)
+    }
+
+    /// Iterator over all primes, starting with 2.
+    pub fn iter(&self) -> CachedIter<'_, P> {
+        CachedIter {
+            cache: self,
+            n: 0,
+            last: 0,
+        }
+    }
+
+    /// Unwraps the underlying prime set.
+    pub fn into_inner(self) -> P {
+        self.set.into_inner()
+    }
+}
+
+impl<P: PrimeSet> From<P> for CachedPrimes<P> {
+    fn from(set: P) -> CachedPrimes<P> {
+        CachedPrimes {
+            set: RefCell::new(set),
+        }
+    }
+}
+
+impl<'a, P: PrimeSet> Iterator for CachedIter<'a, P> {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        self.last = next_capped(self.n, self.last, |n| self.cache.get(n))?;
+        self.n += 1;
+        Some(self.last)
+    }
+}
@@ -0,0 +1,64 @@
+//! Primes for sizing hash tables, Bloom filters and the like.
+
+use crate::arith::is_prime_mr;
+use crate::LARGEST_U64_PRIME;
+
+/**
+A ladder of primes for table capacities: for each `k` from 2 to 62, the smallest prime above
+`1.5 × 2^k`.
+
+Each is about twice the one before, so growing a table along the ladder keeps the amortized cost of
+rehashing constant, and each sits about as far from the neighbouring powers of two as it can, which
+keeps hash functions whose low bits are weak from clustering. The ladder is worked out by the
+compiler, and starts 7, 13, 29, 53, 97, 193, 389.
+**/
+pub const CAPACITY_PRIMES: [u64; 61] = capacity_ladder();
+
+const fn capacity_ladder() -> [u64; 61] {
+    let mut ladder = [0; 61];
+    let mut k = 0;
+    while k < ladder.len() {
+        let mut n = (3 << (k + 2)) / 2 + 1;
+        while !is_prime_mr(n) {
+            n += 1;
+        }
+        ladder[k] = n;
+        k += 1;
+    }
+    ladder
+}
+
+/// The smallest prime that is at least `n`, or `None` if that is past `LARGEST_U64_PRIME`.
+pub fn next_prime_at_least(n: u64) -> Option<u64> {
+    if n > LARGEST_U64_PRIME {
+        return None;
+    }
+    if n <= 2 {
+        return Some(2);
+    }
+    // Only odd candidates; the largest prime bounds the search, so this can't overflow.
+    (n | 1..=LARGEST_U64_PRIME).step_by(2).find(|&c| is_prime_mr(c))
+}
+
+/// The largest prime that is at most `n`, or `None` if `n` is below 2.
+pub fn prev_prime_at_most(n: u64) -> Option<u64> {
+    match n {
+        0 | 1 => None,
+        2 => Some(2),
+        _ => (3..=(n - 1) | 1).rev().step_by(2).find(|&c| is_prime_mr(c)).or(Some(2)),
+    }
+}
+
+/**
+A prime capacity for a table that should hold `n` entries: the first prime on the
+`CAPACITY_PRIMES` ladder that is at least `n`, or past the top of the ladder, the next prime.
+
+Returns `None` if `n` is past `LARGEST_U64_PRIME`. Allow for the load factor first: a table kept at
+most three-quarters full needs a capacity for `n * 4 / 3` entries.
+**/
+pub fn prime_capacity_for(n: u64) -> Option<u64> {
+    match CAPACITY_PRIMES.iter().find(|&&p| p >= n) {
+        Some(&p) => Some(p),
+        None => next_prime_at_least(n),
+    }
+}
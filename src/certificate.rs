@@ -0,0 +1,110 @@
+//! Pratt certificates: short proofs of primality that anyone can check.
+
+use crate::arith::{is_prime_mr, mod_pow};
+use crate::divisors::prime_powers;
+
+/**
+A proof that a number is prime, in the form Pratt showed always exists.
+
+`p` is prime exactly when some witness `a` has order `p - 1` modulo `p`, which is the case when
+`a^(p-1) ≡ 1` but `a^((p-1)/q) ≢ 1` for every prime `q` dividing `p - 1`. The certificate records
+`a` and the factorization of `p - 1`, with a certificate of its own for each of those primes, down
+to 2. Checking it with `verify` takes a few modular powers per prime in the tree and trusts nothing
+else, in particular not the test that produced it.
+**/
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PrattCertificate {
+    prime: u64,
+    witness: u64,
+    // `(exponent, certificate)` for each prime factor of `prime - 1`.
+    factors: Vec<(u32, PrattCertificate)>,
+}
+
+/**
+A Pratt certificate for `p`, or `None` if `p` isn't prime.
+
+Building one factors `p - 1`, and in turn `q - 1` for each prime `q` found, as
+`Factorizer::complete` does: large factors are split off with Pollard's rho, so this is quick for
+every `u64`, even when `p - 1` is a product of two primes near `2^32`.
+**/
+pub fn prime_certificate(p: u64) -> Option<PrattCertificate> {
+    if !is_prime_mr(p) {
+        return None;
+    }
+    if p == 2 {
+        return Some(PrattCertificate {
+            prime: 2,
+            witness: 1,
+            factors: Vec::new(),
+        });
+    }
+    let factors = prime_powers(p - 1);
+    // Some witness is below `p`, since `p` has primitive roots; the smallest is usually tiny.
+    let witness = (2..p)
+        .find(|&a| factors.iter().all(|&(q, _)| mod_pow(a, (p - 1) / q, p) != 1))
+        .unwrap();
+    let factors = factors
+        .into_iter()
+        .map(|(q, e)| (e, prime_certificate(q).unwrap()))
+        .collect();
+    Some(PrattCertificate {
+        prime: p,
+        witness,
+        factors,
+    })
+}
+
+impl PrattCertificate {
+    /// A certificate put together from its parts, such as one read back from storage. Nothing is
+    /// checked until `verify`.
+    pub fn from_parts(
+        prime: u64,
+        witness: u64,
+        factors: Vec<(u32, PrattCertificate)>,
+    ) -> PrattCertificate {
+        PrattCertificate {
+            prime,
+            witness,
+            factors,
+        }
+    }
+
+    /// The prime this certifies.
+    pub fn prime(&self) -> u64 {
+        self.prime
+    }
+
+    /// The number whose order modulo `prime()` is `prime() - 1`.
+    pub fn witness(&self) -> u64 {
+        self.witness
+    }
+
+    /// The prime factors of `prime() - 1`, each as its exponent and its own certificate.
+    pub fn factors(&self) -> &[(u32, PrattCertificate)] {
+        &self.factors
+    }
+
+    /// Checks the whole certificate, so that `true` proves `prime()` is prime.
+    pub fn verify(&self) -> bool {
+        let p = self.prime;
+        if p == 2 {
+            return self.factors.is_empty();
+        }
+        if p < 2 {
+            return false;
+        }
+        // Every factor has to be a proven prime, and together they have to make up `p - 1`.
+        let mut product = 1u64;
+        for (e, cert) in &self.factors {
+            let q = cert.prime;
+            if *e == 0 || !cert.verify() || mod_pow(self.witness, (p - 1) / q, p) == 1 {
+                return false;
+            }
+            match q.checked_pow(*e).and_then(|qe| product.checked_mul(qe)) {
+                Some(next) => product = next,
+                None => return false,
+            }
+        }
+        product == p - 1 && mod_pow(self.witness, p - 1, p) == 1
+    }
+}
@@ -0,0 +1,124 @@
+//! Cunningham chains: runs of primes in which each is twice the one before, plus or minus one.
+
+use std::vec;
+
+use crate::arith::is_prime_mr;
+use crate::range::sieve_range;
+use crate::PrimePredicate;
+
+/// Odd numbers sieved at a time while looking for the starts of chains.
+const BLOCK: u64 = 1 << 20;
+
+/// Which way a Cunningham chain steps from one prime to the next.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CunninghamKind {
+    /// Each prime is `2p + 1`: every prime but the last is a Sophie Germain prime, and every
+    /// prime but the first is a safe prime.
+    First,
+    /// Each prime is `2p - 1`.
+    Second,
+}
+
+impl CunninghamKind {
+    // The prime after `p` in a chain, if it is one and fits in a `u64`.
+    fn step(self, p: u64) -> Option<u64> {
+        match self {
+            CunninghamKind::First if PrimePredicate::SophieGermain.matches(p) => Some(2 * p + 1),
+            CunninghamKind::Second => p.checked_mul(2).map(|q| q - 1).filter(|&q| is_prime_mr(q)),
+            _ => None,
+        }
+    }
+}
+
+/**
+Every Cunningham chain of `kind` with at least `min_length` primes, in ascending order of its first
+prime.
+
+Each chain is complete: it starts at a prime that doesn't follow on from any other, and runs until
+the next number isn't prime (or wouldn't fit in a `u64`). The primes are sieved a million odd
+numbers at a time, so a search takes the same memory however far it goes; the numbers each prime
+would follow on from or lead to are tested by Miller–Rabin, as `PrimePredicate` does.
+
+```
+use primes::{cunningham_chains, CunninghamKind};
+
+let mut long = cunningham_chains(CunninghamKind::First, 6);
+assert_eq!(long.next(), Some(vec![89, 179, 359, 719, 1439, 2879]));
+
+let below: Vec<_> = cunningham_chains(CunninghamKind::Second, 4)
+    .take_while(|chain| chain[0] < 2000)
+    .collect();
+assert_eq!(below, vec![vec![1531, 3061, 6121, 12241, 24481]]);
+```
+**/
+pub fn cunningham_chains(kind: CunninghamKind, min_length: usize) -> CunninghamChains {
+    CunninghamChains {
+        kind,
+        min_length,
+        next: Some(0),
+        block: Vec::new().into_iter(),
+    }
+}
+
+/// The iterator returned by `cunningham_chains`.
+#[derive(Clone, Debug)]
+pub struct CunninghamChains {
+    kind: CunninghamKind,
+    min_length: usize,
+    // Where the next block starts, or `None` once the last block has been sieved.
+    next: Option<u64>,
+    block: vec::IntoIter<u64>,
+}
+
+impl CunninghamChains {
+    // Whether the prime `p` continues a chain rather than starting one.
+    fn follows_on(&self, p: u64) -> bool {
+        match self.kind {
+            CunninghamKind::First => PrimePredicate::SafePrime.matches(p),
+            // `p = 2q - 1` for the prime `q = (p + 1) / 2`; 3 follows 2.
+            CunninghamKind::Second => p % 2 == 1 && PrimePredicate::SafePrime.matches(p + 2),
+        }
+    }
+
+    // Sieves the next block of primes, returning false if there are none left to sieve.
+    fn refill(&mut self) -> bool {
+        let lo = match self.next {
+            Some(lo) => lo,
+            None => return false,
+        };
+        let hi = lo.checked_add(2 * BLOCK);
+        self.next = hi;
+
+        let mut primes = Vec::new();
+        sieve_range(lo..hi.unwrap_or(u64::MAX), |p| {
+            primes.push(p);
+            true
+        });
+        self.block = primes.into_iter();
+        true
+    }
+}
+
+impl Iterator for CunninghamChains {
+    type Item = Vec<u64>;
+
+    fn next(&mut self) -> Option<Vec<u64>> {
+        loop {
+            let p = match self.block.next() {
+                Some(p) => p,
+                None if self.refill() => continue,
+                None => return None,
+            };
+            if self.follows_on(p) {
+                continue;
+            }
+            let mut chain = vec![p];
+            while let Some(q) = self.kind.step(*chain.last().unwrap()) {
+                chain.push(q);
+            }
+            if chain.len() >= self.min_length {
+                return Some(chain);
+            }
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use std::cell::{Cell, OnceCell};
+use std::ops::Index;
+use std::slice::SliceIndex;
+
+use crate::range::sieve_range;
+use crate::{PrimeSetBasics, LARGEST_U64_PRIME};
+
+/// Number of primes between two fully-stored anchors.
+const BLOCK: usize = 64;
+
+/**
+A prime generator that stores the primes it has found as gap deltas instead of full `u64`s.
+
+Every odd prime is kept as half the gap to the previous one in a single byte, with a full value
+stored every 64 primes so that lookups only have to decode a short run. This uses a little over
+one byte per prime, compared to eight for the `Vec<u64>` behind `TrialDivision`.
+
+Finding, getting, iterating over and testing primes through `PrimeSet` all work on the compressed
+form; walking the primes in order decodes each one from the one before. `expand_to` sieves the
+whole stretch in one go and compresses the primes as they turn up.
+
+Only the methods that hand out a slice of the primes, which are `list`, `slice`, `view`,
+`iter_vec`, `iter_found`, `find_many` and indexing, can't work that way. The first call to any of
+them decodes every prime found so far into a `Vec<u64>`, which is then kept up to date alongside
+the compressed form, at the usual eight bytes per prime. Use `iter_decoded` to read through the
+primes found so far without that.
+**/
+#[derive(Clone)]
+pub struct CompressedPrimeSet {
+    // Every `BLOCK`-th odd prime, starting with 3.
+    anchors: Vec<u64>,
+    // Half the gap from the previous odd prime, one entry per odd prime. A zero marks a gap too
+    // wide to fit in a byte, which is then looked up in `wide`.
+    gaps: Vec<u8>,
+    wide: Vec<(usize, u16)>,
+    last: u64,
+    // The odd primes up to the square root of `last`, decoded once for `expand` to divide by.
+    small: Vec<u64>,
+    // Every prime found, once `list()` has been asked for.
+    decoded: OnceCell<Vec<u64>>,
+    // The last position `get_found` was asked for and the prime there, so that asking for the
+    // next one only decodes one gap.
+    cursor: Cell<(usize, u64)>,
+}
+
+pub struct CompressedIter<'a> {
+    set: &'a CompressedPrimeSet,
+    n: usize,
+    cur: u64,
+}
+
+impl CompressedPrimeSet {
+    /// A new prime generator, primed with 2 and 3.
+    pub fn new() -> CompressedPrimeSet {
+        CompressedPrimeSet {
+            anchors: vec![3],
+            gaps: vec![0],
+            wide: Vec::new(),
+            last: 3,
+            small: vec![3],
+            decoded: OnceCell::new(),
+            cursor: Cell::new((0, 2)),
+        }
+    }
+
+    /// Iterator over just the primes found so far, decoded one at a time, without building the
+    /// list that `list()` returns.
+    pub fn iter_decoded(&self) -> CompressedIter<'_> {
+        CompressedIter {
+            set: self,
+            n: 0,
+            cur: 0,
+        }
+    }
+
+    // The `k`th odd prime, given the one before it.
+    fn decode(&self, k: usize, prev: u64) -> u64 {
+        if k.is_multiple_of(BLOCK) {
+            self.anchors[k / BLOCK]
+        } else {
+            prev + 2 * self.half_gap(k)
+        }
+    }
+
+    fn half_gap(&self, k: usize) -> u64 {
+        match self.gaps[k] {
+            0 => {
+                let i = self.wide.binary_search_by_key(&k, |&(k, _)| k).unwrap();
+                u64::from(self.wide[i].1)
+            }
+            g => u64::from(g),
+        }
+    }
+
+    // Decodes odd primes onto `small` until it holds every one up to the square root of `n`.
+    fn decode_small(&mut self, n: u64) {
+        while self.small.len() < self.gaps.len() {
+            let p = self.decode(self.small.len(), *self.small.last().unwrap());
+            if p > n / p {
+                break;
+            }
+            self.small.push(p);
+        }
+    }
+
+    fn push(&mut self, p: u64) {
+        let k = self.gaps.len();
+        let half = (p - self.last) / 2;
+        if k.is_multiple_of(BLOCK) {
+            self.anchors.push(p);
+            self.gaps.push(0);
+        } else if half <= u64::from(u8::MAX) {
+            self.gaps.push(half as u8);
+        } else {
+            self.gaps.push(0);
+            self.wide.push((k, half as u16));
+        }
+        self.last = p;
+        if let Some(list) = self.decoded.get_mut() {
+            list.push(p);
+        }
+    }
+}
+
+impl PrimeSetBasics for CompressedPrimeSet {
+    fn expand(&mut self) {
+        let next = |l: u64| {
+            l.checked_add(2).expect("no primes above the largest prime that fits in a u64")
+        };
+        let mut l = next(self.last);
+        loop {
+            self.decode_small(l);
+            if !self.small.iter().take_while(|&&p| p <= l / p).any(|&p| l.is_multiple_of(p)) {
+                break;
+            }
+            l = next(l);
+        }
+        self.push(l);
+    }
+
+    fn list(&self) -> &[u64] {
+        self.decoded.get_or_init(|| self.iter_decoded().collect())
+    }
+
+    fn expand_to(&mut self, n: u64) {
+        let n = n.min(LARGEST_U64_PRIME);
+        if n > self.last {
+            sieve_range(self.last + 1..n + 1, |p| {
+                self.push(p);
+                true
+            });
+        }
+        while self.last < n {
+            self.expand();
+        }
+    }
+
+    fn found_len(&self) -> usize {
+        self.gaps.len() + 1
+    }
+
+    fn last_found(&self) -> Option<u64> {
+        Some(self.last)
+    }
+
+    fn get_found(&self, index: usize) -> Option<u64> {
+        if index == 0 {
+            return Some(2);
+        }
+        let j = index - 1;
+        if j >= self.gaps.len() {
+            return None;
+        }
+        let p = match self.cursor.get() {
+            (i, p) if i == index => p,
+            (i, p) if i + 1 == index => self.decode(j, p),
+            _ => {
+                let mut p = self.anchors[j / BLOCK];
+                for k in (j - j % BLOCK + 1)..=j {
+                    p = self.decode(k, p);
+                }
+                p
+            }
+        };
+        self.cursor.set((index, p));
+        Some(p)
+    }
+
+    fn find_found(&self, n: u64) -> Option<(usize, u64)> {
+        if n > self.last {
+            return None;
+        }
+        if n <= 2 {
+            return Some((0, 2));
+        }
+
+        // Binary search over the anchors, then decode forward from the one below `n`.
+        let block = match self.anchors.binary_search(&n) {
+            Ok(b) => return Some((b * BLOCK + 1, n)),
+            Err(b) => b - 1,
+        };
+        let mut p = self.anchors[block];
+        for k in (block * BLOCK + 1)..self.gaps.len() {
+            p = self.decode(k, p);
+            if p >= n {
+                return Some((k + 1, p));
+            }
+        }
+        unreachable!("n is at most the last prime found");
+    }
+}
+
+// Indexing borrows from `list()`, so the first use decodes every prime found so far.
+impl<I: SliceIndex<[u64]>> Index<I> for CompressedPrimeSet {
+    type Output = I::Output;
+    fn index(&self, index: I) -> &I::Output {
+        &self.list()[index]
+    }
+}
+
+impl Default for CompressedPrimeSet {
+    fn default() -> CompressedPrimeSet {
+        CompressedPrimeSet::new()
+    }
+}
+
+impl<'a> Iterator for CompressedIter<'a> {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        let n = self.n;
+        if n >= self.set.found_len() {
+            return None;
+        }
+        self.n += 1;
+        self.cur = match n {
+            0 => 2,
+            _ => self.set.decode(n - 1, self.cur),
+        };
+        Some(self.cur)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.set.found_len() - self.n;
+        (left, Some(left))
+    }
+}
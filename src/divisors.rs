@@ -0,0 +1,209 @@
+//! Divisors and divisor functions, worked out from the prime factorization.
+
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+
+use crate::arith::is_prime_mr;
+use crate::{factor_iter, wheel_next, Factorizer};
+
+/// The prime factorization of `n` as `(prime, exponent)` pairs, in ascending order of prime.
+pub(crate) fn prime_powers(n: u64) -> Vec<(u64, u32)> {
+    let mut lst: Vec<(u64, u32)> = Vec::new();
+    for p in Factorizer::complete().factor(n).primes {
+        match lst.last_mut() {
+            Some((q, e)) if *q == p => *e += 1,
+            _ => lst.push((p, 1)),
+        }
+    }
+    lst
+}
+
+/// All divisors of `n`, in ascending order, including 1 and `n` itself. 0 is given none.
+pub fn divisors(n: u64) -> Vec<u64> {
+    if n == 0 {
+        return Vec::new();
+    }
+    let mut lst = vec![1];
+    for (p, e) in prime_powers(n) {
+        let len = lst.len();
+        let mut pk = 1;
+        for _ in 0..e {
+            pk *= p;
+            for i in 0..len {
+                lst.push(lst[i] * pk);
+            }
+        }
+    }
+    lst.sort_unstable();
+    lst
+}
+
+/// The number of divisors of `n`, d(n). 0 is given none.
+pub fn num_divisors(n: u64) -> u64 {
+    if n == 0 {
+        return 0;
+    }
+    prime_powers(n).iter().map(|&(_, e)| u64::from(e) + 1).product()
+}
+
+/**
+Whether `n` is squarefree: whether no prime divides it twice. 0 isn't, and 1 is.
+
+Trial division stops at the first repeated factor, and otherwise at the cube root of what is left,
+since past that the rest has at most two prime factors and is only squared if it's a square. So it
+never gets anywhere near the square root of a squarefree `n`.
+**/
+pub fn is_squarefree(n: u64) -> bool {
+    if n == 0 {
+        return false;
+    }
+    let mut n = n;
+    let mut d = 2;
+    while d <= n / d / d {
+        if n.is_multiple_of(d) {
+            n /= d;
+            if n.is_multiple_of(d) {
+                return false;
+            }
+        }
+        d = if d == 2 { 3 } else { wheel_next(d) };
+    }
+    let r = n.isqrt();
+    n == 1 || r * r != n
+}
+
+/**
+Whether `n` is a semiprime: the product of exactly two primes, which may be the same one.
+
+Like `is_squarefree`, trial division only goes up to the cube root of `n`. A factor found by then
+leaves a cofactor that is tested for primality; otherwise `n` has at most two prime factors, and is
+a semiprime unless it's prime (or 1).
+**/
+pub fn is_semiprime(n: u64) -> bool {
+    if n < 4 {
+        return false;
+    }
+    let mut d = 2;
+    while d <= n / d / d {
+        if n.is_multiple_of(d) {
+            return is_prime_mr(n / d);
+        }
+        d = if d == 2 { 3 } else { wheel_next(d) };
+    }
+    !is_prime_mr(n)
+}
+
+/// The radical of `n`: the product of its distinct prime factors, so 1 for 1 and 30 for 360. 0 is
+/// given 0.
+pub fn radical(n: u64) -> u64 {
+    if n == 0 {
+        return 0;
+    }
+    let mut rad = 1u64;
+    for p in factor_iter(n) {
+        if !rad.is_multiple_of(p) {
+            rad *= p;
+        }
+    }
+    rad
+}
+
+/**
+The divisor function σ_k(n): the sum of the `k`th powers of the divisors of `n`.
+
+`sigma(n, 1)` is the plain sum of divisors, and `sigma(n, 0)` is the number of divisors. Returns
+`None` if the sum overflows a `u64`, which for `k` above 1 happens quite early. 0 has no divisors,
+so the sum for it is 0.
+**/
+pub fn sigma(n: u64, k: u32) -> Option<u64> {
+    if n == 0 {
+        return Some(0);
+    }
+    prime_powers(n).iter().try_fold(1u64, |acc, &(p, e)| {
+        // 1 + p^k + p^2k + ... + p^ek
+        let pk = p.checked_pow(k)?;
+        let (mut sum, mut term) = (1u64, 1u64);
+        for _ in 0..e {
+            term = term.checked_mul(pk)?;
+            sum = sum.checked_add(term)?;
+        }
+        acc.checked_mul(sum)
+    })
+}
+
+// σ(n), which is less than 2^128 for every `u64` `n`, so never overflows.
+fn sigma_wide(n: u64) -> u128 {
+    if n == 0 {
+        return 0;
+    }
+    prime_powers(n).iter().fold(1u128, |acc, &(p, e)| {
+        let (mut sum, mut term) = (1u128, 1u128);
+        for _ in 0..e {
+            term *= u128::from(p);
+            sum += term;
+        }
+        acc * sum
+    })
+}
+
+/// The sum of the proper divisors of `n` (every divisor but `n` itself), or `None` if it overflows
+/// a `u64`.
+pub fn aliquot_sum(n: u64) -> Option<u64> {
+    u64::try_from(sigma_wide(n) - u128::from(n)).ok()
+}
+
+/// Where a number's proper divisors sum to, relative to the number itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Abundance {
+    /// The proper divisors sum to less than the number, as for every prime.
+    Deficient,
+    /// The proper divisors sum to exactly the number, as for 6 and 28.
+    Perfect,
+    /// The proper divisors sum to more than the number, as for 12.
+    Abundant,
+}
+
+/// Classifies `n` as deficient, perfect or abundant.
+///
+/// Panics if `n` is zero.
+pub fn abundance(n: u64) -> Abundance {
+    assert!(n > 0, "only positive numbers are classified");
+    match (sigma_wide(n) - u128::from(n)).cmp(&u128::from(n)) {
+        Ordering::Less => Abundance::Deficient,
+        Ordering::Equal => Abundance::Perfect,
+        Ordering::Greater => Abundance::Abundant,
+    }
+}
+
+pub struct AliquotSequence {
+    next: Option<u64>,
+    steps: usize,
+}
+
+/**
+Iterator over the aliquot sequence starting at `n`: `n`, then the aliquot sum of that, and so on.
+
+At most `max_steps` terms follow `n`. The sequence also ends after reaching 0 (so primes end with
+1, 0), or if a term's aliquot sum overflows a `u64`. Perfect numbers and other cycles repeat
+until `max_steps` runs out.
+**/
+pub fn aliquot_sequence(n: u64, max_steps: usize) -> AliquotSequence {
+    AliquotSequence {
+        next: Some(n),
+        steps: max_steps,
+    }
+}
+
+impl Iterator for AliquotSequence {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        let n = self.next?;
+        self.next = if n == 0 || self.steps == 0 {
+            None
+        } else {
+            self.steps -= 1;
+            aliquot_sum(n)
+        };
+        Some(n)
+    }
+}
@@ -0,0 +1,390 @@
+//! Factoring methods beyond trial division, for composites whose factors are all too large for it.
+
+use std::time::{Duration, Instant};
+
+use crate::arith::{add_mod, gcd, is_prime_mr, mod_pow, mul_mod};
+use crate::range::sieve_range;
+use crate::{quadratic_sieve, wheel_next, Progress};
+
+/// Multipliers for SQUFOF: products of the small odd primes, tried in turn until one works.
+const SQUFOF_MULTIPLIERS: [u64; 16] = [
+    1, 3, 5, 7, 11, 15, 21, 33, 35, 55, 77, 105, 165, 231, 385, 1155,
+];
+
+/**
+A nontrivial factor of `n` by Fermat's method, or `None` if `n` is prime or below 4.
+
+Fermat's method looks for `n = a² - b² = (a - b)(a + b)`, trying `a` upwards from `√n`, so it finds
+the factor pair closest to `√n` after about `(b - a)² / (8√n)` steps. That is almost immediate
+when `n` has two factors of similar size, as badly chosen RSA moduli do, and hopeless when it
+doesn't; for those, use `squfof`. Even `n` gives 2 straight away.
+**/
+pub fn fermat_factor(n: u64) -> Option<u64> {
+    if n < 4 || is_prime_mr(n) {
+        return None;
+    }
+    if n.is_multiple_of(2) {
+        return Some(2);
+    }
+    let mut a = n.isqrt();
+    if a * a < n {
+        a += 1;
+    }
+    // `a² - n`, kept up to date as `a` grows so that nothing overflows.
+    let mut d = u128::from(a) * u128::from(a) - u128::from(n);
+    loop {
+        let b = d.isqrt();
+        if b * b == d {
+            return Some(a - b as u64);
+        }
+        d += 2 * u128::from(a) + 1;
+        a += 1;
+    }
+}
+
+/**
+A nontrivial factor of `n` by Shanks's square forms factorization (SQUFOF), or `None` if `n` is
+prime or below 4, or in the rare case that every multiplier fails.
+
+SQUFOF walks the continued fraction of `√(kn)` for a small multiplier `k` until it finds a square
+form, which takes about `n^(1/4)` steps whatever the sizes of the factors. With numbers that never
+grow past `2√(kn)`, that makes it the quickest simple method for composites of 40 to 60 bits.
+**/
+pub fn squfof(n: u64) -> Option<u64> {
+    if n < 4 || is_prime_mr(n) {
+        return None;
+    }
+    if n.is_multiple_of(2) {
+        return Some(2);
+    }
+    let s = n.isqrt();
+    if s * s == n {
+        return Some(s);
+    }
+    SQUFOF_MULTIPLIERS.iter().find_map(|&k| {
+        let g = gcd(n, k);
+        if g > 1 {
+            return Some(g);
+        }
+        squfof_with(n, k)
+    })
+}
+
+// One attempt at SQUFOF with the multiplier `k`. Everything is signed, since the differences of
+// consecutive `P` in the recurrences go negative.
+fn squfof_with(n: u64, k: u64) -> Option<u64> {
+    let d = i128::from(n) * i128::from(k);
+    let p0 = (d as u128).isqrt() as i128;
+    if p0 * p0 == d {
+        return None;
+    }
+    let bound = 3 * 2 * (2 * p0 as u128).isqrt() as i128;
+
+    // Forward: find a square form at an even step.
+    let (mut p, mut p_prev) = (p0, p0);
+    let (mut q, mut q_prev) = (d - p0 * p0, 1);
+    let mut r = 0;
+    let mut i = 2;
+    while i < bound {
+        let b = (p0 + p) / q;
+        p = b * q - p;
+        let q_old = q;
+        q = q_prev + b * (p_prev - p);
+        r = (q as u128).isqrt() as i128;
+        if i % 2 == 0 && r * r == q {
+            break;
+        }
+        q_prev = q_old;
+        p_prev = p;
+        i += 1;
+    }
+    if i >= bound {
+        return None;
+    }
+
+    // Backward: from the square root of that form, walk until `P` repeats.
+    let b = (p0 - p) / r;
+    p += b * r;
+    p_prev = p;
+    q_prev = r;
+    q = (d - p_prev * p_prev) / q_prev;
+    for _ in 0..bound {
+        let b = (p0 + p) / q;
+        p_prev = p;
+        p = b * q - p;
+        let q_old = q;
+        q = q_prev + b * (p_prev - p);
+        q_prev = q_old;
+        if p == p_prev {
+            break;
+        }
+    }
+    let f = gcd(n, q_prev as u64);
+    if f != 1 && f != n {
+        Some(f)
+    } else {
+        None
+    }
+}
+
+/// One stage of a `Factorizer`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Stage {
+    TrialDivision(u64),
+    Rho(u64),
+    PMinusOne(u64),
+    Squfof,
+    QuadraticSieve,
+}
+
+/**
+A factoring pipeline, built up from the methods to try and the order to try them in.
+
+Each cofactor that isn't prime goes through the stages in order: trial division strips off the
+factors up to its bound, and each of the other methods tries to split it in two, with both halves
+going back through the pipeline from the start. A cofactor that gets through every stage without
+being split is given up on, as is everything left when the time limit runs out, so `factor` always
+returns, possibly with composites left over.
+
+```
+use primes::Factorizer;
+use std::time::Duration;
+
+let factorizer = Factorizer::new()
+    .trial_division(1_000)
+    .rho(100_000)
+    .squfof()
+    .time_limit(Duration::from_secs(1));
+let result = factorizer.factor(600_851_475_143);
+assert_eq!(result.primes, vec![71, 839, 1471, 6857]);
+assert!(result.is_complete());
+```
+**/
+#[derive(Clone, Debug, Default)]
+pub struct Factorizer {
+    stages: Vec<Stage>,
+    time_limit: Option<Duration>,
+}
+
+/// What a `Factorizer` found: the prime factors, and any composite cofactors it gave up on.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Factorization {
+    /// The prime factors found, in ascending order and including repeats.
+    pub primes: Vec<u64>,
+    /// Composite cofactors that no stage managed to split, in ascending order. Multiplied with
+    /// `primes`, they make up the original number.
+    pub composites: Vec<u64>,
+}
+
+impl Factorization {
+    /// Whether the factorization is complete, with no composites left over.
+    pub fn is_complete(&self) -> bool {
+        self.composites.is_empty()
+    }
+}
+
+impl Factorizer {
+    /// An empty pipeline, which only sorts out numbers that are already prime.
+    pub fn new() -> Factorizer {
+        Factorizer::default()
+    }
+
+    /// A pipeline that always factors completely: trial division up to 2^10, then rho with no cap
+    /// on its iterations, which splits every composite eventually.
+    pub fn complete() -> Factorizer {
+        Factorizer::new().trial_division(1 << 10).rho(u64::MAX)
+    }
+
+    /// Adds a stage of trial division by everything up to `bound`.
+    pub fn trial_division(mut self, bound: u64) -> Factorizer {
+        self.stages.push(Stage::TrialDivision(bound));
+        self
+    }
+
+    /// Adds a stage of Pollard's rho method (Brent's variant), giving up after `iterations` steps.
+    /// Finds factors up to about the square root of `iterations` quickly, whatever the cofactor.
+    pub fn rho(mut self, iterations: u64) -> Factorizer {
+        self.stages.push(Stage::Rho(iterations));
+        self
+    }
+
+    /// Adds a stage of Pollard's `p - 1` method, which finds the prime factors `p` for which
+    /// every prime power dividing `p - 1` is at most `bound`.
+    pub fn p_minus_one(mut self, bound: u64) -> Factorizer {
+        self.stages.push(Stage::PMinusOne(bound));
+        self
+    }
+
+    /// Adds a stage of SQUFOF; see `squfof`.
+    pub fn squfof(mut self) -> Factorizer {
+        self.stages.push(Stage::Squfof);
+        self
+    }
+
+    /// Adds a stage of the quadratic sieve; see `quadratic_sieve`. It splits any composite of 50
+    /// bits or more with no small factors quicker than the other methods.
+    pub fn quadratic_sieve(mut self) -> Factorizer {
+        self.stages.push(Stage::QuadraticSieve);
+        self
+    }
+
+    /// Gives up on whatever is left once `limit` has passed. The limit is checked between stages
+    /// and every so often within rho, so it can be overrun by about one stage.
+    pub fn time_limit(mut self, limit: Duration) -> Factorizer {
+        self.time_limit = Some(limit);
+        self
+    }
+
+    /// Factors `n` as far as the pipeline gets. Both 0 and 1 have no factors.
+    pub fn factor(&self, n: u64) -> Factorization {
+        self.factor_with_progress(n, &mut Progress::default())
+    }
+
+    /// Like `factor`, but reporting to `progress` at the same points the time limit is checked,
+    /// with the share of the bits of `n` that have been split off as primes so far. Being told to
+    /// stop works like running out of time: whatever is left is given up on.
+    pub fn factor_with_progress(&self, n: u64, progress: &mut Progress<'_>) -> Factorization {
+        let deadline = self.time_limit.map(|limit| Instant::now() + limit);
+        let bits = (n.max(2) as f64).ln();
+        let mut done = 0.0;
+        let mut expired = |done: f64| {
+            deadline.is_some_and(|d| Instant::now() >= d) || !progress.report(done / bits)
+        };
+        let mut result = Factorization::default();
+        let mut queue = if n > 1 { vec![n] } else { Vec::new() };
+
+        'next: while let Some(mut c) = queue.pop() {
+            for &stage in &self.stages {
+                if c == 1 {
+                    continue 'next;
+                }
+                if is_prime_mr(c) {
+                    break;
+                }
+                if expired(done) {
+                    result.composites.push(c);
+                    continue 'next;
+                }
+                let split = match stage {
+                    Stage::TrialDivision(bound) => {
+                        let found = result.primes.len();
+                        c = strip_small_factors(c, bound, &mut result.primes);
+                        let logs = result.primes[found..].iter().map(|&p| (p as f64).ln());
+                        done += logs.sum::<f64>();
+                        None
+                    }
+                    Stage::Rho(iterations) => rho(c, iterations, &mut || expired(done)),
+                    Stage::PMinusOne(bound) => p_minus_one(c, bound),
+                    Stage::Squfof => squfof(c),
+                    Stage::QuadraticSieve => quadratic_sieve(u128::from(c)).map(|f| f as u64),
+                };
+                if let Some(f) = split {
+                    queue.push(f);
+                    queue.push(c / f);
+                    continue 'next;
+                }
+            }
+            if c == 1 {
+                continue;
+            }
+            if is_prime_mr(c) {
+                result.primes.push(c);
+                done += (c as f64).ln();
+            } else {
+                result.composites.push(c);
+            }
+        }
+        result.primes.sort_unstable();
+        result.composites.sort_unstable();
+        result
+    }
+}
+
+// Divides out every factor of `n` up to `bound`, pushing each onto `found`; returns what's left.
+fn strip_small_factors(mut n: u64, bound: u64, found: &mut Vec<u64>) -> u64 {
+    let mut d = 2;
+    while d <= bound && d <= n / d {
+        while n.is_multiple_of(d) {
+            found.push(d);
+            n /= d;
+        }
+        d = if d == 2 { 3 } else { wheel_next(d) };
+    }
+    // Whatever is left has no factors up to its square root, so it's prime if the bound reached it.
+    if n > 1 && n <= bound {
+        found.push(n);
+        return 1;
+    }
+    n
+}
+
+// Pollard's rho with Brent's cycle finding, multiplying differences together so that only one gcd
+// is taken per batch, and `expired` is checked. `n` has to be odd and composite.
+fn rho(n: u64, iterations: u64, expired: &mut dyn FnMut() -> bool) -> Option<u64> {
+    const BATCH: u64 = 128;
+    if n.is_multiple_of(2) {
+        return Some(2);
+    }
+    let mut spent = 0;
+    for c in 1..n {
+        let f = |x: u64| add_mod(mul_mod(x, x, n), c, n);
+        let (mut x, mut y, mut ys) = (2, 2, 2);
+        let mut acc = 1;
+        let mut g = 1;
+        let mut r = 1;
+        while g == 1 {
+            x = y;
+            for _ in 0..r {
+                y = f(y);
+            }
+            let mut k = 0;
+            while k < r && g == 1 {
+                ys = y;
+                for _ in 0..BATCH.min(r - k) {
+                    y = f(y);
+                    acc = mul_mod(acc, x.abs_diff(y), n);
+                }
+                g = gcd(acc, n);
+                k += BATCH;
+                spent += BATCH;
+                if spent >= iterations || expired() {
+                    return None;
+                }
+            }
+            r *= 2;
+        }
+        if g == n {
+            // The batch overshot; step through it one at a time.
+            loop {
+                ys = f(ys);
+                g = gcd(x.abs_diff(ys), n);
+                if g > 1 {
+                    break;
+                }
+            }
+        }
+        if g != n {
+            return Some(g);
+        }
+    }
+    None
+}
+
+// Stage one of Pollard's p - 1: raises 2 to every prime power up to `bound`, then takes a gcd.
+fn p_minus_one(n: u64, bound: u64) -> Option<u64> {
+    let mut a = 2 % n;
+    sieve_range(0..bound.saturating_add(1), |q| {
+        let mut qe = q;
+        while let Some(next) = qe.checked_mul(q).filter(|&next| next <= bound) {
+            qe = next;
+        }
+        a = mod_pow(a, qe, n);
+        true
+    });
+    let g = gcd(if a == 0 { n - 1 } else { a - 1 }, n);
+    if g > 1 && g < n {
+        Some(g)
+    } else {
+        None
+    }
+}
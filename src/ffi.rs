@@ -0,0 +1,49 @@
+/*!
+A C interface to the core functions, declared in `include/primes.h`.
+
+Build a shared library with `cargo rustc --release --features ffi --crate-type cdylib` (or
+`staticlib`), and link against it from C or C++, or load it with Python's `ctypes`. Every function
+is exact over the whole `uint64_t` range, and none of them allocates memory that the caller has to
+free.
+*/
+
+use std::slice;
+
+use crate::arith::is_prime_mr;
+use crate::{next_prime_at_least, Factorizer};
+
+/// Whether `n` is prime.
+#[no_mangle]
+pub extern "C" fn primes_is_prime(n: u64) -> bool {
+    is_prime_mr(n)
+}
+
+/// The smallest prime greater than `n`, or 0 if there is none that fits in a `uint64_t`.
+#[no_mangle]
+pub extern "C" fn primes_next_prime(n: u64) -> u64 {
+    n.checked_add(1).and_then(next_prime_at_least).unwrap_or(0)
+}
+
+/**
+Writes the prime factors of `n`, in ascending order and with repeats, to `out`, and returns how many
+there are.
+
+At most `len` factors are written; if the return value is larger, the rest were left out, and a
+buffer of 64 is always enough. Both 0 and 1 have no factors.
+
+# Safety
+
+`out` has to point to `len` writable `uint64_t`s, unless `len` is 0, when it may be null.
+**/
+#[no_mangle]
+pub unsafe extern "C" fn primes_factorize(n: u64, out: *mut u64, len: usize) -> usize {
+    let primes = Factorizer::complete().factor(n).primes;
+    if len > 0 {
+        // SAFETY: the caller promises `len` writable words at `out`.
+        let out = unsafe { slice::from_raw_parts_mut(out, len) };
+        for (slot, &p) in out.iter_mut().zip(&primes) {
+            *slot = p;
+        }
+    }
+    primes.len()
+}
@@ -0,0 +1,155 @@
+//! Arithmetic in the integers modulo a prime.
+
+use std::fmt;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use crate::arith::{add_mod, mul_mod};
+use crate::{mod_pow, PrimeModulus};
+
+/**
+An element of the field of integers modulo a prime, `Z/pZ`.
+
+Each element carries its `PrimeModulus`, so the modulus is only checked to be prime once, when it
+is made, and the operators can't be handed a composite one. Sums, differences, products and
+quotients all stay reduced; combining elements of two different fields panics.
+
+```
+use primes::{Fp, PrimeModulus};
+
+let p = PrimeModulus::from_prime(13);
+let (a, b) = (Fp::new(5, p), Fp::new(11, p));
+assert_eq!((a + b).value(), 3);
+assert_eq!((a - b).value(), 7);
+assert_eq!((a * b).value(), 3);
+assert_eq!((a / b * b), a);
+assert_eq!(a.pow(12).value(), 1);
+```
+**/
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Fp {
+    value: u64,
+    modulus: PrimeModulus,
+}
+
+impl Fp {
+    /// `value` reduced modulo `modulus`.
+    pub fn new(value: u64, modulus: PrimeModulus) -> Fp {
+        Fp {
+            value: value % modulus.get(),
+            modulus,
+        }
+    }
+
+    /// The element as a number from 0 up to the modulus.
+    pub fn value(self) -> u64 {
+        self.value
+    }
+
+    /// The modulus of the field the element is in.
+    pub fn modulus(self) -> PrimeModulus {
+        self.modulus
+    }
+
+    /// Whether this is the zero of its field.
+    pub fn is_zero(self) -> bool {
+        self.value == 0
+    }
+
+    /// The element raised to the power `e`. Zero to the power 0 is 1.
+    pub fn pow(self, e: u64) -> Fp {
+        self.with(mod_pow(self.value, e, self.modulus.get()))
+    }
+
+    /// The multiplicative inverse, or `None` for zero.
+    pub fn inv(self) -> Option<Fp> {
+        self.modulus.inv(self.value).map(|v| self.with(v))
+    }
+
+    // Another element of the same field, from a value that is already reduced.
+    fn with(self, value: u64) -> Fp {
+        Fp {
+            value,
+            modulus: self.modulus,
+        }
+    }
+
+    // The modulus shared by `self` and `other`, which have to be in the same field.
+    fn common(self, other: Fp) -> u64 {
+        assert_eq!(self.modulus, other.modulus, "elements of different fields");
+        self.modulus.get()
+    }
+}
+
+impl fmt::Display for Fp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (mod {})", self.value, self.modulus.get())
+    }
+}
+
+impl Add for Fp {
+    type Output = Fp;
+    fn add(self, other: Fp) -> Fp {
+        let m = self.common(other);
+        self.with(add_mod(self.value, other.value, m))
+    }
+}
+
+impl Sub for Fp {
+    type Output = Fp;
+    fn sub(self, other: Fp) -> Fp {
+        self + -other
+    }
+}
+
+impl Neg for Fp {
+    type Output = Fp;
+    fn neg(self) -> Fp {
+        if self.value == 0 {
+            self
+        } else {
+            self.with(self.modulus.get() - self.value)
+        }
+    }
+}
+
+impl Mul for Fp {
+    type Output = Fp;
+    fn mul(self, other: Fp) -> Fp {
+        let m = self.common(other);
+        self.with(mul_mod(self.value, other.value, m))
+    }
+}
+
+// Panics on division by zero, as integer division does.
+impl Div for Fp {
+    type Output = Fp;
+    fn div(self, other: Fp) -> Fp {
+        let m = self.common(other);
+        let inv = other.inv().expect("division by zero");
+        self.with(mul_mod(self.value, inv.value, m))
+    }
+}
+
+impl AddAssign for Fp {
+    fn add_assign(&mut self, other: Fp) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Fp {
+    fn sub_assign(&mut self, other: Fp) {
+        *self = *self - other;
+    }
+}
+
+impl MulAssign for Fp {
+    fn mul_assign(&mut self, other: Fp) {
+        *self = *self * other;
+    }
+}
+
+impl DivAssign for Fp {
+    fn div_assign(&mut self, other: Fp) {
+        *self = *self / other;
+    }
+}
@@ -0,0 +1,78 @@
+//! Divisibility tests by a fixed set of divisors, with the division replaced by a multiplication.
+
+/**
+A set of divisors, usually small primes, that numbers can be screened against quickly.
+
+Each divisor `d = d₀ 2^k` (with `d₀` odd) is stored as the inverse of `d₀` modulo `2^64` and the
+bound `u64::MAX / d`. Then `n` is a multiple of `d` exactly when `n` times the inverse, rotated
+right by `k` bits, is at most the bound; that's a multiply, a rotate and a compare, several times
+quicker than the division behind `n % d`. Divisors can be added one at a time as they're needed,
+for callers that screen many numbers against a growing list of primes; the crate's own prime sets
+and factorizers strike multiples or divide directly instead.
+**/
+#[derive(Clone, Debug, Default)]
+pub struct DivisibilityFilter {
+    divisors: Vec<Divisor>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Divisor {
+    inv: u64,
+    limit: u64,
+    shift: u32,
+}
+
+impl Divisor {
+    fn new(d: u64) -> Divisor {
+        assert!(d != 0, "cannot filter by zero");
+        let shift = d.trailing_zeros();
+        let odd = d >> shift;
+        // Newton's iteration doubles the correct low bits each step, from the 3 that `odd` has.
+        let mut inv = odd;
+        for _ in 0..5 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(odd.wrapping_mul(inv)));
+        }
+        Divisor {
+            inv,
+            limit: u64::MAX / d,
+            shift,
+        }
+    }
+
+    fn divides(&self, n: u64) -> bool {
+        n.wrapping_mul(self.inv).rotate_right(self.shift) <= self.limit
+    }
+}
+
+impl DivisibilityFilter {
+    /// A filter for the divisors in `primes`, which needn't actually be prime.
+    ///
+    /// Panics if any of them is zero.
+    pub fn new(primes: &[u64]) -> DivisibilityFilter {
+        DivisibilityFilter {
+            divisors: primes.iter().map(|&p| Divisor::new(p)).collect(),
+        }
+    }
+
+    /// Adds `p` to the divisors.
+    ///
+    /// Panics if `p` is zero.
+    pub fn push(&mut self, p: u64) {
+        self.divisors.push(Divisor::new(p));
+    }
+
+    /// Number of divisors in the filter.
+    pub fn len(&self) -> usize {
+        self.divisors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.divisors.is_empty()
+    }
+
+    /// Whether `n` passes the filter, that is, none of the divisors divides it. The divisors
+    /// themselves don't pass, and neither does 0.
+    pub fn passes(&self, n: u64) -> bool {
+        !self.divisors.iter().any(|d| d.divides(n))
+    }
+}
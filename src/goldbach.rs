@@ -0,0 +1,41 @@
+//! Goldbach partitions: ways of writing an even number as the sum of two primes.
+
+use crate::arith::is_prime_mr;
+use crate::{global, quick};
+
+/**
+Every way of writing `n` as the sum of two primes `p + q` with `p ≤ q`, in ascending order of `p`.
+
+The primes below `n` come from the global cache, which is grown to cover them if it doesn't yet, and
+each `n - p` is looked up in the same list. Odd `n` and `n` below 4 have no partitions here (odd
+`n` can only be `2 + (n - 2)`, which isn't what Goldbach's conjecture is about).
+**/
+pub fn goldbach_pairs(n: u64) -> Vec<(u64, u64)> {
+    if n < 4 || !n.is_multiple_of(2) {
+        return Vec::new();
+    }
+    let primes = quick::below(n);
+    primes
+        .iter()
+        .take_while(|&&p| p <= n / 2)
+        .filter(|&&p| primes.binary_search(&(n - p)).is_ok())
+        .map(|&p| (p, n - p))
+        .collect()
+}
+
+/**
+The partition `p + q` of `n` with the smallest `p`, or `None` for odd `n` and `n` below 4.
+
+The smallest `p` is always small in practice, so unlike `goldbach_pairs` this only walks the first
+few primes from the global cache and tests each `n - p` directly, which works for any even `u64`.
+**/
+pub fn first_goldbach_pair(n: u64) -> Option<(u64, u64)> {
+    if n < 4 || !n.is_multiple_of(2) {
+        return None;
+    }
+    global()
+        .iter()
+        .take_while(|&p| p <= n / 2)
+        .find(|&p| is_prime_mr(n - p))
+        .map(|p| (p, n - p))
+}
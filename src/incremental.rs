@@ -0,0 +1,105 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::ops::Index;
+use std::slice::SliceIndex;
+
+use crate::{initial_primes, wheel_next, PrimeSetBasics, LARGEST_U64_PRIME};
+
+/**
+A prime generator, using an incremental Sieve of Eratosthenes (O'Neill's priority queue sieve).
+
+Each prime `p` from 7 up is kept in a priority queue under its next odd multiple, starting from
+`p²`, and candidates come off a wheel that skips multiples of 2, 3 and 5. A candidate is composite
+exactly when it's at the front of the queue, and the primes there then move on to their next
+multiples. So every `expand()` finds exactly one prime, at an amortized `O(log π(√n))` queue
+operations per number passed, with no segments or limits: it can go on as far as the `u64` range
+does, holding only the primes up to the square root of the last one in the queue.
+
+Create with `let mut pset = IncrementalSieve::new()`, and then use `pset.iter()` to iterate over all
+primes.
+**/
+#[derive(Clone)]
+pub struct IncrementalSieve {
+    lst: Vec<u64>,
+    // The next odd multiple of each prime whose square has been reached, and twice the prime.
+    queue: BinaryHeap<Reverse<(u64, u64)>>,
+    // Index in `lst` of the next prime to go in the queue, once candidates reach its square.
+    next_base: usize,
+}
+
+impl IncrementalSieve {
+    /// A new prime generator, primed with 2 and 3, or with the first 10,000 primes under the
+    /// `table` feature.
+    pub fn new() -> IncrementalSieve {
+        IncrementalSieve {
+            lst: initial_primes().to_vec(),
+            queue: BinaryHeap::new(),
+            // 2, 3 and 5 are left to the wheel.
+            next_base: 3,
+        }
+    }
+
+    // Whether the candidate `c`, which is above every prime found so far, is composite. Moves
+    // every queue entry up to `c` on past it.
+    fn struck_off(&mut self, c: u64) -> bool {
+        while let Some(&p) = self.lst.get(self.next_base) {
+            match p.checked_mul(p) {
+                Some(sq) if sq <= c => {
+                    // The first odd multiple of `p` from `c` on.
+                    let m = c.div_ceil(p) * p;
+                    let m = if m.is_multiple_of(2) { m + p } else { m };
+                    self.queue.push(Reverse((m.max(sq), 2 * p)));
+                    self.next_base += 1;
+                }
+                _ => break,
+            }
+        }
+        let mut composite = false;
+        while let Some(&Reverse((m, step))) = self.queue.peek() {
+            if m > c {
+                break;
+            }
+            composite |= m == c;
+            self.queue.pop();
+            // Multiples past the end of the `u64` range will never come up again.
+            if let Some(next) = m.checked_add(step) {
+                self.queue.push(Reverse((next, step)));
+            }
+        }
+        composite
+    }
+}
+
+impl Default for IncrementalSieve {
+    fn default() -> IncrementalSieve {
+        IncrementalSieve::new()
+    }
+}
+
+impl PrimeSetBasics for IncrementalSieve {
+    /// Finds one more prime and adds it to the list.
+    ///
+    /// Panics if the largest prime that fits in a `u64` has already been found.
+    fn expand(&mut self) {
+        let last = *self.lst.last().unwrap();
+        assert!(last < LARGEST_U64_PRIME, "no primes above the largest prime that fits in a u64");
+        let mut c = wheel_next(last);
+        while self.struck_off(c) {
+            c = wheel_next(c);
+        }
+        self.lst.push(c);
+    }
+
+    /// Returns all primes found so far as a slice.
+    fn list(&self) -> &[u64] {
+        &self.lst[..]
+    }
+}
+
+// Indexes the primes found so far, by position (`pset[3]`) or by range (`pset[..10]`).
+impl<I: SliceIndex<[u64]>> Index<I> for IncrementalSieve {
+    type Output = I::Output;
+    fn index(&self, index: I) -> &I::Output {
+        &self.list()[index]
+    }
+}
@@ -6,167 +6,681 @@ factorizing numbers. Most methods generate primes lazily, so only enough primes
 for the given test, and primes are cached for later use.
 */
 
-use std::cmp::Ordering::{Equal, Greater, Less};
-use std::ops::Index;
-use std::slice;
+use std::borrow::Cow;
+use std::iter::{FromIterator, FusedIterator};
+use std::ops::{Index, Range};
+use std::slice::{self, SliceIndex};
+
+use arith::is_prime_mr;
+
+#[cfg(feature = "aks")]
+pub mod aks;
+mod analytic;
+mod arith;
+mod atkin;
+mod batch;
+mod bounded;
+mod bounds;
+mod cached;
+mod capacity;
+mod certificate;
+mod chains;
+mod compressed;
+mod divisors;
+mod factor;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod field;
+mod filter;
+mod goldbach;
+#[cfg(all(feature = "hugepages", target_os = "linux"))]
+mod hugepage;
+mod incremental;
+mod limited;
+mod modular;
+mod modulus;
+mod predicate;
+pub mod prelude;
+mod prime;
+mod progress;
+mod pseudoprime;
+mod qsieve;
+pub mod quick;
+mod ramanujan;
+mod range;
+#[cfg(feature = "rand")]
+mod sample;
+mod screen;
+mod shared;
+mod sieve;
+mod smooth;
+mod sorted;
+pub mod special;
+mod spf;
+mod squares;
+pub mod stats;
+mod summatory;
+#[cfg(any(feature = "macros", feature = "table"))]
+#[doc(hidden)]
+pub mod table;
+mod text;
+mod totient;
+mod tuples;
+mod view;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use analytic::{
+	brun_sum, brun_sum_with, chebyshev_psi, chebyshev_psi_with, chebyshev_theta,
+	chebyshev_theta_with, nth_prime_approx, prime_pi_approx, prime_zeta, prime_zeta_with,
+	Accumulator, DoubleDouble,
+};
+pub use arith::{lucas_uv, mod_inv, mod_pow, Montgomery};
+pub use atkin::AtkinSieve;
+#[cfg(feature = "bigint")]
+pub use batch::{batch_gcd, euler_product_exact, product_tree, remainder_tree};
+pub use batch::{primorial_mod, product_of_primes_in_mod};
+pub use bounded::{BoundedIter, BoundedPrimeSet};
+pub use bounds::{nth_prime_bounds, pi_bounds, verify_nth_prime};
+pub use cached::{CachedIter, CachedPrimes};
+pub use capacity::{
+	next_prime_at_least, prev_prime_at_most, prime_capacity_for, CAPACITY_PRIMES,
+};
+pub use certificate::{prime_certificate, PrattCertificate};
+pub use chains::{cunningham_chains, CunninghamChains, CunninghamKind};
+pub use compressed::{CompressedIter, CompressedPrimeSet};
+pub use divisors::{
+	abundance, aliquot_sequence, aliquot_sum, divisors, is_semiprime, is_squarefree, num_divisors,
+	radical, sigma, Abundance, AliquotSequence,
+};
+pub use factor::{fermat_factor, squfof, Factorization, Factorizer};
+pub use field::Fp;
+pub use filter::DivisibilityFilter;
+pub use goldbach::{first_goldbach_pair, goldbach_pairs};
+#[cfg(all(feature = "hugepages", target_os = "linux"))]
+pub use hugepage::HugePageBuffer;
+pub use incremental::IncrementalSieve;
+pub use limited::{Limited, PrimeError};
+pub use modular::{
+	crt, discrete_log, jacobi, legendre, multiplicative_order, primitive_root, sqrt_mod,
+};
+pub use modulus::PrimeModulus;
+pub use predicate::PrimePredicate;
+pub use prime::{factors_exp, NotPrime, Prime};
+pub use progress::Progress;
+pub use pseudoprime::{
+	is_carmichael, is_prime_with_witnesses, is_pseudoprime_base, is_strong_pseudoprime_base,
+	MillerRabin, SINCLAIR_WITNESSES,
+};
+pub use qsieve::quadratic_sieve;
+pub use ramanujan::{nth_ramanujan_prime, ramanujan_primes_below};
+pub use range::{
+	bucket_counts, fill_primes, for_each_prime_below, prime_pi, prime_pi_with_progress,
+};
+#[cfg(feature = "parallel")]
+pub use range::par_for_each_prime_below;
+#[cfg(feature = "rand")]
+pub use sample::{
+	gen_prime, gen_prime_with, gen_semiprime, sample_from_stream, sample_prime_weighted,
+	sample_primes_below, PrimeConstraints, Weight,
+};
+#[cfg(feature = "parallel")]
+pub use screen::par_filter_primes;
+pub use screen::are_prime;
+pub use shared::{global, SharedIter, SharedPrimeSet};
+pub use sieve::{Sieve, StepResult};
+pub use smooth::{is_rough, is_smooth, smooth_numbers_below};
+pub use sorted::PrimeSlice;
+pub use spf::SpfSieve;
+pub use squares::{is_gaussian_prime, sum_of_two_squares, two_squares};
+pub use summatory::{
+	hyperbola_sum, mertens, sum_num_divisors_below, totient_summatory, PrefixSummable,
+};
+pub use text::{format_factorization, parse_factorization, read_bfile, write_bfile};
+pub use totient::{carmichael_lambda, euler_phi};
+pub use tuples::{hardy_littlewood_constant, is_admissible};
+pub use view::PrimeView;
 
+/**
+The prime generator to reach for when you have no reason to pick a particular one.
+
+This may be repointed to a faster backend in any release, so code written against it picks up
+performance improvements without changes. Whatever it points to, it will always implement
+`PrimeSet`, have a `new()` constructor, and find every prime, in ascending order, starting from 2.
+Anything beyond that, such as how many primes a single `expand()` finds, may change. Name a
+generator such as `TrialDivision` or `Sieve` directly to keep a specific algorithm.
+**/
+pub type DefaultPrimes = Sieve;
+
+/// The largest prime that fits in a `u64`, `2^64 - 59`.
+pub const LARGEST_U64_PRIME: u64 = 18_446_744_073_709_551_557;
+
+/**
+The storage and generation half of a prime set.
+
+Every method in this crate works over the whole `u64` range: intermediate products never overflow,
+and generators can run all the way up to `LARGEST_U64_PRIME`. Expanding past it panics; use
+`PrimeSet::try_expand` to stop there instead.
+**/
 pub trait PrimeSetBasics {
 	/// Finds one more prime, and adds it to the list.
+	///
+	/// Panics if the largest prime that fits in a `u64` has already been found.
 	fn expand(&mut self);
 
 	/// Returns all primes found so far as a slice.
 	fn list(&self) -> &[u64];
+
+	/// Finds every prime up to and including `n`. Backends that can find primes in bulk override
+	/// this to do so.
+	fn expand_to(&mut self, n: u64) {
+		let n = n.min(LARGEST_U64_PRIME);
+		while self.last_found().is_none_or(|p| p < n) {
+			self.expand();
+		}
+	}
+
+	/// The number of primes found so far.
+	///
+	/// This and the three methods after it are all `PrimeSet` needs for finding, getting and
+	/// iterating over primes. Backends that don't keep their primes as a slice override them, so
+	/// that `list()` is only called by the methods that hand out a slice.
+	fn found_len(&self) -> usize {
+		self.list().len()
+	}
+
+	/// The largest prime found so far, if any.
+	fn last_found(&self) -> Option<u64> {
+		self.list().last().cloned()
+	}
+
+	/// The prime at position `index` among those found so far, counting 2 as position 0.
+	fn get_found(&self, index: usize) -> Option<u64> {
+		self.list().get(index).cloned()
+	}
+
+	/// The smallest prime found so far that is at least `n`, with its position, or `None` if the
+	/// primes found so far don't reach `n`.
+	fn find_found(&self, n: u64) -> Option<(usize, u64)> {
+		let list = self.list();
+		let i = list.partition_point(|&p| p < n);
+		list.get(i).map(|&p| (i, p))
+	}
+
+	/// The number past which `PrimeSet::try_get` and `PrimeSet::try_find` won't search, or `None`
+	/// if they may search as far as it takes. See `PrimeSet::with_work_limit`.
+	fn work_limit(&self) -> Option<u64> {
+		None
+	}
 }
 
 /**
 A prime generator, using the Trial Division method.
 
 Create with `let mut pset = TrialDivision::new()`, and then use `pset.iter()` to iterate over all primes.
+
+Rather than dividing one candidate at a time, the candidates are struck off a block at a time by
+the multiples of the primes already found, which is trial division turned inside out. The list
+still only grows one prime per `expand()`; the rest of each block waits to be handed out by the
+calls after it.
 **/
-#[derive(Default, Clone)]
+#[derive(Clone)]
 pub struct TrialDivision {
-	lst: Vec<u64>,
+	// Every prime found so far, including those `list()` doesn't show yet. New generators share
+	// the primes they start with, and only copy them once they find more.
+	lst: Cow<'static, [u64]>,
+	// How many of `lst` have been handed out.
+	shown: usize,
+}
+
+/// Odd candidates struck off per block by `TrialDivision`.
+#[cfg(not(feature = "tiny"))]
+const TRIAL_BLOCK: usize = 1 << 12;
+#[cfg(feature = "tiny")]
+const TRIAL_BLOCK: usize = 1 << 6;
+
+/// Where `PrimeSet::find_at_or_above` landed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FindResult {
+	/// The smallest prime greater than or equal to the number searched for.
+	pub prime: u64,
+	/// The index of `prime` in the list, counting 2 as index 0.
+	pub index: usize,
+	/// Whether the number searched for was itself prime, so that `prime` is that number.
+	pub was_exact: bool,
 }
 
 pub struct PrimeSetIter<'a, P: PrimeSet> {
 	p: &'a mut P,
 	n: usize,
-	expand: bool,
+	// Where iterating from the back has got to. Until it starts, the iterator has no end.
+	end: Option<usize>,
+}
+
+/// Iterator over primes together with their indexes, counting 2 as index 0, from
+/// `PrimeSet::enumerate_primes` or `PrimeSetIter::indexed`.
+pub struct IndexedPrimes<'a, P: PrimeSet> {
+	iter: PrimeSetIter<'a, P>,
+}
+
+/// Iterator over the primes that match a `PrimePredicate`, from `PrimeSet::iter_matching`.
+pub struct MatchingPrimes<'a, P: PrimeSet> {
+	iter: PrimeSetIter<'a, P>,
+	pred: PrimePredicate,
+}
+
+/// Iterator over just the primes a `PrimeSet` has found so far, from `PrimeSet::iter_found`.
+///
+/// It never expands the set, so it only borrows it immutably and always knows how many primes are
+/// left.
+#[derive(Clone, Debug)]
+pub struct FoundIter<'a> {
+	primes: slice::Iter<'a, u64>,
 }
 
 impl TrialDivision {
-	/// A new prime generator, primed with 2 and 3.
+	/// A new prime generator, primed with 2 and 3, or with the first 10,000 primes under the
+	/// `table` feature.
 	pub fn new() -> TrialDivision {
-		TrialDivision { lst: vec![2, 3] }
+		TrialDivision {
+			lst: Cow::Borrowed(initial_primes()),
+			shown: initial_primes().len(),
+		}
+	}
+
+	/// A prime generator that carries on from `primes`, such as a table loaded from a file.
+	///
+	/// `primes` has to be every prime up to its last entry, in order. Returns `None` if it doesn't
+	/// start with 2 and 3, isn't strictly ascending, or has a composite in it. Missing primes can't
+	/// be detected, and would let their multiples through later on.
+	pub fn from_primes(primes: Vec<u64>) -> Option<TrialDivision> {
+		if !primes.starts_with(&[2, 3])
+			|| primes.windows(2).any(|w| w[0] >= w[1])
+			|| !primes.iter().all(|&p| is_prime_mr(p))
+		{
+			return None;
+		}
+		Some(TrialDivision {
+			shown: primes.len(),
+			lst: Cow::Owned(primes),
+		})
+	}
+}
+
+// Collects a table of primes into a generator, panicking where `from_primes` would return `None`.
+impl FromIterator<u64> for TrialDivision {
+	fn from_iter<I: IntoIterator<Item = u64>>(primes: I) -> TrialDivision {
+		TrialDivision::from_primes(primes.into_iter().collect())
+			.expect("not every prime from 2 up, in order")
+	}
+}
+
+// Appends primes to the ones found so far, panicking on a composite or one out of order, where
+// `from_primes` would return `None`. As there, a missing prime can't be detected.
+impl Extend<u64> for TrialDivision {
+	fn extend<I: IntoIterator<Item = u64>>(&mut self, primes: I) {
+		// Primes found but not yet shown could disagree with the new ones, so they go.
+		let lst = self.lst.to_mut();
+		lst.truncate(self.shown);
+		for p in primes {
+			assert!(p > *lst.last().unwrap(), "primes must be added in ascending order");
+			assert!(is_prime_mr(p), "{} is not prime", p);
+			lst.push(p);
+		}
+		self.shown = lst.len();
+	}
+}
+
+impl Default for TrialDivision {
+	fn default() -> TrialDivision {
+		TrialDivision::new()
+	}
+}
+
+impl TrialDivision {
+	// Strikes off the next blocks of odd candidates by the primes found so far, keeping the ones
+	// left over, until at least one is left. A block stops short of the square of the largest
+	// prime, which is as far as those primes can vouch for, and at the largest prime that fits in
+	// a `u64`.
+	fn strike_block(&mut self) {
+		let last = *self.lst.last().unwrap();
+		assert!(last < LARGEST_U64_PRIME, "no primes above the largest prime that fits in a u64");
+		let mut lo = last + 2;
+		while *self.lst.last().unwrap() == last {
+			let reach = last.saturating_mul(last).min(LARGEST_U64_PRIME + 2) - lo;
+			let n = (reach / 2).clamp(1, TRIAL_BLOCK as u64) as usize;
+
+			let mut bits = [0u64; TRIAL_BLOCK / 64];
+			sieve::mark_composites(&self.lst[1..], lo, n, &mut bits);
+			let lst = self.lst.to_mut();
+			for j in 0..n {
+				if bits[j / 64] & (1 << (j % 64)) == 0 {
+					lst.push(lo + 2 * j as u64);
+				}
+			}
+			lo += 2 * n as u64;
+		}
 	}
 }
 
 impl PrimeSetBasics for TrialDivision {
 	/// Finds one more prime and adds it to the list.
 	fn expand(&mut self) {
-		let mut l: u64 = self.lst.last().unwrap() + 2;
-		let mut remainder = 0;
-		loop {
-			for &n in &self.lst {
-				remainder = l % n;
-				if remainder == 0 || n * n > l {
-					break;
-				}
-			}
-			if remainder != 0 {
-				self.lst.push(l);
-				break;
-			}
-			l += 2;
+		while self.shown == self.lst.len() {
+			self.strike_block();
 		}
+		self.shown += 1;
 	}
 
 	/// Returns all primes found so far as a slice.
 	fn list(&self) -> &[u64] {
-		&self.lst[..]
+		&self.lst[..self.shown]
+	}
+
+	/// Strikes off whole blocks until a prime at or above `n` turns up, and shows every prime up
+	/// to there.
+	fn expand_to(&mut self, n: u64) {
+		let n = n.min(LARGEST_U64_PRIME);
+		while *self.lst.last().unwrap() < n {
+			self.strike_block();
+		}
+		self.shown = self.shown.max(self.lst.partition_point(|&p| p < n) + 1);
 	}
 }
 
 pub trait PrimeSet: PrimeSetBasics + Sized {
 	/// Number of primes found so far.
 	fn len(&self) -> usize {
-		self.list().len()
+		self.found_len()
 	}
 
 	fn is_empty(&self) -> bool {
-		self.list().is_empty()
+		self.found_len() == 0
+	}
+
+	/// Finds one more prime and returns it, or `None` if the largest prime that fits in a `u64`
+	/// has already been found (where `expand()` would panic).
+	fn try_expand(&mut self) -> Option<u64> {
+		if self.last_found() == Some(LARGEST_U64_PRIME) {
+			return None;
+		}
+		self.expand();
+		self.last_found()
+	}
+
+	/// Finds every prime below `n`, returning how many primes were added.
+	///
+	/// Afterwards `find_vec(m)` has an answer for every `m` up to `n`, so a service can warm the
+	/// cache up front (on a background thread, say) and only query it read-only from then on.
+	fn ensure_primes_below(&mut self, n: u64) -> usize {
+		let n = n.min(LARGEST_U64_PRIME);
+		let len = self.len();
+		self.expand_to(n);
+		while self.last_found().is_none_or(|p| p < n) {
+			self.expand();
+		}
+		self.len() - len
+	}
+
+	/// Like `expand_to`, but a stretch at a time, reporting how far it has got to `progress` and
+	/// stopping with `PrimeError::Cancelled` between stretches if told to. Whatever was found
+	/// before then is kept.
+	fn expand_to_with_progress(
+		&mut self,
+		n: u64,
+		progress: &mut Progress<'_>,
+	) -> Result<(), PrimeError> {
+		let n = n.min(LARGEST_U64_PRIME);
+		let start = self.last_found().unwrap_or(0);
+		// A hundred stretches, unless that would make them too short to be worth stopping for.
+		let step = (n.saturating_sub(start) / 100).max(1 << 16);
+		loop {
+			let last = self.last_found().unwrap_or(0);
+			if last >= n {
+				break;
+			}
+			if !progress.report((last - start) as f64 / (n - start) as f64) {
+				return Err(PrimeError::Cancelled);
+			}
+			self.expand_to(last.saturating_add(step).min(n));
+		}
+		progress.report(1.0);
+		Ok(())
 	}
 
 	/// Iterator over all primes not yet found.
-	fn generator(&mut self) -> PrimeSetIter<Self> {
+	fn generator(&mut self) -> PrimeSetIter<'_, Self> {
 		let n = self.len();
 		PrimeSetIter {
 			p: self,
 			n,
-			expand: true,
+			end: None,
 		}
 	}
 
 	/// Iterator over all primes, starting with 2. If you don't care about the "state" of the
 	/// `PrimeSet`, this is what you want!
-	fn iter(&mut self) -> PrimeSetIter<Self> {
+	fn iter(&mut self) -> PrimeSetIter<'_, Self> {
 		PrimeSetIter {
 			p: self,
 			n: 0,
-			expand: true,
+			end: None,
+		}
+	}
+
+	/// Iterator over all primes as `(index, prime)` pairs, starting with `(0, 2)`. The indexes are
+	/// the ones `get` and `find` use.
+	fn enumerate_primes(&mut self) -> IndexedPrimes<'_, Self> {
+		self.iter().indexed()
+	}
+
+	/// Iterator over the primes that match `pred`, starting from 2.
+	fn iter_matching(&mut self, pred: PrimePredicate) -> MatchingPrimes<'_, Self> {
+		MatchingPrimes {
+			iter: self.iter(),
+			pred,
 		}
 	}
 
 	/// Iterator over just the primes found so far.
-	fn iter_vec(&self) -> slice::Iter<u64> {
+	fn iter_vec(&self) -> slice::Iter<'_, u64> {
 		self.list().iter()
 	}
 
+	/// Iterator over just the primes found so far, by value, for read-only consumers that must
+	/// not expand the set.
+	fn iter_found(&self) -> FoundIter<'_> {
+		FoundIter {
+			primes: self.list().iter(),
+		}
+	}
+
+	/// The primes found so far at the positions in `range`, counting 2 as position 0.
+	///
+	/// Panics if the range goes past the primes found so far.
+	fn slice(&self, range: Range<usize>) -> &[u64] {
+		&self.list()[range]
+	}
+
+	/// A read-only view of the primes found so far, for code that must not expand the set.
+	fn view(&self) -> PrimeView<'_> {
+		PrimeView::new(self.list())
+	}
+
 	/// Find the next largest prime from a number
     ///
     /// Returns `(idx, prime)`
     ///
     /// Note that if `n` is prime, then the output will be `(idx, n)`
 	fn find(&mut self, n: u64) -> (usize, u64) {
-		while n > *(self.list().last().unwrap_or(&0)) {
+		while n > self.last_found().unwrap_or(0) {
 			self.expand();
 		}
 		self.find_vec(n).unwrap()
 	}
 
+	/// `find` for every one of `queries`, in the same order, expanding the set only once, to the
+	/// largest of them.
+	///
+	/// Queries in ascending order are answered in a single pass over the primes, which gallops
+	/// ahead from one answer to the next, so each costs about the logarithm of the distance from
+	/// the one before. Queries in any other order are each answered by a binary search.
+	fn find_many(&mut self, queries: &[u64]) -> Vec<(usize, u64)> {
+		let max = match queries.iter().max() {
+			Some(&max) => max,
+			None => return Vec::new(),
+		};
+		self.expand_to(max);
+		while max > self.last_found().unwrap_or(0) {
+			self.expand();
+		}
+
+		let list = self.list();
+		if queries.windows(2).any(|w| w[0] > w[1]) {
+			return queries.iter().map(|&n| self.find_vec(n).unwrap()).collect();
+		}
+		let mut i = 0;
+		queries
+			.iter()
+			.map(|&n| {
+				// Double the step until it passes `n`, then search the last step.
+				let mut step = 1;
+				while i + step < list.len() && list[i + step] < n {
+					step *= 2;
+				}
+				let end = (i + step + 1).min(list.len());
+				i += list[i..end].partition_point(|&p| p < n);
+				(i, list[i])
+			})
+			.collect()
+	}
+
+	/// Like `find`, but gives up with an error where `find` would overflow, or search past the
+	/// set's work limit.
+	fn try_find(&mut self, n: u64) -> Result<(usize, u64), PrimeError> {
+		if let Some(found) = self.find_vec(n) {
+			return Ok(found);
+		}
+		if n > LARGEST_U64_PRIME {
+			return Err(PrimeError::Overflow);
+		}
+		if self.work_limit().is_some_and(|limit| n > limit) {
+			return Err(PrimeError::LimitExceeded);
+		}
+		self.expand_to(n);
+		Ok(self.find(n))
+	}
+
+	/// Find the smallest prime at or above `n`, and whether it is `n` itself.
+	fn find_at_or_above(&mut self, n: u64) -> FindResult {
+		let (index, prime) = self.find(n);
+		FindResult {
+			prime,
+			index,
+			was_exact: prime == n,
+		}
+	}
+
     /// Find the next largest prime from a number, if it is within the already-found list
     ///
     /// Returns `(idx, prime)`
     ///
     /// Note that if `n` is prime, then the output will be `(idx, n)`
 	fn find_vec(&self, n: u64) -> Option<(usize, u64)> {
-		if n > *(self.list().last().unwrap_or(&0)) {
-			return None;
-		}
+		self.find_found(n)
+	}
 
-		let mut base: usize = 0;
-		let mut lim: usize = self.len();
+	/// Whether `n` is prime, found by expanding the set up to `n` and looking it up.
+	fn contains(&mut self, n: u64) -> bool {
+		self.expand_to(n);
+		self.find_found(n).is_some_and(|(_, p)| p == n)
+	}
 
-		// Binary search algorithm
-		while lim != 0 {
-			let idx = base + (lim >> 1);
-			match self.list()[idx].cmp(&n) {
-				Equal => return Some((idx, self.list()[idx])),
-				Less => {
-					base = idx + 1;
-					lim -= 1;
-				},
-				Greater => (),
+	/// Whether `n` is prime, if the primes found so far reach it, and `None` if they don't.
+	fn contains_cached(&self, n: u64) -> Option<bool> {
+		self.find_found(n).map(|(_, p)| p == n)
+	}
+
+	/// Whether `n` is prime, using the primes found so far to answer quickly.
+	///
+	/// Up to the largest prime found, this is a binary search. Above it, `n` is trial divided by
+	/// the cached primes up to `CACHED_TRIAL_BOUND`, which settles it if they reach `sqrt(n)`;
+	/// otherwise a Miller–Rabin test that is exact for every `u64` has the last word. The set is
+	/// only expanded to `CACHED_TRIAL_BOUND` (or `sqrt(n)`, if smaller), so a query never sets off
+	/// a long sieve, and however many primes are cached, only those up to the bound are divided
+	/// by.
+	fn is_prime_cached(&mut self, n: u64) -> bool {
+		if n < 2 {
+			return false;
+		}
+		if let Some((_, p)) = self.find_vec(n) {
+			return p == n;
+		}
+		self.expand_to(n.isqrt().min(CACHED_TRIAL_BOUND));
+		let small = (0..self.found_len()).map_while(|i| self.get_found(i));
+		for p in small.take_while(|&p| p <= CACHED_TRIAL_BOUND) {
+			if p > n / p {
+				return true;
+			}
+			if n.is_multiple_of(p) {
+				return false;
 			}
-			lim = lim >> 1;
 		}
-		Some((base, self.list()[base]))
+		is_prime_mr(n)
 	}
 
 	/// Get the nth prime, even if we haven't found it yet.
 	fn get(&mut self, index: usize) -> u64 {
-		for _ in 0..(index as isize) + 1 - (self.len() as isize) {
+		while self.len() <= index {
 			self.expand();
 		}
-		self.list()[index]
+		self.get_found(index).unwrap()
+	}
+
+	/// Like `get`, but `None` where `get` would panic or search past the set's work limit.
+	///
+	/// Indexes that are certain to be out of reach, because a lower bound on the prime is already
+	/// past the limit (or past `LARGEST_U64_PRIME`), are turned down without searching at all.
+	fn try_get(&mut self, index: usize) -> Option<u64> {
+		if let Some(p) = self.get_found(index) {
+			return Some(p);
+		}
+		let limit = self.work_limit().unwrap_or(LARGEST_U64_PRIME);
+		let (lo, hi) = nth_prime_bounds((index as u64).saturating_add(1));
+		if lo > limit {
+			return None;
+		}
+		self.expand_to(hi.min(limit));
+		while self.len() <= index {
+			if self.last_found().is_some_and(|p| p >= limit) {
+				return None;
+			}
+			self.try_expand()?;
+		}
+		self.get_found(index)
+	}
+
+	/// This set with a work limit: `try_get` and `try_find` won't search for primes past `limit`.
+	fn with_work_limit(self, limit: u64) -> Limited<Self> {
+		Limited::new(self, limit)
 	}
 
 	// Get the prime factors of a number, starting from 2, including repeats. This method will
 	// expand the prime number pool as they are needed.
 	fn prime_factors(&mut self, n: u64) -> Vec<u64> {
-		if n == 1 {
+		if n <= 1 {
 			return Vec::new();
 		}
 		let mut curn = n;
 		let mut lst: Vec<u64> = Vec::new();
 		for p in self.iter() {
-			while curn % p == 0 {
+			while curn.is_multiple_of(p) {
 				lst.push(p);
 				curn /= p;
 				if curn == 1 {
 					return lst;
 				}
-				if p * p > curn {
+				if p > curn / p {
 					lst.push(curn);
 					return lst;
 				}
@@ -179,9 +693,10 @@ pub trait PrimeSet: PrimeSetBasics + Sized {
 // This line implements `PrimeSet` trait for all types in scope that implement `PrimeSetBasics`.
 impl<P: PrimeSetBasics> PrimeSet for P {}
 
-impl Index<usize> for TrialDivision {
-	type Output = u64;
-	fn index(&self, index: usize) -> &u64 {
+// Indexes the primes found so far, by position (`pset[3]`) or by range (`pset[..10]`).
+impl<I: SliceIndex<[u64]>> Index<I> for TrialDivision {
+	type Output = I::Output;
+	fn index(&self, index: I) -> &I::Output {
 		&self.list()[index]
 	}
 }
@@ -189,29 +704,150 @@ impl Index<usize> for TrialDivision {
 impl<'a, P: PrimeSet> Iterator for PrimeSetIter<'a, P> {
 	type Item = u64;
 	fn next(&mut self) -> Option<u64> {
-		while self.n >= self.p.list().len() {
-			if self.expand {
-				self.p.expand();
-			} else {
-				return None;
-			}
+		if self.end.is_some_and(|end| self.n >= end) {
+			return None;
+		}
+		while self.n >= self.p.len() {
+			self.p.expand();
 		}
 		self.n += 1;
 
-		Some(self.p.list()[self.n - 1])
+		self.p.get_found(self.n - 1)
+	}
+
+	// Skips straight to the prime wanted, rather than stepping through every one before it, and
+	// expands to an upper bound on it in one go rather than one `expand()` at a time.
+	fn nth(&mut self, k: usize) -> Option<u64> {
+		self.n = self.n.saturating_add(k);
+		if self.end.is_none() && self.n >= self.p.len() {
+			self.p.expand_to(nth_prime_bounds((self.n as u64).saturating_add(1)).1);
+		}
+		self.next()
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		match self.end {
+			Some(end) => (end.saturating_sub(self.n), Some(end.saturating_sub(self.n))),
+			None => (self.p.len().saturating_sub(self.n), None),
+		}
 	}
 }
 
+// From the back, only the primes already found are iterated over; the iterator stops expanding
+// once this is used, and its front end stops where the back end got to.
+impl<'a, P: PrimeSet> DoubleEndedIterator for PrimeSetIter<'a, P> {
+	fn next_back(&mut self) -> Option<u64> {
+		let end = self.end.unwrap_or(self.p.len());
+		if end <= self.n {
+			self.end = Some(end);
+			return None;
+		}
+		self.end = Some(end - 1);
+		self.p.get_found(end - 1)
+	}
+}
+
+impl<'a, P: PrimeSet> PrimeSetIter<'a, P> {
+	/// This iterator with each prime's index alongside it, counting 2 as index 0 wherever the
+	/// iterator started: from `generator()`, the first index is the number of primes found before.
+	pub fn indexed(self) -> IndexedPrimes<'a, P> {
+		IndexedPrimes { iter: self }
+	}
+}
+
+impl<'a, P: PrimeSet> Iterator for IndexedPrimes<'a, P> {
+	type Item = (usize, u64);
+	fn next(&mut self) -> Option<(usize, u64)> {
+		let p = self.iter.next()?;
+		Some((self.iter.n - 1, p))
+	}
+
+	fn nth(&mut self, k: usize) -> Option<(usize, u64)> {
+		let p = self.iter.nth(k)?;
+		Some((self.iter.n - 1, p))
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.iter.size_hint()
+	}
+}
+
+impl<'a, P: PrimeSet> DoubleEndedIterator for IndexedPrimes<'a, P> {
+	fn next_back(&mut self) -> Option<(usize, u64)> {
+		let p = self.iter.next_back()?;
+		Some((self.iter.end.unwrap(), p))
+	}
+}
+
+impl<'a, P: PrimeSet> Iterator for MatchingPrimes<'a, P> {
+	type Item = u64;
+	fn next(&mut self) -> Option<u64> {
+		let pred = &self.pred;
+		self.iter.find(|&p| pred.matches(p))
+	}
+}
+
+impl<'a> Iterator for FoundIter<'a> {
+	type Item = u64;
+	fn next(&mut self) -> Option<u64> {
+		self.primes.next().cloned()
+	}
+
+	fn nth(&mut self, k: usize) -> Option<u64> {
+		self.primes.nth(k).cloned()
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.primes.size_hint()
+	}
+}
+
+impl<'a> DoubleEndedIterator for FoundIter<'a> {
+	fn next_back(&mut self) -> Option<u64> {
+		self.primes.next_back().cloned()
+	}
+}
+
+impl<'a> ExactSizeIterator for FoundIter<'a> {}
+
+impl<'a> FusedIterator for FoundIter<'a> {}
+
+/// The primes a new generator starts out with.
+pub(crate) fn initial_primes() -> &'static [u64] {
+	#[cfg(feature = "table")]
+	return &table::FIRST_PRIMES;
+	#[cfg(not(feature = "table"))]
+	return &[2, 3];
+}
+
+/// How far `PrimeSet::is_prime_cached` expands a set for trial division before relying on
+/// Miller–Rabin instead.
+pub const CACHED_TRIAL_BOUND: u64 = 1 << 10;
+
+/// Distance from each residue mod 30 to the next number coprime to 30.
+const WHEEL_30: [u64; 30] = [
+	1, 6, 5, 4, 3, 2, 1, 4, 3, 2, 1, 2, 1, 4, 3, 2, 1, 2, 1, 4, 3, 2, 1, 6, 5, 4, 3, 2, 1, 2,
+];
+
+/// The next candidate after odd `x` that could be prime: 5, 7, and then only numbers coprime to 30.
+fn wheel_next(x: u64) -> u64 {
+	let step = if x < 5 { 2 } else { WHEEL_30[(x % 30) as usize] };
+	x.checked_add(step).expect("no primes above the largest prime that fits in a u64")
+}
 
 /// Find the first factor (other than 1) of a number.
 fn firstfac(x: u64) -> u64 {
-	if x % 2 == 0 {
-		return 2;
+	for &p in &[2, 3, 5] {
+		if x.is_multiple_of(p) {
+			return p;
+		}
 	}
-	for d in (1..).map(|m| 2 * m + 1).take_while(|m| m * m <= x) {
-		if x % d == 0 {
+	let mut d = 7;
+	while d <= x / d {
+		if x.is_multiple_of(d) {
 			return d;
 		}
+		d = wheel_next(d);
 	}
 	// No factor found, it must be prime.
 	x
@@ -220,11 +856,11 @@ fn firstfac(x: u64) -> u64 {
 /// Find all prime factors of a number. Different implementation.
 pub fn factors_v2(mut n: u64) -> Vec<u64> {
     let mut factors = Vec::new();
-    let mut candidates = std::iter::once(2_u64).chain((3..).step_by(2).take_while(move |x| x * x <= n));
+    let mut candidates = std::iter::once(2_u64).chain((3..).step_by(2).take_while(move |x| *x <= n / x));
 
     while n > 1 {
         if let Some(x) = candidates.next() {
-            while n % x == 0 {
+            while n.is_multiple_of(x) {
                 n /= x;
                 factors.push(x);
             }
@@ -237,6 +873,32 @@ pub fn factors_v2(mut n: u64) -> Vec<u64> {
     factors
 }
 
+/**
+Iterator over the prime factors of a number, in ascending order and including repeats.
+
+Each factor is yielded as soon as it is found, so stopping early (say, after the first factor, or
+once a factor is too large) skips the rest of the work. Both 0 and 1 have no factors.
+**/
+pub fn factor_iter(mut x: u64) -> impl Iterator<Item = u64> {
+	let mut d = 2;
+	std::iter::from_fn(move || {
+		if x <= 1 {
+			return None;
+		}
+		while d <= x / d {
+			if x.is_multiple_of(d) {
+				x /= d;
+				return Some(d);
+			}
+			d = if d == 2 { 3 } else { wheel_next(d) };
+		}
+		// Nothing left divides it, so what remains is prime.
+		let p = x;
+		x = 1;
+		Some(p)
+	})
+}
+
 /// Find all prime factors of a number.
 pub fn factors(mut x: u64) -> Vec<u64> {
 	if x <= 1 {
@@ -267,7 +929,7 @@ pub fn factors_unique(mut x: u64) -> Vec<u64> {
 		if d == x {
 			break;
 		}
-		while x % d == 0 {
+		while x.is_multiple_of(d) {
 			x /= d;
 		}
 		if x == 1 {
@@ -277,7 +939,58 @@ pub fn factors_unique(mut x: u64) -> Vec<u64> {
 	lst
 }
 
-/// Tests whether a number is prime. Checks every odd number up to `sqrt(n)`.
+/// Tests whether a number is prime. Checks 2, 3, 5 and every number coprime to 30 up to `sqrt(n)`.
 pub fn is_prime(n: u64) -> bool {
 	n > 1 && firstfac(n) == n
 }
+
+/**
+Tests whether a number is prime, and can do so at compile time.
+
+This is a Miller–Rabin test to the prime bases up to 37, which is exact for every `u64` and takes
+microseconds even for the largest, so it also makes a quick run-time test. Being a `const fn`, it
+can check constants such as moduli as the program is compiled:
+
+```
+const MODULUS: u64 = 998_244_353;
+const _: () = assert!(primes::is_prime_const(MODULUS));
+```
+**/
+pub const fn is_prime_const(n: u64) -> bool {
+	is_prime_mr(n)
+}
+
+/**
+The first `N` primes, computed by a `const fn` so that tables can be built at compile time.
+
+```
+use primes::first_n_primes;
+
+static SMALL_PRIMES: [u64; 8] = first_n_primes::<8>();
+assert_eq!(SMALL_PRIMES, [2, 3, 5, 7, 11, 13, 17, 19]);
+```
+
+Each candidate is trial divided by the primes already in the table, which is quick enough for a
+few thousand primes; beyond that it slows down compilation noticeably.
+**/
+pub const fn first_n_primes<const N: usize>() -> [u64; N] {
+	let mut table = [0; N];
+	if N == 0 {
+		return table;
+	}
+	table[0] = 2;
+	let mut len = 1;
+	let mut n = 3;
+	while len < N {
+		let mut i = 1;
+		while i < len && table[i] * table[i] <= n && !n.is_multiple_of(table[i]) {
+			i += 1;
+		}
+		if i == len || table[i] * table[i] > n {
+			table[len] = n;
+			len += 1;
+		}
+		n += 2;
+	}
+	table
+}
@@ -0,0 +1,99 @@
+//! Prime sets with a cap on how far they will search, and the errors from searching too far.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::PrimeSetBasics;
+
+/// Why `PrimeSet::try_find`, or an operation with a `Progress`, didn't finish.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PrimeError {
+    /// The prime would be past `LARGEST_U64_PRIME`, so it doesn't fit in a `u64`.
+    Overflow,
+    /// Finding the prime would mean searching past the set's work limit.
+    LimitExceeded,
+    /// The operation was stopped through its `Progress`.
+    Cancelled,
+}
+
+impl fmt::Display for PrimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrimeError::Overflow => write!(f, "prime does not fit in a u64"),
+            PrimeError::LimitExceeded => write!(f, "prime is past the work limit"),
+            PrimeError::Cancelled => write!(f, "operation was cancelled"),
+        }
+    }
+}
+
+impl Error for PrimeError {}
+
+/**
+A prime set that won't search past a given number in its checked methods, from
+`PrimeSet::with_work_limit`.
+
+`try_get` and `try_find` give up with `None` or `PrimeError::LimitExceeded` rather than look for
+primes above the limit, so a service answering queries from outside can put a bound on the work any
+one of them does. Primes already found are still handed out wherever they are, and the unchecked
+methods ignore the limit altogether.
+
+```
+use primes::{PrimeError, PrimeSet, Sieve};
+
+let mut pset = Sieve::new().with_work_limit(1_000_000);
+assert_eq!(pset.try_find(999_980), Ok((78_497, 999_983)));
+assert_eq!(pset.try_find(2_000_000), Err(PrimeError::LimitExceeded));
+assert_eq!(pset.try_get(1_000_000), None);
+assert_eq!(pset.try_get(1000), Some(7927));
+```
+**/
+#[derive(Clone, Debug)]
+pub struct Limited<P> {
+    set: P,
+    limit: u64,
+}
+
+impl<P> Limited<P> {
+    pub(crate) fn new(set: P, limit: u64) -> Limited<P> {
+        Limited { set, limit }
+    }
+
+    /// Unwraps the underlying prime set.
+    pub fn into_inner(self) -> P {
+        self.set
+    }
+}
+
+impl<P: PrimeSetBasics> PrimeSetBasics for Limited<P> {
+    fn expand(&mut self) {
+        self.set.expand();
+    }
+
+    fn list(&self) -> &[u64] {
+        self.set.list()
+    }
+
+    fn expand_to(&mut self, n: u64) {
+        self.set.expand_to(n);
+    }
+
+    fn found_len(&self) -> usize {
+        self.set.found_len()
+    }
+
+    fn last_found(&self) -> Option<u64> {
+        self.set.last_found()
+    }
+
+    fn get_found(&self, index: usize) -> Option<u64> {
+        self.set.get_found(index)
+    }
+
+    fn find_found(&self, n: u64) -> Option<(usize, u64)> {
+        self.set.find_found(n)
+    }
+
+    fn work_limit(&self) -> Option<u64> {
+        Some(self.set.work_limit().map_or(self.limit, |l| l.min(self.limit)))
+    }
+}
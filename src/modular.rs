@@ -0,0 +1,306 @@
+//! The structure of the multiplicative group modulo `n`: orders of elements and generators.
+
+use std::collections::HashMap;
+
+use crate::arith::{add_mod, gcd, is_prime_mr, mod_inv, mod_pow, mul_mod};
+use crate::divisors::prime_powers;
+use crate::{carmichael_lambda, euler_phi, factors_unique};
+
+/**
+The multiplicative order of `a` modulo `n`: the smallest `k > 0` with `a^k ≡ 1 (mod n)`.
+
+Returns `None` if there is no such `k`, which is when `a` and `n` share a factor (or `n` is 0).
+The order always divides λ(n), so this starts from λ(n) and divides out each of its prime factors
+for as long as the power still comes to 1.
+**/
+pub fn multiplicative_order(a: u64, n: u64) -> Option<u64> {
+    if n == 0 || gcd(a % n, n) != 1 {
+        return None;
+    }
+    let lambda = carmichael_lambda(n);
+    let mut order = lambda;
+    for q in factors_unique(lambda) {
+        while order.is_multiple_of(q) && mod_pow(a, order / q, n) == 1 % n {
+            order /= q;
+        }
+    }
+    Some(order)
+}
+
+/**
+The smallest primitive root modulo `n`, a number whose powers run through every residue coprime to
+`n`.
+
+Primitive roots only exist when `n` is 2, 4, a power of an odd prime, or twice one; for anything
+else, including 0 and 1, this returns `None`.
+**/
+pub fn primitive_root(n: u64) -> Option<u64> {
+    let phi = euler_phi(n);
+    if n < 2 || carmichael_lambda(n) != phi {
+        return None;
+    }
+    let qs = factors_unique(phi);
+    (1..n).find(|&g| gcd(g, n) == 1 && qs.iter().all(|&q| mod_pow(g, phi / q, n) != 1))
+}
+
+/// Above this many factors of 2 in `p - 1`, Tonelli–Shanks slows down enough that `sqrt_mod` uses
+/// Cipolla's algorithm instead.
+const CIPOLLA_ABOVE: u32 = 12;
+
+/**
+A square root of `a` modulo the prime `p`: an `x` with `x^2 ≡ a (mod p)`, or `None` if `a` isn't a
+square modulo `p`.
+
+Of the two roots `x` and `p - x`, the smaller is returned. This uses Tonelli–Shanks, whose running
+time grows with the square of the number of factors of 2 in `p - 1`; for primes where that is
+large it switches to Cipolla's algorithm, which doesn't care.
+
+`p` has to be prime, which is checked by Miller–Rabin; for any other `p` this returns `None`.
+**/
+pub fn sqrt_mod(a: u64, p: u64) -> Option<u64> {
+    if !is_prime_mr(p) {
+        return None;
+    }
+    if p == 2 {
+        return Some(a % 2);
+    }
+    let a = a % p;
+    if a == 0 {
+        return Some(0);
+    }
+    if jacobi(a, p) != 1 {
+        return None;
+    }
+
+    let s = (p - 1).trailing_zeros();
+    let x = if s > CIPOLLA_ABOVE {
+        cipolla(a, p)?
+    } else {
+        tonelli_shanks(a, p, s)?
+    };
+    if mul_mod(x, x, p) != a {
+        return None;
+    }
+    Some(x.min(p - x))
+}
+
+// Tonelli–Shanks, for a quadratic residue `a` modulo `p = q 2^s + 1` with `q` odd.
+fn tonelli_shanks(a: u64, p: u64, s: u32) -> Option<u64> {
+    let q = (p - 1) >> s;
+    let z = (2..p).find(|&z| jacobi(z, p) == -1)?;
+
+    let mut m = s;
+    let mut c = mod_pow(z, q, p);
+    let mut t = mod_pow(a, q, p);
+    let mut r = mod_pow(a, q.div_ceil(2), p);
+    while t != 1 {
+        // The least `i` with `t^(2^i) = 1`.
+        let mut i = 0;
+        let mut t2 = t;
+        while t2 != 1 {
+            t2 = mul_mod(t2, t2, p);
+            i += 1;
+            if i == m {
+                return None;
+            }
+        }
+        let b = mod_pow(c, 1 << (m - i - 1), p);
+        m = i;
+        c = mul_mod(b, b, p);
+        t = mul_mod(t, c, p);
+        r = mul_mod(r, b, p);
+    }
+    Some(r)
+}
+
+// Cipolla's algorithm: with `w = t^2 - a` not a square, `(t + sqrt(w))^((p + 1) / 2)` is a root.
+fn cipolla(a: u64, p: u64) -> Option<u64> {
+    let (t, w) = (1..p)
+        .map(|t| (t, add_mod(mul_mod(t, t, p), p - a, p)))
+        .find(|&(_, w)| jacobi(w, p) == -1)?;
+
+    // Multiplication in `F_p[sqrt(w)]`, on pairs `(x, y)` standing for `x + y sqrt(w)`.
+    let mul = |(x1, y1): (u64, u64), (x2, y2): (u64, u64)| {
+        (
+            add_mod(mul_mod(x1, x2, p), mul_mod(mul_mod(y1, y2, p), w, p), p),
+            add_mod(mul_mod(x1, y2, p), mul_mod(x2, y1, p), p),
+        )
+    };
+    let (mut base, mut acc) = ((t, 1), (1, 0));
+    let mut e = p / 2 + 1;
+    while e > 0 {
+        if e & 1 == 1 {
+            acc = mul(acc, base);
+        }
+        base = mul(base, base);
+        e >>= 1;
+    }
+    Some(acc.0)
+}
+
+/**
+The Jacobi symbol (a/n), which is 0, 1 or -1.
+
+It is 0 when `a` and `n` share a factor. For prime `n` it is the Legendre symbol; for composite `n`
+a -1 still proves `a` is not a square modulo `n`, but a 1 proves nothing. Worked out by quadratic
+reciprocity, in about as many steps as a gcd.
+
+Panics if `n` is even.
+**/
+pub fn jacobi(a: u64, n: u64) -> i8 {
+    assert!(n % 2 == 1, "the Jacobi symbol needs an odd modulus");
+    let (mut a, mut n) = (a % n, n);
+    let mut t = 1;
+    while a != 0 {
+        let twos = a.trailing_zeros();
+        a >>= twos;
+        // (2/n) is -1 exactly when n is 3 or 5 mod 8.
+        if twos % 2 == 1 && (n % 8 == 3 || n % 8 == 5) {
+            t = -t;
+        }
+        // Reciprocity flips the sign when both are 3 mod 4.
+        if a % 4 == 3 && n % 4 == 3 {
+            t = -t;
+        }
+        (a, n) = (n % a, a);
+    }
+    if n == 1 {
+        t
+    } else {
+        0
+    }
+}
+
+/**
+The Legendre symbol (a/p): 0 if `p` divides `a`, 1 if `a` is a nonzero square modulo `p`, and -1
+otherwise.
+
+`p` has to be an odd prime; that isn't checked, and for composite `p` this gives the Jacobi symbol.
+
+Panics if `p` is even.
+**/
+pub fn legendre(a: u64, p: u64) -> i8 {
+    jacobi(a, p)
+}
+
+/**
+Solves the simultaneous congruences `x ≡ r (mod m)` for every `(r, m)` in `residues`, by the
+Chinese Remainder Theorem.
+
+Returns `(x, l)`, where `l` is the lcm of the moduli and `x` is the only solution in `0..l`, so the
+solutions are exactly `x + k l`. The moduli don't have to be coprime, but then the residues have to
+agree wherever the moduli overlap. Returns `None` if they don't, if a modulus is 0, or if the lcm
+overflows a `u64`.
+**/
+pub fn crt(residues: &[(u64, u64)]) -> Option<(u64, u64)> {
+    residues.iter().try_fold((0, 1), |(r1, m1), &(r2, m2)| {
+        if m2 == 0 {
+            return None;
+        }
+        let r2 = r2 % m2;
+        let g = gcd(m1, m2);
+        let diff = i128::from(r2) - i128::from(r1);
+        if diff % i128::from(g) != 0 {
+            return None;
+        }
+        let l = (m1 / g).checked_mul(m2)?;
+
+        // x = r1 + m1 k, where k solves (m1 / g) k ≡ (r2 - r1) / g (mod m2 / g).
+        let m = m2 / g;
+        let rhs = (diff / i128::from(g)).rem_euclid(i128::from(m)) as u64;
+        let k = mul_mod(rhs, mod_inv(m1 / g, m)?, m);
+        let x = (u128::from(r1) + u128::from(m1) * u128::from(k)) % u128::from(l);
+        Some((x as u64, l))
+    })
+}
+
+/**
+The discrete logarithm of `target` to `base` modulo `modulus`: the smallest `x` with
+`base^x ≡ target (mod modulus)`, or `None` if there is none.
+
+When `base` is coprime to the modulus, this uses Pohlig–Hellman: the order of `base` is factored,
+and the logarithm is found one prime power of the order at a time, each digit by baby-step
+giant-step. That takes about `sqrt(q)` steps and memory for the largest prime `q` dividing the
+order, so it is quick whenever the order is smooth. Any common factor of `base` and the modulus is
+divided out first.
+**/
+pub fn discrete_log(base: u64, target: u64, modulus: u64) -> Option<u64> {
+    if modulus == 0 {
+        return None;
+    }
+    let (mut a, mut t, mut m) = (base % modulus, target % modulus, modulus);
+    if 1 % m == t {
+        return Some(0);
+    }
+
+    // Divide common factors out of `base^x ≡ target`, one power of `base` at a time; what is left
+    // is `coef base^y ≡ t` with `base` coprime to the new modulus, and `x = y + k`.
+    let (mut k, mut coef) = (0, 1 % m);
+    loop {
+        let g = gcd(a, m);
+        if g == 1 {
+            break;
+        }
+        if t % g != 0 {
+            return None;
+        }
+        t /= g;
+        m /= g;
+        k += 1;
+        a %= m;
+        coef = mul_mod(coef % m, (base / g) % m, m);
+        if coef == t {
+            return Some(k);
+        }
+    }
+    let t = mul_mod(t, mod_inv(coef, m)?, m);
+    coprime_log(a, t, m).map(|y| y + k)
+}
+
+// The smallest `x` with `a^x ≡ t (mod m)`, for `a` coprime to `m`, by Pohlig–Hellman.
+fn coprime_log(a: u64, t: u64, m: u64) -> Option<u64> {
+    let n = multiplicative_order(a, m)?;
+    let mut parts = Vec::new();
+    for (q, e) in prime_powers(n) {
+        let qe = q.pow(e);
+        // Project into the subgroup of order q^e, and find the logarithm there digit by digit.
+        let (ai, ti) = (mod_pow(a, n / qe, m), mod_pow(t, n / qe, m));
+        let gamma = mod_pow(ai, qe / q, m);
+        let ai_inv = mod_inv(ai, m)?;
+        let (mut x, mut qj) = (0, 1);
+        for j in 0..e {
+            let h = mod_pow(mul_mod(mod_pow(ai_inv, x, m), ti, m), q.pow(e - 1 - j), m);
+            x += baby_giant(gamma, h, q, m)? * qj;
+            qj *= q;
+        }
+        parts.push((x, qe));
+    }
+    let (x, _) = crt(&parts)?;
+    if mod_pow(a, x, m) == t {
+        Some(x)
+    } else {
+        None
+    }
+}
+
+// The `x` in `0..order` with `g^x ≡ h (mod m)`, where `g` has order `order`, by baby-step
+// giant-step.
+fn baby_giant(g: u64, h: u64, order: u64, m: u64) -> Option<u64> {
+    let steps = order.isqrt() + 1;
+    let mut baby = HashMap::new();
+    let mut gj = 1 % m;
+    for j in 0..steps {
+        baby.entry(gj).or_insert(j);
+        gj = mul_mod(gj, g, m);
+    }
+    // `gj` is now `g^steps`; each giant step multiplies by its inverse.
+    let giant = mod_inv(gj, m)?;
+    let mut gamma = h;
+    for i in 0..steps {
+        if let Some(&j) = baby.get(&gamma) {
+            return Some(i * steps + j);
+        }
+        gamma = mul_mod(gamma, giant, m);
+    }
+    None
+}
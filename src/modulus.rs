@@ -0,0 +1,69 @@
+//! A modulus that is known to be prime.
+
+use crate::arith::is_prime_mr;
+use crate::{mod_pow, sqrt_mod};
+
+/**
+A modulus that has been checked to be prime.
+
+Generic code that only works modulo a prime, such as anything that divides by inverting through
+Fermat's little theorem, can take a `PrimeModulus` instead of a `u64` and leave the check to
+whoever builds one. Construction runs a Miller–Rabin test that is exact over the whole `u64` range,
+and is a `const fn`, so a constant modulus is checked at compile time:
+
+```
+use primes::PrimeModulus;
+
+const P: PrimeModulus = PrimeModulus::from_prime(998_244_353);
+assert_eq!(P.get(), 998_244_353);
+assert_eq!(PrimeModulus::new(998_244_351), None);
+```
+**/
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PrimeModulus(pub(crate) u64);
+
+impl PrimeModulus {
+    /// `p` as a `PrimeModulus`, or `None` if it isn't prime.
+    pub const fn new(p: u64) -> Option<PrimeModulus> {
+        if is_prime_mr(p) {
+            Some(PrimeModulus(p))
+        } else {
+            None
+        }
+    }
+
+    /// `p` as a `PrimeModulus`, for constants.
+    ///
+    /// Panics if `p` isn't prime, which in a `const` item is a compile error.
+    pub const fn from_prime(p: u64) -> PrimeModulus {
+        match PrimeModulus::new(p) {
+            Some(m) => m,
+            None => panic!("modulus is not prime"),
+        }
+    }
+
+    /// The modulus itself.
+    pub const fn get(self) -> u64 {
+        self.0
+    }
+
+    /// The inverse of `a`, by Fermat's little theorem, or `None` if `a` is a multiple of the
+    /// modulus.
+    pub fn inv(self, a: u64) -> Option<u64> {
+        if a.is_multiple_of(self.0) {
+            return None;
+        }
+        Some(mod_pow(a, self.0 - 2, self.0))
+    }
+
+    /// A square root of `a`, or `None` if `a` isn't a square; see `sqrt_mod`.
+    pub fn sqrt(self, a: u64) -> Option<u64> {
+        sqrt_mod(a, self.0)
+    }
+}
+
+impl From<PrimeModulus> for u64 {
+    fn from(m: PrimeModulus) -> u64 {
+        m.0
+    }
+}
@@ -0,0 +1,161 @@
+//! Properties of primes, such as being a twin or a safe prime, as values that can be combined and
+//! used to filter any stream of primes.
+
+use crate::arith::is_prime_mr;
+
+/// Every `u64` with at most this many digits fits, which is as far as palindromes are built.
+const MAX_DIGITS: u32 = 19;
+
+/**
+A property a prime can have, for picking out the special primes from any iterator of primes.
+
+The basic properties can be combined with `and` and `or` into one predicate, so a single `filter`
+covers what would otherwise take a separate iterator for each kind of special prime:
+
+```
+use primes::{PrimePredicate, PrimeSet, Sieve};
+
+let mut pset = Sieve::new();
+let wanted = PrimePredicate::SafePrime.and(PrimePredicate::Mod(3, 4));
+let safe: Vec<u64> = wanted.filter(pset.iter().take_while(|&p| p < 500)).collect();
+assert_eq!(safe, vec![7, 11, 23, 47, 59, 83, 107, 167, 179, 227, 263, 347, 359, 383, 467, 479]);
+```
+
+Every predicate assumes that the number it's given is prime, and says nothing useful otherwise.
+The partner primes it needs, such as `p + 2` for a twin, are tested by Miller–Rabin. A partner that
+doesn't fit in a `u64` counts as not prime.
+**/
+#[derive(Clone, Debug)]
+pub enum PrimePredicate {
+    /// `p - 2` or `p + 2` is prime as well.
+    Twin,
+    /// `(p - 1) / 2` is prime as well.
+    SafePrime,
+    /// `2p + 1` is prime as well.
+    SophieGermain,
+    /// `p` reads the same backwards in base 10.
+    Palindromic,
+    /// `p ≡ a (mod q)`. Panics when tested if `q` is zero.
+    Mod(u64, u64),
+    /// Whatever the function says.
+    Custom(fn(u64) -> bool),
+    /// Both predicates hold.
+    And(Box<PrimePredicate>, Box<PrimePredicate>),
+    /// Either predicate holds.
+    Or(Box<PrimePredicate>, Box<PrimePredicate>),
+}
+
+impl PrimePredicate {
+    /// The predicate that both `self` and `other` hold.
+    pub fn and(self, other: PrimePredicate) -> PrimePredicate {
+        PrimePredicate::And(Box::new(self), Box::new(other))
+    }
+
+    /// The predicate that `self` or `other` holds.
+    pub fn or(self, other: PrimePredicate) -> PrimePredicate {
+        PrimePredicate::Or(Box::new(self), Box::new(other))
+    }
+
+    /// Whether the prime `p` has this property.
+    pub fn matches(&self, p: u64) -> bool {
+        match self {
+            PrimePredicate::Twin => {
+                p.checked_sub(2).is_some_and(is_prime_mr)
+                    || p.checked_add(2).is_some_and(is_prime_mr)
+            }
+            PrimePredicate::SafePrime => p % 2 == 1 && is_prime_mr(p / 2),
+            PrimePredicate::SophieGermain => {
+                p.checked_mul(2).and_then(|q| q.checked_add(1)).is_some_and(is_prime_mr)
+            }
+            PrimePredicate::Palindromic => is_palindrome(p),
+            PrimePredicate::Mod(a, q) => p % q == a % q,
+            PrimePredicate::Custom(f) => f(p),
+            PrimePredicate::And(a, b) => a.matches(p) && b.matches(p),
+            PrimePredicate::Or(a, b) => a.matches(p) || b.matches(p),
+        }
+    }
+
+    /// The primes from `primes` that have this property, in the same order.
+    pub fn filter<I>(self, primes: I) -> impl Iterator<Item = u64>
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        primes.into_iter().filter(move |&p| self.matches(p))
+    }
+
+    /// Every prime with this property, in ascending order.
+    ///
+    /// Predicates that need `Palindromic` to hold look for primes among the palindromes, which
+    /// reach far further than going through every prime would, up to the largest prime that fits
+    /// in a `u64`. Any other predicate tries the odd numbers in turn, so nothing is cached and
+    /// memory stays constant however far the iterator is taken.
+    pub fn primes(self) -> impl Iterator<Item = u64> {
+        let candidates: Box<dyn Iterator<Item = u64>> = if self.needs_palindrome() {
+            Box::new(Palindromes { len: 1, half: 1 })
+        } else {
+            Box::new(std::iter::once(2).chain((3..=u64::MAX).step_by(2)))
+        };
+        self.filter(candidates.filter(|&n| is_prime_mr(n)))
+    }
+
+    // Whether only palindromes can have this property.
+    fn needs_palindrome(&self) -> bool {
+        match self {
+            PrimePredicate::Palindromic => true,
+            PrimePredicate::And(a, b) => a.needs_palindrome() || b.needs_palindrome(),
+            _ => false,
+        }
+    }
+}
+
+// The palindromes that can be prime, in ascending order: 2, 3, 5, 7, 11, and those with an odd
+// number of digits whose first digit is odd and not 5. Every other palindrome with an even number
+// of digits is a multiple of 11, and the rest end in an even digit or 5.
+struct Palindromes {
+    // Palindromes of `len` digits are built from their first `(len + 1) / 2` digits, `half`.
+    len: u32,
+    half: u64,
+}
+
+impl Iterator for Palindromes {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        while self.len <= MAX_DIGITS {
+            let h = self.len.div_ceil(2);
+            if self.half == 10u64.pow(h) {
+                self.len += 2;
+                self.half = 10u64.pow(self.len.div_ceil(2) - 1);
+                if self.len == 3 {
+                    return Some(11);
+                }
+                continue;
+            }
+            let lead = self.half / 10u64.pow(h - 1);
+            if self.len > 1 && (lead.is_multiple_of(2) || lead == 5) {
+                self.half = (lead + 1) * 10u64.pow(h - 1);
+                continue;
+            }
+            let mut n = self.half;
+            let mut rest = self.half / 10;
+            while rest > 0 {
+                n = n * 10 + rest % 10;
+                rest /= 10;
+            }
+            self.half += 1;
+            if self.len > 1 || [2, 3, 5, 7].contains(&n) {
+                return Some(n);
+            }
+        }
+        None
+    }
+}
+
+fn is_palindrome(n: u64) -> bool {
+    let mut rest = n;
+    let mut reversed = 0u128;
+    while rest > 0 {
+        reversed = reversed * 10 + u128::from(rest % 10);
+        rest /= 10;
+    }
+    reversed == u128::from(n)
+}
@@ -0,0 +1,13 @@
+/*!
+The traits, types and functions most code needs, for importing all at once.
+
+```
+use primes::prelude::*;
+
+let mut pset = DefaultPrimes::new();
+assert_eq!(pset.get(3), 7);
+assert_eq!(factors(12), vec![2, 2, 3]);
+```
+*/
+
+pub use crate::{factors, factors_unique, global, is_prime, DefaultPrimes, PrimeSet, PrimeSetBasics};
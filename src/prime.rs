@@ -0,0 +1,112 @@
+//! A number that is known to be prime.
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+
+use crate::arith::is_prime_mr;
+use crate::divisors::prime_powers;
+use crate::{PrimeModulus, LARGEST_U64_PRIME};
+
+/**
+A number that has been checked to be prime.
+
+Code that assumes its input is prime can take a `Prime` instead of a `u64`, so the assumption is
+checked once, where the value is made, instead of being trusted everywhere it's passed. Like
+`PrimeModulus`, construction runs a Miller–Rabin test that is exact over the whole `u64` range, and
+is a `const fn`:
+
+```
+use primes::Prime;
+use std::convert::TryFrom;
+
+const P: Prime = Prime::from_prime(1_000_003);
+assert_eq!(P.get(), 1_000_003);
+assert_eq!(P.next().map(Prime::get), Some(1_000_033));
+assert!(Prime::try_from(1_000_001).is_err());
+```
+**/
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Prime(u64);
+
+impl Prime {
+    /// `n` as a `Prime`, or `None` if it isn't prime.
+    pub const fn new(n: u64) -> Option<Prime> {
+        if is_prime_mr(n) {
+            Some(Prime(n))
+        } else {
+            None
+        }
+    }
+
+    /// `n` as a `Prime`, for constants.
+    ///
+    /// Panics if `n` isn't prime, which in a `const` item is a compile error.
+    pub const fn from_prime(n: u64) -> Prime {
+        match Prime::new(n) {
+            Some(p) => p,
+            None => panic!("number is not prime"),
+        }
+    }
+
+    /// The prime itself.
+    pub const fn get(self) -> u64 {
+        self.0
+    }
+
+    /// The next prime after this one, or `None` for `LARGEST_U64_PRIME`.
+    pub fn next(self) -> Option<Prime> {
+        if self.0 == LARGEST_U64_PRIME {
+            return None;
+        }
+        (self.0 + 1..).find_map(Prime::new)
+    }
+
+    /// The prime before this one, or `None` for 2.
+    pub fn prev(self) -> Option<Prime> {
+        (2..self.0).rev().find_map(Prime::new)
+    }
+}
+
+/// The error from converting a number that isn't prime into a `Prime`, holding that number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NotPrime(pub u64);
+
+impl fmt::Display for NotPrime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} is not prime", self.0)
+    }
+}
+
+impl Error for NotPrime {}
+
+impl TryFrom<u64> for Prime {
+    type Error = NotPrime;
+    fn try_from(n: u64) -> Result<Prime, NotPrime> {
+        Prime::new(n).ok_or(NotPrime(n))
+    }
+}
+
+impl From<Prime> for u64 {
+    fn from(p: Prime) -> u64 {
+        p.0
+    }
+}
+
+impl From<Prime> for PrimeModulus {
+    fn from(p: Prime) -> PrimeModulus {
+        PrimeModulus(p.0)
+    }
+}
+
+impl fmt::Display for Prime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+/// The prime factorization of `n` as `(prime, exponent)` pairs, in ascending order of prime. Both
+/// 0 and 1 have no factors.
+pub fn factors_exp(n: u64) -> Vec<(Prime, u32)> {
+    prime_powers(n).into_iter().map(|(p, e)| (Prime(p), e)).collect()
+}
@@ -0,0 +1,74 @@
+//! Progress reports from long-running operations, and a way to stop them part of the way through.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/**
+Where a long-running operation reports how far it has got, and finds out whether to carry on.
+
+The operations that take one, such as `PrimeSet::expand_to_with_progress`, `prime_pi_with_progress`
+and `Factorizer::factor_with_progress`, call it every so often with the fraction of the work done
+so far, from 0 to 1. Returning `false` from the callback stops the operation at that point, and
+it returns `PrimeError::Cancelled` (or, for the factorizer, gives up on whatever is left). Once
+stopped, a `Progress` stays stopped.
+
+```
+use primes::{prime_pi_with_progress, PrimeError, Progress};
+
+let mut reports = Vec::new();
+let mut progress = Progress::new(|done| {
+    reports.push(done);
+    done < 0.5
+});
+assert_eq!(prime_pi_with_progress(100_000_000, &mut progress), Err(PrimeError::Cancelled));
+assert!(progress.is_cancelled());
+drop(progress);
+assert!(reports.windows(2).all(|w| w[0] <= w[1]));
+```
+**/
+pub struct Progress<'a> {
+    report: Box<dyn FnMut(f64) -> bool + 'a>,
+    cancelled: bool,
+}
+
+impl<'a> Progress<'a> {
+    /// Progress reported to `report`, which returns whether to carry on.
+    pub fn new<F: FnMut(f64) -> bool + 'a>(report: F) -> Progress<'a> {
+        Progress {
+            report: Box::new(report),
+            cancelled: false,
+        }
+    }
+
+    /// Progress that isn't reported anywhere, but stops once `flag` is set, from this thread or
+    /// any other: a cancellation token for a UI's cancel button, or a server's request timeout.
+    pub fn cancel_flag(flag: &'a AtomicBool) -> Progress<'a> {
+        Progress::new(move |_| !flag.load(Ordering::Relaxed))
+    }
+
+    /// Whether the operation has been told to stop.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    // Reports that `done` of the work is done, returning whether to carry on.
+    pub(crate) fn report(&mut self, done: f64) -> bool {
+        if !self.cancelled && !(self.report)(done.clamp(0.0, 1.0)) {
+            self.cancelled = true;
+        }
+        !self.cancelled
+    }
+}
+
+// Never stops, and reports to nowhere.
+impl Default for Progress<'_> {
+    fn default() -> Self {
+        Progress::new(|_| true)
+    }
+}
+
+impl fmt::Debug for Progress<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Progress").field("cancelled", &self.cancelled).finish()
+    }
+}
@@ -0,0 +1,141 @@
+//! Composites that fool the Fermat and Miller–Rabin tests, and Miller–Rabin with chosen witnesses.
+
+use crate::arith::{is_prime_mr, mod_pow, mul_mod};
+use crate::Factorizer;
+
+/**
+Whether `n` is a Fermat pseudoprime to base `a`: composite, but with `a^(n-1) ≡ 1 (mod n)`, so
+that Fermat's test with `a` takes it for a prime. Such an `a` is a Fermat liar for `n`.
+**/
+pub fn is_pseudoprime_base(n: u64, a: u64) -> bool {
+    n > 3 && !is_prime_mr(n) && mod_pow(a, n - 1, n) == 1
+}
+
+/**
+Whether `n` is a strong pseudoprime to base `a`: odd and composite, but passing the Miller–Rabin
+round with `a`. Writing `n - 1 = d 2^s` with `d` odd, that round passes when `a^d ≡ 1` or
+`a^(d 2^r) ≡ -1 (mod n)` for some `r < s`. Every strong pseudoprime to a base is also a Fermat
+pseudoprime to it, but at most a quarter of the bases are strong liars for any `n`.
+**/
+pub fn is_strong_pseudoprime_base(n: u64, a: u64) -> bool {
+    if n < 5 || n.is_multiple_of(2) || a.is_multiple_of(n) || is_prime_mr(n) {
+        return false;
+    }
+    passes_round(n, a)
+}
+
+// Whether the odd `n` passes the Miller–Rabin round with base `a`. A base that is a multiple of `n`
+// says nothing, and passes.
+fn passes_round(n: u64, a: u64) -> bool {
+    if a.is_multiple_of(n) {
+        return true;
+    }
+    let s = (n - 1).trailing_zeros();
+    let mut x = mod_pow(a, (n - 1) >> s, n);
+    if x == 1 || x == n - 1 {
+        return true;
+    }
+    for _ in 1..s {
+        x = mul_mod(x, x, n);
+        if x == n - 1 {
+            return true;
+        }
+    }
+    false
+}
+
+/**
+Whether `n` is a Carmichael number: composite, but a Fermat pseudoprime to every base coprime to
+it.
+
+Primes, and everything that isn't a Fermat pseudoprime to base 2, are ruled out first, so only the
+rare numbers that are get factored, by `Factorizer::complete`. Those are then checked against
+Korselt's criterion: that `n` is squarefree with at least two prime factors, and `p - 1` divides
+`n - 1` for each of them.
+**/
+pub fn is_carmichael(n: u64) -> bool {
+    if n < 3 || n.is_multiple_of(2) || is_prime_mr(n) || mod_pow(2, n - 1, n) != 1 {
+        return false;
+    }
+    let factors = Factorizer::complete().factor(n).primes;
+    factors.len() > 1
+        && factors.windows(2).all(|w| w[0] != w[1])
+        && factors.iter().all(|&p| (n - 1).is_multiple_of(p - 1))
+}
+
+/**
+Jim Sinclair's seven witnesses, with which Miller–Rabin is correct for every `n` below 2^64, where
+the first twelve primes would be needed otherwise.
+**/
+pub const SINCLAIR_WITNESSES: [u64; 7] = [2, 325, 9375, 28178, 450775, 9780504, 1795265022];
+
+/**
+The Miller–Rabin test with a fixed set of witnesses, for reproducing the published deterministic
+bounds, or checking new witness sets against them.
+
+A number passes when it passes the round for every witness, each reduced modulo `n` first, as the
+published bounds assume; a witness that reduces to 0 is skipped. Every prime passes, and a witness
+set is deterministic below a bound when no composite below it passes. For the first `k` primes as
+witnesses (see `first_primes`), the smallest composites that pass are (OEIS A014233):
+
+| `k` | witnesses | smallest composite passing |
+|-----|-----------|----------------------------|
+| 1   | 2         | 2,047 |
+| 2   | 2, 3      | 1,373,653 |
+| 3   | up to 5   | 25,326,001 |
+| 4   | up to 7   | 3,215,031,751 |
+| 5   | up to 11  | 2,152,302,898,747 |
+| 6   | up to 13  | 3,474,749,660,383 |
+| 7–8 | up to 17 or 19 | 341,550,071,728,321 |
+| 9–11 | up to 23, 29 or 31 | 3,825,123,056,546,413,051 |
+| 12  | up to 37  | none below 2^64 |
+
+```
+use primes::{MillerRabin, SINCLAIR_WITNESSES};
+
+let three = MillerRabin::first_primes(3);
+assert!(three.is_prime(25_325_981));
+assert!(three.is_prime(25_326_001)); // 2251 × 11251, the first composite to get through
+assert!(!MillerRabin::first_primes(4).is_prime(25_326_001));
+assert!(!MillerRabin::new(&SINCLAIR_WITNESSES).is_prime(3_825_123_056_546_413_051));
+```
+**/
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MillerRabin {
+    witnesses: Vec<u64>,
+}
+
+impl MillerRabin {
+    /// The test with `witnesses`, tried in the order given.
+    pub fn new(witnesses: &[u64]) -> MillerRabin {
+        MillerRabin {
+            witnesses: witnesses.to_vec(),
+        }
+    }
+
+    /// The test with the first `k` primes as witnesses.
+    pub fn first_primes(k: usize) -> MillerRabin {
+        let witnesses = (2..).filter(|&a| is_prime_mr(a)).take(k).collect();
+        MillerRabin { witnesses }
+    }
+
+    /// The witnesses, in the order they are tried.
+    pub fn witnesses(&self) -> &[u64] {
+        &self.witnesses
+    }
+
+    /// Whether `n` passes the round for every witness. Numbers below 2 never pass, 2 always does,
+    /// and other even numbers never do.
+    pub fn is_prime(&self, n: u64) -> bool {
+        is_prime_with_witnesses(n, &self.witnesses)
+    }
+}
+
+/// Whether `n` passes the Miller–Rabin round for every base in `witnesses`, as with
+/// `MillerRabin::is_prime`.
+pub fn is_prime_with_witnesses(n: u64, witnesses: &[u64]) -> bool {
+    if n < 4 || n.is_multiple_of(2) {
+        return n == 2 || n == 3;
+    }
+    witnesses.iter().all(|&a| passes_round(n, a))
+}
@@ -0,0 +1,420 @@
+//! The self-initializing quadratic sieve, for composites beyond what SQUFOF and rho handle well.
+
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+
+use crate::arith::{gcd_wide, is_prime_wide, mod_inv, mul_mod_wide, pow_mod_wide};
+use crate::range::sieve_range;
+use crate::{jacobi, sqrt_mod, squfof};
+
+/// Below this, SQUFOF is quicker, and there are too few primes to build polynomials from.
+const SQUFOF_BELOW: u128 = 1 << 42;
+
+/// Leftover cofactors up to this many times the largest factor base prime are kept as partial
+/// relations, to be paired up with others sharing the same large prime.
+const LARGE_PRIME_FACTOR: u64 = 64;
+
+/**
+A nontrivial factor of `n` by the self-initializing quadratic sieve (SIQS), or `None` if `n` is
+prime or below 4, a power of a prime, 2^126 or more, or in the rare case that the sieve runs out of
+polynomials.
+
+The quadratic sieve collects many `x` for which `(ax + b)² - n` factors completely over a base of
+small primes, then combines them by linear algebra over GF(2) into a congruence of squares
+`X² ≡ Y² (mod n)`, which usually gives a factor as `gcd(X - Y, n)`. Its running time depends only on
+the size of `n`: a few milliseconds for 90 bits and tens of them for 120 in a release build, where
+rho and SQUFOF would take minutes to hours. Each polynomial is switched to the next in a few
+additions per prime, and cofactors with one prime a little past the factor base are paired up on
+that prime. Numbers below 2^42 go to `squfof`, which is quicker there, and numbers beyond 64 bits
+are first checked for primality by a Miller–Rabin test that is exact up to 3.3 × 10^24 and probable
+past it.
+
+```
+use primes::quadratic_sieve;
+
+let n = 1_000_000_007u128 * 998_244_353 * 1_000_000_009;
+let f = quadratic_sieve(n).unwrap();
+assert!(f > 1 && f < n && n % f == 0);
+```
+**/
+pub fn quadratic_sieve(n: u128) -> Option<u128> {
+    if !(4..1 << 126).contains(&n) || is_prime_wide(n) {
+        return None;
+    }
+    if n.is_multiple_of(2) {
+        return Some(2);
+    }
+    if n < SQUFOF_BELOW {
+        return squfof(n as u64).map(u128::from);
+    }
+    let s = n.isqrt();
+    if s * s == n {
+        return Some(s);
+    }
+    match Siqs::new(n) {
+        Ok(siqs) => siqs.run(),
+        Err(found) => found,
+    }
+}
+
+// The size of the factor base and half the width of the sieve interval, by the bits of `n`.
+fn parameters(bits: u32) -> (usize, usize) {
+    match bits {
+        0..=50 => (60, 1 << 12),
+        51..=60 => (100, 1 << 13),
+        61..=70 => (160, 1 << 14),
+        71..=80 => (240, 1 << 15),
+        81..=90 => (360, 1 << 15),
+        91..=100 => (520, 1 << 16),
+        101..=110 => (760, 1 << 16),
+        _ => (1100, 1 << 16),
+    }
+}
+
+// An `x` with `x² ≡ q square² (mod n)`, where `q` is the product of the factor base primes at
+// `factors` (with repeats, and 0 standing for -1). `x` is reduced modulo `n`.
+#[derive(Clone, Debug)]
+struct Relation {
+    x: u128,
+    factors: Vec<usize>,
+    square: u128,
+}
+
+struct Siqs {
+    n: u128,
+    // The factor base: a placeholder 1 standing for -1, then 2, then the odd primes modulo which
+    // `n` is a square, each with a square root of `n` modulo it and its logarithm for the sieve.
+    primes: Vec<u64>,
+    roots: Vec<u64>,
+    logs: Vec<u8>,
+    half_width: usize,
+    threshold: u8,
+    large_bound: u64,
+    // For picking the primes that `A` is made of, without picking the same ones twice.
+    rng: u64,
+    tried: HashSet<Vec<usize>>,
+}
+
+impl Siqs {
+    // Sets up the factor base. A factor of `n` turning up in it is returned as an error, or `None`
+    // if `n` is a power of it.
+    fn new(n: u128) -> Result<Siqs, Option<u128>> {
+        let bits = 128 - n.leading_zeros();
+        let (size, half_width) = parameters(bits);
+        let (mut primes, mut roots) = (vec![1, 2], vec![0, 1]);
+        let (mut found, mut from) = (None, 3);
+        // About half of all primes make it in, so this rarely needs a second pass.
+        while primes.len() < size && found.is_none() {
+            let to = from * 2 + 32 * size as u64;
+            sieve_range(from..to, |p| {
+                let r = (n % u128::from(p)) as u64;
+                if r == 0 {
+                    found = Some(u128::from(p));
+                    return false;
+                }
+                if jacobi(r, p) == 1 {
+                    primes.push(p);
+                    roots.push(sqrt_mod(r, p).unwrap());
+                }
+                primes.len() < size
+            });
+            from = to;
+        }
+        if let Some(p) = found {
+            return Err(if is_power_of(n, p) { None } else { Some(p) });
+        }
+        let logs = primes.iter().map(|&p| (p as f64).log2().round() as u8).collect();
+        let large_bound = primes[size - 1] * LARGE_PRIME_FACTOR;
+        // The values sieved reach about `M √(n / 2)`. Leave room for a large prime, and a few
+        // bits for the powers of small primes and the rounding of the logarithms.
+        let top = (half_width as f64).log2() + f64::from(bits) / 2.0 - 0.5;
+        let threshold = (top - (large_bound as f64).log2() - 4.0).max(8.0) as u8;
+        Ok(Siqs {
+            n,
+            primes,
+            roots,
+            logs,
+            half_width,
+            threshold,
+            large_bound,
+            rng: n as u64 | 1,
+            tried: HashSet::new(),
+        })
+    }
+
+    fn run(mut self) -> Option<u128> {
+        let wanted = self.primes.len() + 16;
+        let mut relations = Vec::with_capacity(wanted);
+        let mut partials = HashMap::new();
+        let mut sieve = vec![0u8; 2 * self.half_width];
+        while relations.len() < wanted {
+            let qs = self.choose_a()?;
+            self.sieve_polynomials(&qs, &mut sieve, &mut relations, &mut partials);
+        }
+        self.combine(&relations)
+    }
+
+    // The indexes of the primes to make up the next `A`, which should come to about `√(2n) / M`
+    // so that the values sieved are as small as they can be.
+    fn choose_a(&mut self) -> Option<Vec<usize>> {
+        let target = (2.0 * self.n as f64).sqrt().ln() - (self.half_width as f64).ln();
+        let lo = (self.primes.len() / 8).max(3);
+        let pool = lo..self.primes.len();
+        let logs: Vec<f64> = self.primes.iter().map(|&p| (p as f64).ln()).collect();
+        let ln = |i: usize| logs[i];
+        let count = (target / ln(lo + pool.len() * 2 / 3)).ceil().max(1.0) as usize;
+        let ideal = target / count as f64;
+        let near: Vec<usize> =
+            pool.clone().filter(|&i| (ln(i) - ideal).abs() < 0.7).collect();
+        let near = if near.len() < count + 2 { pool.clone().collect() } else { near };
+        for _ in 0..64 {
+            let mut chosen = Vec::with_capacity(count);
+            while chosen.len() + 1 < count.min(near.len()) {
+                let i = near[self.random() as usize % near.len()];
+                if !chosen.contains(&i) {
+                    chosen.push(i);
+                }
+            }
+            // The last prime makes up what the others fall short by, as nearly as it can.
+            let rest = target - chosen.iter().map(|&i| ln(i)).sum::<f64>();
+            let mut last: Vec<usize> = pool.clone().filter(|i| !chosen.contains(i)).collect();
+            last.sort_by(|&a, &b| (ln(a) - rest).abs().total_cmp(&(ln(b) - rest).abs()));
+            for i in last {
+                let mut key = chosen.clone();
+                key.push(i);
+                key.sort_unstable();
+                if self.tried.insert(key.clone()) {
+                    return Some(key);
+                }
+            }
+        }
+        None
+    }
+
+    // Xorshift, which is plenty for spreading the choice of primes for `A` around.
+    fn random(&mut self) -> u64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        self.rng
+    }
+
+    // Sieves all `2^(s - 1)` polynomials `(A x + B)² - n` for the `A` made of the primes at `qs`,
+    // stepping from one `B` to the next in Gray code order.
+    fn sieve_polynomials(
+        &self,
+        qs: &[usize],
+        sieve: &mut [u8],
+        relations: &mut Vec<Relation>,
+        partials: &mut HashMap<u64, Relation>,
+    ) {
+        let (n, m) = (self.n, self.half_width as u64);
+        let len = self.primes.len();
+        let a: u128 = qs.iter().map(|&i| u128::from(self.primes[i])).product();
+        // `B_l` is `A / q_l` times a root of `n` modulo `q_l`, and 0 modulo the other `q`s, so
+        // that `B = ±B_1 ± ... ± B_s` has `B² ≡ n (mod A)` whatever the signs.
+        let bs: Vec<i128> = qs
+            .iter()
+            .map(|&i| {
+                let q = self.primes[i];
+                let rest = a / u128::from(q);
+                let inv = mod_inv((rest % u128::from(q)) as u64, q).unwrap();
+                let g = (u128::from(self.roots[i]) * u128::from(inv) % u128::from(q)) as u64;
+                (rest * u128::from(g.min(q - g))) as i128
+            })
+            .collect();
+        let mut b: i128 = bs.iter().sum();
+
+        // Where the sieve starts for each prime, and how far that moves with each `B_l`.
+        let mut in_a = vec![false; len];
+        for &i in qs {
+            in_a[i] = true;
+        }
+        let mut starts = vec![(0, 0); len];
+        let mut steps = vec![vec![0; len]; bs.len()];
+        for i in 2..len {
+            if in_a[i] {
+                continue;
+            }
+            let p = self.primes[i];
+            let inv = mod_inv((a % u128::from(p)) as u64, p).unwrap();
+            let at = |r: u64| {
+                let b = b.rem_euclid(i128::from(p)) as u64;
+                let x = (r + p - b) % p * inv % p;
+                (x + m % p) % p
+            };
+            starts[i] = (at(self.roots[i]), at(p - self.roots[i]));
+            for (l, &bl) in bs.iter().enumerate() {
+                steps[l][i] = 2 * (bl.rem_euclid(i128::from(p)) as u64) % p * inv % p;
+            }
+        }
+
+        for k in 0..1usize << (bs.len() - 1) {
+            if k > 0 {
+                // Flip the sign of one `B_l`, and move every start to match.
+                let bit = k.trailing_zeros() as usize;
+                let negative = ((k ^ (k >> 1)) >> bit) & 1 == 1;
+                let l = bit + 1;
+                b += if negative { -2 * bs[l] } else { 2 * bs[l] };
+                for i in 2..len {
+                    let p = self.primes[i];
+                    let d = steps[l][i];
+                    let (r1, r2) = &mut starts[i];
+                    if negative {
+                        *r1 = (*r1 + d) % p;
+                        *r2 = (*r2 + d) % p;
+                    } else {
+                        *r1 = (*r1 + p - d) % p;
+                        *r2 = (*r2 + p - d) % p;
+                    }
+                }
+            }
+            let c = (b * b - n as i128) / a as i128;
+
+            sieve.fill(0);
+            for i in 2..len {
+                if in_a[i] {
+                    continue;
+                }
+                let p = self.primes[i] as usize;
+                let log = self.logs[i];
+                let (r1, r2) = starts[i];
+                for r in [r1 as usize, r2 as usize] {
+                    for v in sieve.iter_mut().skip(r).step_by(p) {
+                        *v += log;
+                    }
+                }
+            }
+
+            for (j, _) in sieve.iter().enumerate().filter(|&(_, &v)| v >= self.threshold) {
+                let x = j as i128 - m as i128;
+                let value = (a as i128 * x + 2 * b) * x + c;
+                let relation = self.trial_divide(value, qs, &in_a, &starts, j);
+                let Some((factors, large)) = relation else { continue };
+                let x = (a as i128 * x + b).unsigned_abs() % n;
+                let relation = Relation { x, factors, square: 1 };
+                if large == 1 {
+                    relations.push(relation);
+                } else if let Some(other) = partials.get(&large) {
+                    let Relation { x: y, factors: more, .. } = other;
+                    relations.push(Relation {
+                        x: mul_mod_wide(x, *y, n),
+                        factors: [relation.factors, more.clone()].concat(),
+                        square: u128::from(large),
+                    });
+                } else {
+                    partials.insert(large, relation);
+                }
+            }
+        }
+    }
+
+    // Factors `A value` over the factor base, where `value` is the polynomial at sieve position
+    // `j`, returning the indexes of its factors and what's left: 1, or a prime below the large
+    // prime bound. `None` if anything larger is left.
+    fn trial_divide(
+        &self,
+        value: i128,
+        qs: &[usize],
+        in_a: &[bool],
+        starts: &[(u64, u64)],
+        j: usize,
+    ) -> Option<(Vec<usize>, u64)> {
+        let mut factors = qs.to_vec();
+        if value < 0 {
+            factors.push(0);
+        }
+        let mut v = value.unsigned_abs();
+        let twos = v.trailing_zeros();
+        v >>= twos;
+        factors.extend(std::iter::repeat_n(1, twos as usize));
+        for i in 2..self.primes.len() {
+            let p = self.primes[i];
+            let at = j as u64 % p;
+            if in_a[i] || at == starts[i].0 || at == starts[i].1 {
+                while v.is_multiple_of(u128::from(p)) {
+                    v /= u128::from(p);
+                    factors.push(i);
+                }
+            }
+        }
+        // Anything left below the large prime bound is past every factor base prime, and below
+        // the square of the largest, so it's prime.
+        match u64::try_from(v) {
+            Ok(v) if v < self.large_bound => Some((factors, v)),
+            _ => None,
+        }
+    }
+
+    // Finds sets of relations whose product is a square, by Gaussian elimination over GF(2), and
+    // tries each for a factor until one gives it.
+    fn combine(&self, relations: &[Relation]) -> Option<u128> {
+        let n = self.n;
+        let cols = self.primes.len();
+        let mut rows: Vec<Vec<u64>> = relations
+            .iter()
+            .map(|r| {
+                let mut row = vec![0u64; cols.div_ceil(64)];
+                for &i in &r.factors {
+                    row[i / 64] ^= 1 << (i % 64);
+                }
+                row
+            })
+            .collect();
+        // Which relations each row is the sum of.
+        let mut history: Vec<Vec<u64>> = (0..rows.len())
+            .map(|k| {
+                let mut h = vec![0u64; rows.len().div_ceil(64)];
+                h[k / 64] |= 1 << (k % 64);
+                h
+            })
+            .collect();
+        let mut pivot = vec![false; rows.len()];
+        for col in 0..cols {
+            let bit = |row: &[u64]| row[col / 64] >> (col % 64) & 1 == 1;
+            let Some(p) = (0..rows.len()).find(|&k| !pivot[k] && bit(&rows[k])) else {
+                continue;
+            };
+            pivot[p] = true;
+            let (row, hist) = (rows[p].clone(), history[p].clone());
+            for k in 0..rows.len() {
+                if k != p && bit(&rows[k]) {
+                    rows[k].iter_mut().zip(&row).for_each(|(a, b)| *a ^= b);
+                    history[k].iter_mut().zip(&hist).for_each(|(a, b)| *a ^= b);
+                }
+            }
+        }
+
+        // Every row that wasn't a pivot has been cleared: its relations multiply to a square.
+        (0..rows.len()).filter(|&k| !pivot[k]).find_map(|k| {
+            let (mut x, mut y) = (1, 1);
+            let mut counts = vec![0u32; cols];
+            for (r, relation) in relations.iter().enumerate() {
+                if history[k][r / 64] >> (r % 64) & 1 == 1 {
+                    x = mul_mod_wide(x, relation.x, n);
+                    y = mul_mod_wide(y, relation.square, n);
+                    for &i in &relation.factors {
+                        counts[i] += 1;
+                    }
+                }
+            }
+            for (i, &count) in counts.iter().enumerate().skip(1) {
+                let p = u128::from(self.primes[i]);
+                y = mul_mod_wide(y, pow_mod_wide(p, u128::from(count / 2), n), n);
+            }
+            let g = gcd_wide(x.abs_diff(y), n);
+            if g > 1 && g < n {
+                Some(g)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+fn is_power_of(mut n: u128, p: u128) -> bool {
+    while n.is_multiple_of(p) {
+        n /= p;
+    }
+    n == 1
+}
@@ -0,0 +1,67 @@
+/*!
+Zero-setup functions for scripts and one-off calculations.
+
+Everything here works without creating a prime set: primes are looked up in, and added to, the
+process-wide cache from `global()`, and the best method for each question is picked automatically.
+With the `tiny` feature the cache is only grown to cover the primes below 2^16; anything past that
+is worked out afresh on every call.
+
+```
+use primes::quick;
+
+assert_eq!(quick::nth(0), 2);
+assert_eq!(quick::below(20), vec![2, 3, 5, 7, 11, 13, 17, 19]);
+assert_eq!(quick::factor(60), vec![2, 2, 3, 5]);
+assert!(quick::is_prime(97));
+```
+*/
+
+use crate::arith::is_prime_mr;
+use crate::range::sieve_range;
+use crate::shared::CACHE_LIMIT;
+use crate::{global, DefaultPrimes, Factorizer, PrimeSet, PrimeSetBasics};
+
+/// The `k`th prime, counting from zero, so `nth(0)` is 2.
+pub fn nth(k: usize) -> u64 {
+    global().get(k)
+}
+
+/// All primes less than `n`, in ascending order.
+pub fn below(n: u64) -> Vec<u64> {
+    let primes = |set: &DefaultPrimes| {
+        let lst = set.list();
+        lst[..lst.partition_point(|&p| p < n)].to_vec()
+    };
+    let covered = |set: &DefaultPrimes| set.list().last().is_some_and(|&p| p >= n);
+
+    if let Some(found) = global().read(|set| if covered(set) { Some(primes(set)) } else { None }) {
+        return found;
+    }
+    if CACHE_LIMIT.is_some_and(|limit| n >= limit) {
+        let mut found = Vec::new();
+        sieve_range(0..n, |p| {
+            found.push(p);
+            true
+        });
+        return found;
+    }
+    global().write(|set| {
+        set.expand_to(n);
+        primes(set)
+    })
+}
+
+/// The prime factors of `n`, in ascending order and including repeats, found by trial division
+/// of the small ones and Pollard's rho for the rest, as `Factorizer::complete` does. Both 0 and 1
+/// have none.
+pub fn factor(n: u64) -> Vec<u64> {
+    Factorizer::complete().factor(n).primes
+}
+
+/// Tests whether `n` is prime, by looking it up if the cache already reaches it and by a
+/// Miller–Rabin test that is exact for every `u64` otherwise.
+pub fn is_prime(n: u64) -> bool {
+    global()
+        .read(|set| set.find_vec(n).map(|(_, p)| p == n))
+        .unwrap_or_else(|| is_prime_mr(n))
+}
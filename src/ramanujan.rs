@@ -0,0 +1,79 @@
+//! Ramanujan primes, the primes behind the strengthened forms of Bertrand's postulate.
+
+use crate::range::sieve_range;
+use crate::{nth_prime_bounds, pi_bounds};
+
+/**
+The `n`th Ramanujan prime, counting from 1: the smallest `R` such that there are at least `n` primes
+in `(x/2, x]` for every `x` ≥ `R`.
+
+So `R_1` = 2 is Bertrand's postulate, and the sequence goes on 11, 17, 29, 41. Since `R_n` is less
+than the `3n`th prime (Laishram, 2010), this sieves up to a bound on that and follows
+`π(x) - π(x/2)` all the way, which takes a `u64` for every prime up to there.
+
+Panics if `n` is zero.
+**/
+pub fn nth_ramanujan_prime(n: u64) -> u64 {
+    assert!(n > 0, "Ramanujan primes are counted from 1");
+    *first_ramanujan_primes(n).last().unwrap()
+}
+
+/**
+Every Ramanujan prime below `limit`, in ascending order; see `nth_ramanujan_prime`.
+
+Only `π(limit) - π(limit/2)` Ramanujan primes can be below `limit`, so this works out that many (or
+a few more, since the counts are bounded rather than exact) and keeps the ones that are.
+**/
+pub fn ramanujan_primes_below(limit: u64) -> Vec<u64> {
+    let count = pi_bounds(limit).1.saturating_sub(pi_bounds(limit / 2).0);
+    let mut found = first_ramanujan_primes(count);
+    found.retain(|&r| r < limit);
+    found
+}
+
+// The first `count` Ramanujan primes.
+fn first_ramanujan_primes(count: u64) -> Vec<u64> {
+    if count == 0 {
+        return Vec::new();
+    }
+    let horizon = nth_prime_bounds(3 * count).1;
+    let mut primes = Vec::new();
+    sieve_range(0..horizon + 1, |p| {
+        primes.push(p);
+        true
+    });
+
+    // f(x) = π(x) - π(x/2) only goes up at primes and only goes down at twice a prime, so walking
+    // through those in order finds the last `x` at which f(x) is each value. R_n is one past the
+    // last `x` at which it is still below `n`.
+    let mut last = vec![0; count as usize];
+    let (mut up, mut down, mut f) = (0, 0, 0);
+    loop {
+        let rise = primes.get(up).cloned();
+        let fall = primes.get(down).map(|&q| 2 * q).filter(|&x| x <= horizon);
+        let x = match (rise, fall) {
+            (Some(p), Some(q2)) => p.min(q2),
+            (Some(p), None) => p,
+            (None, Some(q2)) => q2,
+            (None, None) => break,
+        };
+        if let Some(slot) = last.get_mut(f) {
+            *slot = x - 1;
+        }
+        if Some(x) == rise {
+            f += 1;
+            up += 1;
+        } else {
+            f -= 1;
+            down += 1;
+        }
+    }
+
+    let mut below = 0;
+    last.iter()
+        .map(|&x| {
+            below = below.max(x);
+            below + 1
+        })
+        .collect()
+}
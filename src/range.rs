@@ -0,0 +1,288 @@
+//! Primes in an arbitrary range, found with a segmented sieve over just that range.
+
+use std::ops::Range;
+
+use crate::arith::is_prime_mr;
+use crate::sieve::{mark_composites, unmarked};
+use crate::{PrimeError, PrimeSet, PrimeSetBasics, Progress, Sieve};
+
+/// Segment size in words; 2^15 odd numbers per segment, in 4 KiB of stack.
+#[cfg(not(feature = "tiny"))]
+const SEGMENT_WORDS: usize = 1 << 9;
+#[cfg(feature = "tiny")]
+const SEGMENT_WORDS: usize = 1 << 2;
+
+/// Ranges narrower than the square root of their end divided by this are tested number by number
+/// rather than sieved, as finding the base primes would take longer than the range itself.
+const NARROW_RATIO: u64 = 64;
+
+/// `prime_pi` counts by Lucy's method as long as the square root of `x` is at most this, which
+/// keeps its two tables of counts to 64 MiB.
+#[cfg(not(feature = "tiny"))]
+const LUCY_ROOT_LIMIT: u64 = 1 << 22;
+#[cfg(feature = "tiny")]
+const LUCY_ROOT_LIMIT: u64 = 1 << 12;
+
+/**
+Calls `f` on every prime in `range`, in ascending order, until it returns `false`.
+
+Returns whether the whole range was covered. Only the primes up to `sqrt(range.end)` are kept in
+memory; the range itself is sieved a segment at a time in a fixed-size buffer. A range far
+narrower than that square root, such as a few thousand numbers near `u64::MAX`, is instead checked
+one odd number at a time by Miller–Rabin.
+**/
+pub(crate) fn sieve_range<F: FnMut(u64) -> bool>(range: Range<u64>, mut f: F) -> bool {
+    let Range { start: lo, end: hi } = range;
+    if lo >= hi {
+        return true;
+    }
+    if hi - lo < hi.isqrt() / NARROW_RATIO {
+        return (lo..hi).filter(|&n| n == 2 || n % 2 == 1).filter(|&n| is_prime_mr(n)).all(f);
+    }
+    if lo <= 2 && 2 < hi && !f(2) {
+        return false;
+    }
+    sieve_odd(&base_primes(hi), lo..hi, f)
+}
+
+// The odd primes up to the square root of the last number before `hi`.
+fn base_primes(hi: u64) -> Vec<u64> {
+    let mut base = Sieve::new();
+    base.find(hi.saturating_sub(1).isqrt());
+    base.list()[1..].to_vec()
+}
+
+// Calls `f` on the odd primes in `range`, given every odd prime up to the square root of the last
+// number in it (such as those from `base_primes(range.end)`) in `base`.
+pub(crate) fn sieve_odd<F: FnMut(u64) -> bool>(base: &[u64], range: Range<u64>, mut f: F) -> bool {
+    let Range { start: lo, end: hi } = range;
+    let mut bits = [0u64; SEGMENT_WORDS];
+    let mut s = lo.max(3) | 1;
+    while s < hi {
+        let n = ((hi - s).div_ceil(2) as usize).min(SEGMENT_WORDS * 64);
+        mark_composites(base, s, n, &mut bits);
+
+        for j in unmarked(n, &bits) {
+            let c = s + 2 * j as u64;
+            if c != 1 && !f(c) {
+                return false;
+            }
+        }
+        s += 2 * n as u64;
+    }
+    true
+}
+
+/**
+Calls `f` on every prime below `limit`, in ascending order.
+
+Nothing is collected: the primes are sieved a segment at a time in a fixed-size buffer, and only
+those up to `sqrt(limit)` are kept, so memory stays at a few megabytes even for scans to 10^11
+and beyond, where a `Vec` of the primes would need tens of gigabytes.
+
+```
+use primes::for_each_prime_below;
+
+let (mut count, mut sum) = (0, 0);
+for_each_prime_below(1_000_000, |p| {
+    count += 1;
+    sum += p;
+});
+assert_eq!((count, sum), (78_498, 37_550_402_023));
+```
+**/
+pub fn for_each_prime_below<F: FnMut(u64)>(limit: u64, mut f: F) {
+    sieve_range(0..limit, |p| {
+        f(p);
+        true
+    });
+}
+
+// The number of odd primes in `range`, given every odd prime up to the square root of the last
+// number in it in `base`, counted by the bits `mark_composites` leaves clear.
+fn count_odd(base: &[u64], range: Range<u64>) -> u64 {
+    let Range { start: lo, end: hi } = range;
+    let mut bits = [0u64; SEGMENT_WORDS];
+    let mut s = lo.max(3) | 1;
+    let mut count = 0;
+    while s < hi {
+        let n = ((hi - s).div_ceil(2) as usize).min(SEGMENT_WORDS * 64);
+        mark_composites(base, s, n, &mut bits);
+        // The `simd` presieve marks whole words, so bits past `n` in the last one may be set.
+        let (full, rest) = (n / 64, n % 64);
+        let mut marked: u32 = bits[..full].iter().map(|w| w.count_ones()).sum();
+        if rest != 0 {
+            marked += (bits[full] & ((1 << rest) - 1)).count_ones();
+        }
+        count += n as u64 - u64::from(marked);
+        s += 2 * n as u64;
+    }
+    count
+}
+
+/**
+The number of primes up to and including `x`.
+
+Up to 2^44 (2^24 with the `tiny` feature) this is Lucy Hedgehog's method, which works out how
+many numbers up to each `x / i` survive sieving by each prime in turn, taking about `x^(3/4)` steps
+and two tables of `sqrt(x)` counts: 10^10 takes milliseconds. Past that the tables would grow too
+large, and the primes are counted a segment at a time by a segmented sieve instead, split across
+the rayon thread pool with the `parallel` feature.
+**/
+pub fn prime_pi(x: u64) -> u64 {
+    if x < 2 {
+        return 0;
+    }
+    if x.isqrt() <= LUCY_ROOT_LIMIT {
+        return lucy_pi(x);
+    }
+    let end = x.saturating_add(1);
+    1 + count_odd_from_base(&base_primes(end), end)
+}
+
+// Lucy Hedgehog's prime count. `small[v]` and `large[i]` start as the count of numbers from 2 to
+// `v` and to `x / i`, and sieving by each prime `p` takes off those whose smallest factor is `p`.
+fn lucy_pi(x: u64) -> u64 {
+    let r = x.isqrt() as usize;
+    let mut small: Vec<u64> = (0..=r as u64).map(|v| v.saturating_sub(1)).collect();
+    let mut large: Vec<u64> = (0..=r as u64).map(|i| (x / i.max(1)).saturating_sub(1)).collect();
+    for p in 2..=r {
+        if small[p] == small[p - 1] {
+            continue;
+        }
+        let below = small[p - 1];
+        let p2 = (p * p) as u64;
+        for i in 1..=r.min((x / p2) as usize) {
+            let d = i * p;
+            let count = if d <= r { large[d] } else { small[(x / d as u64) as usize] };
+            large[i] -= count - below;
+        }
+        for v in (p * p..=r).rev() {
+            small[v] -= small[v / p] - below;
+        }
+    }
+    large[1]
+}
+
+// The number of odd primes below `end`, with the base primes for it in `base`.
+#[cfg(not(all(feature = "parallel", not(feature = "tiny"))))]
+fn count_odd_from_base(base: &[u64], end: u64) -> u64 {
+    count_odd(base, 0..end)
+}
+
+#[cfg(all(feature = "parallel", not(feature = "tiny")))]
+fn count_odd_from_base(base: &[u64], end: u64) -> u64 {
+    use rayon::prelude::*;
+
+    const CHUNK: u64 = 1 << 24;
+    (0..end.div_ceil(CHUNK))
+        .into_par_iter()
+        .map(|i| count_odd(base, i * CHUNK..end.min((i * CHUNK).saturating_add(CHUNK))))
+        .sum()
+}
+
+/**
+Like `prime_pi`, but reporting how far the count has got to `progress` every 1/256th of the way
+(or every million numbers, if that is further), and stopping with `PrimeError::Cancelled` if told
+to.
+**/
+pub fn prime_pi_with_progress(x: u64, progress: &mut Progress<'_>) -> Result<u64, PrimeError> {
+    let end = x.saturating_add(1);
+    let step = (end / 256).max(1 << 20);
+    let base = base_primes(end);
+    let (mut count, mut lo) = (u64::from(end > 2), 0);
+    while lo < end {
+        if !progress.report(lo as f64 / end as f64) {
+            return Err(PrimeError::Cancelled);
+        }
+        let hi = lo.saturating_add(step).min(end);
+        count += count_odd(&base, lo..hi);
+        lo = hi;
+    }
+    progress.report(1.0);
+    Ok(count)
+}
+
+/**
+Calls `f` on every prime below `limit` from the rayon thread pool, like `for_each_prime_below` but
+with the range split across threads.
+
+The calls come in no particular order, and from several threads at once, so `f` can only share
+state through something like an atomic or a mutex; the usual pattern is to fold into per-thread
+totals. Each thread sieves its own stretch of the range in its own fixed-size buffer.
+
+With the `tiny` feature nothing is handed to the thread pool: the primes are passed to `f` in
+ascending order from the calling thread, as `for_each_prime_below` does.
+**/
+#[cfg(feature = "parallel")]
+pub fn par_for_each_prime_below<F: Fn(u64) + Sync>(limit: u64, f: F) {
+    #[cfg(not(feature = "tiny"))]
+    par_sieve_below(limit, f);
+    #[cfg(feature = "tiny")]
+    for_each_prime_below(limit, f);
+}
+
+#[cfg(all(feature = "parallel", not(feature = "tiny")))]
+fn par_sieve_below<F: Fn(u64) + Sync>(limit: u64, f: F) {
+    use rayon::prelude::*;
+
+    // Enough segments per task that the tasks are cheap to hand out.
+    const CHUNK: u64 = 1 << 24;
+
+    if limit > 2 {
+        f(2);
+    }
+    let base = base_primes(limit);
+    (0..limit.div_ceil(CHUNK)).into_par_iter().for_each(|i| {
+        let lo = i * CHUNK;
+        sieve_odd(&base, lo..limit.min(lo.saturating_add(CHUNK)), |p| {
+            f(p);
+            true
+        });
+    });
+}
+
+/**
+Writes the primes in `range` into `out`, in ascending order, until either runs out.
+
+Returns `(count, next)`: the number of primes written, and where to continue from to get the rest
+(`range.end` if the whole range fit). Nothing is allocated for the output, so `out` can live
+anywhere, including memory handed over from across an FFI boundary.
+**/
+pub fn fill_primes(range: Range<u64>, out: &mut [u64]) -> (usize, u64) {
+    let mut count = 0;
+    let mut next = range.end;
+    sieve_range(range, |p| {
+        if count == out.len() {
+            next = p;
+            return false;
+        }
+        out[count] = p;
+        count += 1;
+        true
+    });
+    (count, next)
+}
+
+/**
+The number of primes in each `bucket_width`-wide bucket of `range`, in order: the first bucket
+starts at `range.start`, and the last one is cut short at `range.end` if the width doesn't divide
+the length of the range.
+
+The whole range is covered by a single segmented sieve, counting as it goes, so this costs the same
+as finding the primes in it without keeping any of them, which is what density plots and the like
+want.
+
+Panics if `bucket_width` is zero.
+**/
+pub fn bucket_counts(range: Range<u64>, bucket_width: u64) -> Vec<u64> {
+    assert!(bucket_width > 0, "buckets must not be empty");
+    let len = range.end.saturating_sub(range.start);
+    let mut counts = vec![0; len.div_ceil(bucket_width) as usize];
+    let start = range.start;
+    sieve_range(range, |p| {
+        counts[((p - start) / bucket_width) as usize] += 1;
+        true
+    });
+    counts
+}
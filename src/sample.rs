@@ -0,0 +1,303 @@
+//! Random sampling of primes.
+
+use std::collections::BTreeSet;
+use std::ops::Range;
+
+use rand::Rng;
+
+use crate::arith::{gcd, is_prime_mr};
+use crate::range::sieve_range;
+
+/// The largest modulus the congruences in `PrimeConstraints` can combine to.
+const MAX_MODULUS: u64 = 1 << 20;
+
+/**
+`k` distinct primes below `n`, chosen uniformly at random, in ascending order.
+
+Every set of `k` primes is equally likely. Rather than drawing random numbers until enough of them
+turn out to be prime (which favours primes that follow long gaps), this counts the primes below
+`n`, picks `k` distinct indices among them, and sieves once more to pick those primes out. That
+takes two sieves up to `n`, but only memory for the sample.
+
+Panics if there are fewer than `k` primes below `n`.
+**/
+pub fn sample_primes_below<R: Rng + ?Sized>(n: u64, k: usize, rng: &mut R) -> Vec<u64> {
+    let mut count = 0;
+    sieve_range(0..n, |_| {
+        count += 1;
+        true
+    });
+    assert!(k <= count, "there are only {} primes below {}", count, n);
+
+    // Floyd's algorithm: `k` distinct indices out of `count`, with one draw each.
+    let mut picked = BTreeSet::new();
+    for j in count - k..count {
+        let t = rng.random_range(0..=j);
+        if !picked.insert(t) {
+            picked.insert(j);
+        }
+    }
+
+    let mut wanted = picked.into_iter().peekable();
+    let mut found = Vec::with_capacity(k);
+    let mut idx = 0;
+    sieve_range(0..n, |p| {
+        if wanted.peek() == Some(&idx) {
+            found.push(p);
+            wanted.next();
+        }
+        idx += 1;
+        wanted.peek().is_some()
+    });
+    found
+}
+
+/**
+`k` items chosen uniformly at random from `iter`, in ascending order, storing only the `k` items.
+
+This is reservoir sampling with geometric skips (Li's Algorithm L): after the first `k` items, the
+number of items to pass over before the next one enters the sample is drawn directly, and skipped
+with `Iterator::nth`. Only about `k ln(n/k)` of `n` items are ever looked at individually, and the
+crate's prime iterators jump over skipped primes in bulk, so sampling from an enormous range costs
+little more than generating it. Fewer than `k` items in `iter` are all returned.
+**/
+pub fn sample_from_stream<I, R>(iter: I, k: usize, rng: &mut R) -> Vec<u64>
+where
+    I: IntoIterator<Item = u64>,
+    R: Rng + ?Sized,
+{
+    if k == 0 {
+        return Vec::new();
+    }
+    let mut iter = iter.into_iter();
+    let mut reservoir: Vec<u64> = iter.by_ref().take(k).collect();
+    if reservoir.len() == k {
+        let mut w = (unit(rng).ln() / k as f64).exp();
+        loop {
+            let skip = (unit(rng).ln() / (-w).ln_1p()).floor();
+            let next = if skip < usize::MAX as f64 {
+                iter.nth(skip as usize)
+            } else {
+                None
+            };
+            let Some(item) = next else { break };
+            reservoir[rng.random_range(0..k)] = item;
+            w *= (unit(rng).ln() / k as f64).exp();
+        }
+    }
+    reservoir.sort_unstable();
+    reservoir
+}
+
+/// How likely `sample_prime_weighted` is to pick each prime.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Weight {
+    /// Every prime is equally likely.
+    Uniform,
+    /// Each prime `p` is picked with probability proportional to `1 / ln p`, its density in the
+    /// prime number theorem, so smaller primes are favoured.
+    LogInverse,
+}
+
+/**
+One prime from `range`, picked at random with the given `weight`, or `None` if there are no primes
+in `range`.
+
+The total weight of the primes in the range is summed over one sieve, a point is drawn below it,
+and a second sieve walks up to the prime it lands on. Nothing is kept in memory in between.
+**/
+pub fn sample_prime_weighted<R: Rng + ?Sized>(
+    range: Range<u64>,
+    weight: Weight,
+    rng: &mut R,
+) -> Option<u64> {
+    let w = |p: u64| match weight {
+        Weight::Uniform => 1.0,
+        Weight::LogInverse => 1.0 / (p as f64).ln(),
+    };
+    let (mut total, mut last) = (0.0, None);
+    sieve_range(range.clone(), |p| {
+        total += w(p);
+        last = Some(p);
+        true
+    });
+    last?;
+
+    let target = rng.random::<f64>() * total;
+    // If rounding leaves the target past the end, the walk stops at the last prime.
+    let mut acc = 0.0;
+    let mut found = None;
+    sieve_range(range, |p| {
+        acc += w(p);
+        found = Some(p);
+        acc <= target
+    });
+    found
+}
+
+/**
+Conditions on the primes `gen_prime_with` generates.
+
+Congruences such as `p ≡ 3 (mod 4)` or `p ≢ 1 (mod 8)` are combined into one list of allowed
+residues modulo the least common multiple of their moduli, and candidates are built directly in
+those residue classes, so no effort goes into numbers that would be thrown away and every prime
+that meets the conditions stays equally likely. Conditions that can't be written as congruences
+can be given as closures, which are checked after primality.
+
+```
+use primes::{gen_prime_with, PrimeConstraints};
+
+// A 40-bit prime that is 3 mod 4 but not 7 mod 8, and whose digits sum to an odd number.
+let constraints = PrimeConstraints::new()
+    .congruent_to(3, 4)
+    .not_congruent_to(7, 8)
+    .filter(|p| p.to_string().bytes().map(|d| u64::from(d - b'0')).sum::<u64>() % 2 == 1);
+let p = gen_prime_with(40, &constraints, &mut rand::rng()).unwrap();
+assert_eq!(p % 8, 3);
+```
+**/
+pub struct PrimeConstraints {
+    modulus: u64,
+    // The allowed residues modulo `modulus`, in ascending order.
+    residues: Vec<u64>,
+    filters: Vec<Box<dyn Fn(u64) -> bool>>,
+}
+
+impl PrimeConstraints {
+    /// No constraints at all.
+    pub fn new() -> PrimeConstraints {
+        PrimeConstraints {
+            modulus: 1,
+            residues: vec![0],
+            filters: Vec::new(),
+        }
+    }
+
+    /// Also require `p ≡ r (mod m)`.
+    ///
+    /// Panics if `m` is zero, or if the moduli so far combine to more than 2^20.
+    pub fn congruent_to(self, r: u64, m: u64) -> PrimeConstraints {
+        self.restrict(m, |x| x == r % m)
+    }
+
+    /// Also require `p ≢ r (mod m)`.
+    ///
+    /// Panics if `m` is zero, or if the moduli so far combine to more than 2^20.
+    pub fn not_congruent_to(self, r: u64, m: u64) -> PrimeConstraints {
+        self.restrict(m, |x| x != r % m)
+    }
+
+    /// Also require `f(p)` to hold.
+    pub fn filter<F: Fn(u64) -> bool + 'static>(mut self, f: F) -> PrimeConstraints {
+        self.filters.push(Box::new(f));
+        self
+    }
+
+    fn restrict<F: Fn(u64) -> bool>(mut self, m: u64, keep: F) -> PrimeConstraints {
+        (self.modulus, self.residues) = combine(self.modulus, &self.residues, m, keep);
+        self
+    }
+
+    fn accepts(&self, p: u64) -> bool {
+        let r = p % self.modulus;
+        self.residues.binary_search(&r).is_ok() && self.filters.iter().all(|f| f(p))
+    }
+}
+
+impl Default for PrimeConstraints {
+    fn default() -> PrimeConstraints {
+        PrimeConstraints::new()
+    }
+}
+
+// The residues modulo `lcm(modulus, m)` that are in `residues` modulo `modulus` and pass `keep`
+// modulo `m`.
+fn combine<F: Fn(u64) -> bool>(modulus: u64, residues: &[u64], m: u64, keep: F) -> (u64, Vec<u64>) {
+    assert!(m > 0, "congruence modulo zero");
+    let lcm = modulus / gcd(modulus, m) * m;
+    assert!(lcm <= MAX_MODULUS, "congruence moduli combine to more than 2^20");
+    let residues = (0..lcm)
+        .filter(|&x| residues.binary_search(&(x % modulus)).is_ok() && keep(x % m))
+        .collect();
+    (lcm, residues)
+}
+
+/// A prime of exactly `bits` bits, chosen uniformly at random.
+///
+/// Panics if `bits` isn't between 2 and 64.
+pub fn gen_prime<R: Rng + ?Sized>(bits: u32, rng: &mut R) -> u64 {
+    gen_prime_with(bits, &PrimeConstraints::new(), rng).unwrap()
+}
+
+/**
+A prime of exactly `bits` bits that meets `constraints`, chosen uniformly at random among those
+that do, or `None` if there are none.
+
+Candidates are drawn from the allowed residue classes (only the odd ones, and only those coprime
+to the modulus, since nothing else can be a large prime) and tested with a Miller–Rabin test that
+is exact for every `u64`. For sizes up to 2^20 the whole range is sieved instead, which is what
+lets this return `None`; above that, closures that reject every prime make it loop forever.
+
+Panics if `bits` isn't between 2 and 64.
+**/
+pub fn gen_prime_with<R: Rng + ?Sized>(
+    bits: u32,
+    constraints: &PrimeConstraints,
+    rng: &mut R,
+) -> Option<u64> {
+    assert!((2..=64).contains(&bits), "primes have between 2 and 64 bits");
+    let lo = 1u64 << (bits - 1);
+    let hi = u64::MAX >> (64 - bits);
+    if hi - lo <= MAX_MODULUS {
+        let mut found = Vec::new();
+        sieve_range(lo..hi + 1, |p| {
+            if constraints.accepts(p) {
+                found.push(p);
+            }
+            true
+        });
+        return found.get(rng.random_range(0..found.len().max(1))).cloned();
+    }
+
+    let (m, rs) = combine(constraints.modulus, &constraints.residues, 2, |x| x == 1);
+    let rs: Vec<u64> = rs.into_iter().filter(|&r| gcd(r, m) == 1).collect();
+    if rs.is_empty() {
+        return None;
+    }
+    loop {
+        let q = rng.random_range(lo / m..=hi / m);
+        let Some(c) = (m * q).checked_add(rs[rng.random_range(0..rs.len())]) else {
+            continue;
+        };
+        if c >= lo && c <= hi && is_prime_mr(c) && constraints.filters.iter().all(|f| f(c)) {
+            return Some(c);
+        }
+    }
+}
+
+/**
+A random semiprime of exactly `bits` bits, the product of two random primes of about half that
+size each, in the manner of an RSA modulus.
+
+The primes have `bits / 2` and `bits - bits / 2` bits, and pairs whose product comes up a bit
+short are drawn again. They can be the same prime, though only for the smallest sizes is that at
+all likely.
+
+Panics if `bits` isn't between 4 and 64.
+**/
+pub fn gen_semiprime<R: Rng + ?Sized>(bits: u32, rng: &mut R) -> u64 {
+    assert!((4..=64).contains(&bits), "semiprimes are generated with between 4 and 64 bits");
+    loop {
+        let p = gen_prime(bits - bits / 2, rng);
+        let q = gen_prime(bits / 2, rng);
+        let n = p * q;
+        if n.leading_zeros() == 64 - bits {
+            return n;
+        }
+    }
+}
+
+// A uniform draw from (0, 1], which is safe to take the logarithm of.
+fn unit<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    1.0 - rng.random::<f64>()
+}
@@ -0,0 +1,40 @@
+//! Primality testing of many candidates in one call.
+
+use crate::is_prime;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/**
+Tests every number in `candidates` for primality, returning whether each one is prime, in order.
+
+With the `parallel` feature (and without `tiny`) the candidates are split across the rayon thread
+pool; otherwise they are tested one after another.
+**/
+pub fn are_prime(candidates: &[u64]) -> Vec<bool> {
+    #[cfg(all(feature = "parallel", not(feature = "tiny")))]
+    return candidates.par_iter().map(|&n| is_prime(n)).collect();
+    #[cfg(not(all(feature = "parallel", not(feature = "tiny"))))]
+    return candidates.iter().map(|&n| is_prime(n)).collect();
+}
+
+/// Keeps just the primes from `candidates`, testing them on the rayon thread pool. The primes come
+/// back in the order the candidates were given.
+///
+/// With the `tiny` feature the candidates are only gathered up through rayon, and then tested one
+/// after another on the calling thread.
+#[cfg(feature = "parallel")]
+pub fn par_filter_primes<I>(candidates: I) -> Vec<u64>
+where
+    I: IntoParallelIterator<Item = u64>,
+{
+    #[cfg(not(feature = "tiny"))]
+    return candidates.into_par_iter().filter(|&n| is_prime(n)).collect();
+    #[cfg(feature = "tiny")]
+    return candidates
+        .into_par_iter()
+        .collect::<Vec<_>>()
+        .into_iter()
+        .filter(|&n| is_prime(n))
+        .collect();
+}
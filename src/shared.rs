@@ -0,0 +1,162 @@
+use std::sync::{Arc, OnceLock, RwLock};
+
+use crate::range::sieve_range;
+use crate::{next_prime_at_least, nth_prime_bounds, prime_pi};
+use crate::{DefaultPrimes, PrimeSet, TrialDivision};
+
+/// With the `tiny` feature, `SharedPrimeSet` and `CachedPrimes` only grow their prime set to cover
+/// the primes below `CACHE_LIMIT`, of which there are `CACHE_PRIMES`; anything past that is worked
+/// out afresh on every call.
+#[cfg(not(feature = "tiny"))]
+pub(crate) const CACHE_LIMIT: Option<u64> = None;
+#[cfg(not(feature = "tiny"))]
+pub(crate) const CACHE_PRIMES: Option<usize> = None;
+#[cfg(feature = "tiny")]
+pub(crate) const CACHE_LIMIT: Option<u64> = Some(1 << 16);
+#[cfg(feature = "tiny")]
+pub(crate) const CACHE_PRIMES: Option<usize> = Some(6_542);
+
+/**
+A prime set that many threads can query and expand at once.
+
+Cloning a `SharedPrimeSet` gives another handle to the same cache. Lookups of primes that have
+already been found only take a read lock, so they proceed concurrently; a lookup beyond the end
+of the cache takes the write lock just long enough to expand it, and every handle sees the result.
+With the `tiny` feature the cache stops growing at 2^16, and lookups past that are answered
+without it.
+**/
+#[derive(Default)]
+pub struct SharedPrimeSet<P = TrialDivision> {
+    set: Arc<RwLock<P>>,
+}
+
+pub struct SharedIter<P> {
+    shared: SharedPrimeSet<P>,
+    n: usize,
+    last: u64,
+}
+
+impl SharedPrimeSet {
+    /// A new shared cache, backed by a new `TrialDivision`.
+    pub fn new() -> SharedPrimeSet {
+        SharedPrimeSet::default()
+    }
+}
+
+impl<P: PrimeSet> SharedPrimeSet<P> {
+    /// Number of primes found so far.
+    pub fn len(&self) -> usize {
+        self.read(|set| set.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.read(|set| set.is_empty())
+    }
+
+    /// Get the nth prime, even if no thread has found it yet.
+    pub fn get(&self, index: usize) -> u64 {
+        if let Some(p) = self.read(|set| set.get_found(index)) {
+            return p;
+        }
+        if CACHE_PRIMES.is_some_and(|cap| index >= cap) {
+            return nth_uncached(index);
+        }
+        self.write(|set| set.get(index))
+    }
+
+    /// Find the next largest prime from a number.
+    ///
+    /// Returns `(idx, prime)`
+    pub fn find(&self, n: u64) -> (usize, u64) {
+        if let Some(found) = self.read(|set| set.find_vec(n)) {
+            return found;
+        }
+        if CACHE_LIMIT.is_some_and(|limit| n >= limit) {
+            return find_uncached(n);
+        }
+        self.write(|set| set.find(n))
+    }
+
+    /// Iterator over all primes, starting with 2. The iterator holds its own handle, so it can be
+    /// sent to another thread.
+    pub fn iter(&self) -> SharedIter<P> {
+        SharedIter {
+            shared: self.clone(),
+            n: 0,
+            last: 0,
+        }
+    }
+
+    /// Runs `f` with shared access to the underlying prime set.
+    pub fn read<T, F: FnOnce(&P) -> T>(&self, f: F) -> T {
+        f(&self.set.read().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Runs `f` with exclusive access to the underlying prime set, blocking every other handle.
+    pub fn write<T, F: FnOnce(&mut P) -> T>(&self, f: F) -> T {
+        f(&mut self.set.write().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+impl<P> Clone for SharedPrimeSet<P> {
+    fn clone(&self) -> SharedPrimeSet<P> {
+        SharedPrimeSet {
+            set: Arc::clone(&self.set),
+        }
+    }
+}
+
+impl<P: PrimeSet> From<P> for SharedPrimeSet<P> {
+    fn from(set: P) -> SharedPrimeSet<P> {
+        SharedPrimeSet {
+            set: Arc::new(RwLock::new(set)),
+        }
+    }
+}
+
+impl<P: PrimeSet> Iterator for SharedIter<P> {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        self.last = next_capped(self.n, self.last, |n| self.shared.get(n))?;
+        self.n += 1;
+        Some(self.last)
+    }
+}
+
+/**
+The process-wide prime cache, created the first time this is called.
+
+Libraries built on this crate can all use this one cache instead of each keeping their own. Nothing
+is allocated until the first call, so code that never uses it pays nothing.
+**/
+pub fn global() -> &'static SharedPrimeSet<DefaultPrimes> {
+    static GLOBAL: OnceLock<SharedPrimeSet<DefaultPrimes>> = OnceLock::new();
+    GLOBAL.get_or_init(|| SharedPrimeSet::from(DefaultPrimes::new()))
+}
+
+// The `index`th prime, sieved from scratch.
+pub(crate) fn nth_uncached(index: usize) -> u64 {
+    let (_, hi) = nth_prime_bounds(index as u64 + 1);
+    let (mut count, mut found) = (0, 0);
+    sieve_range(0..hi.saturating_add(1), |p| {
+        count += 1;
+        found = p;
+        count <= index
+    });
+    found
+}
+
+// `PrimeSet::find`, worked out without a prime set.
+pub(crate) fn find_uncached(n: u64) -> (usize, u64) {
+    let p = next_prime_at_least(n).expect("no prime past LARGEST_U64_PRIME fits in a u64");
+    (prime_pi(p) as usize - 1, p)
+}
+
+// The `n`th prime for the cache iterators, given the one before it: looked up with `get` until the
+// cap, and found by stepping on from `last` past it, rather than sieving from scratch every time.
+pub(crate) fn next_capped<F: FnOnce(usize) -> u64>(n: usize, last: u64, get: F) -> Option<u64> {
+    match CACHE_PRIMES {
+        Some(cap) if n > cap => next_prime_at_least(last + 1),
+        _ => Some(get(n)),
+    }
+}
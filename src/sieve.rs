@@ -0,0 +1,338 @@
+use std::ops::Index;
+use std::slice::SliceIndex;
+
+use crate::arith::is_prime_mr;
+use crate::{initial_primes, PrimeSetBasics, LARGEST_U64_PRIME};
+
+/// Default segment buffer size in words, so that a segment's bits fit in L1/L2 cache.
+#[cfg(not(feature = "tiny"))]
+const SEGMENT_WORDS: usize = 1 << 12;
+#[cfg(feature = "tiny")]
+const SEGMENT_WORDS: usize = 1 << 4;
+
+/**
+A prime generator, using a segmented Sieve of Eratosthenes.
+
+Each call to `expand()` sieves the next segment above the primes found so far, which yields many
+primes at once. Segments only track odd numbers, one bit each, so the working buffer is 16 times
+smaller than a byte-per-number sieve over the same range.
+
+Create with `let mut pset = Sieve::new()`, and then use `pset.iter()` to iterate over all primes.
+
+The segment buffer can be any `AsMut<[u64]>`, so embedders that need control over where sieve
+memory comes from can pass their own with `Sieve::with_buffers`.
+**/
+#[derive(Clone)]
+pub struct Sieve<B = Vec<u64>> {
+    lst: Vec<u64>,
+    // The next (odd) number to sieve; everything below it is already in `lst`.
+    limit: u64,
+    buf: B,
+}
+
+impl Sieve {
+    /// A new prime generator, primed with 2 and 3, or with the first 10,000 primes under the
+    /// `table` feature.
+    pub fn new() -> Sieve {
+        Sieve::with_buffers(Vec::new(), vec![0; SEGMENT_WORDS])
+    }
+
+    /// A prime generator that carries on from `primes`, a list found earlier; see `Sieve::resume`.
+    pub fn from_prefix(primes: Vec<u64>) -> Option<Sieve> {
+        Sieve::resume(primes, vec![0; SEGMENT_WORDS])
+    }
+}
+
+impl<B: AsMut<[u64]>> Sieve<B> {
+    /**
+    A new prime generator that stores primes in `primes` and sieves in `segment`.
+
+    Any contents of `primes` are discarded, but its capacity is kept, so reserving it up front
+    avoids reallocating the list as it grows. Each segment covers 128 numbers per word of
+    `segment`, which must not be empty.
+    **/
+    pub fn with_buffers(mut primes: Vec<u64>, mut segment: B) -> Sieve<B> {
+        assert!(!segment.as_mut().is_empty(), "segment buffer must not be empty");
+        primes.clear();
+        primes.extend_from_slice(initial_primes());
+        let limit = primes.last().unwrap() + 2;
+        Sieve {
+            lst: primes,
+            limit,
+            buf: segment,
+        }
+    }
+
+    /**
+    A prime generator that carries on from `primes`, which sieves in `segment`.
+
+    `primes` has to be every prime up to its last entry, in order, as saved from `list()` or
+    `into_buffers` on an earlier generator; the new one picks up where that one stopped, with the
+    stored primes as its base primes, so work loaded from disk isn't redone. Returns `None` if
+    `primes` doesn't start with 2 and 3, isn't strictly ascending, or has a composite in it, as
+    with `TrialDivision::from_primes`. Gaps can't be detected, and a missing prime would let its
+    multiples through later on. `segment` must not be empty.
+    **/
+    pub fn resume(primes: Vec<u64>, segment: B) -> Option<Sieve<B>> {
+        if !primes.starts_with(&[2, 3])
+            || primes.windows(2).any(|w| w[0] >= w[1])
+            || !primes.iter().all(|&p| is_prime_mr(p))
+        {
+            return None;
+        }
+        let limit = primes.last().unwrap() + 2;
+        let mut sieve = Sieve::with_buffers(Vec::new(), segment);
+        sieve.lst = primes;
+        sieve.limit = limit;
+        Some(sieve)
+    }
+
+    /// Takes the sieve apart, returning the primes found so far and the segment buffer.
+    pub fn into_buffers(self) -> (Vec<u64>, B) {
+        (self.lst, self.buf)
+    }
+
+    /**
+    Sieves at most `max_candidates` more odd numbers, and reports how many primes that found.
+
+    This is for event loops that can't block and don't have threads, such as GUIs and wasm: each
+    call does a bounded amount of work, so generation can be interleaved with everything else by
+    calling this repeatedly. Besides the candidates themselves, each call also walks the base
+    primes up to the square root of where it stops, so very small steps waste some time. Once the
+    largest prime that fits in a `u64` has been found, this returns `StepResult::Done`; before
+    that, a step of 0 candidates does nothing and returns `StepResult::Found(0)`.
+    **/
+    pub fn expand_step(&mut self, max_candidates: u64) -> StepResult {
+        if self.limit > LARGEST_U64_PRIME {
+            return StepResult::Done;
+        }
+        if max_candidates == 0 {
+            return StepResult::Found(0);
+        }
+        let len = self.lst.len();
+        self.sieve_segment(max_candidates.saturating_mul(2));
+        StepResult::Found(self.lst.len() - len)
+    }
+
+    // Sieves the odd numbers in `[limit, 2 * limit)` (capped at the size of the segment buffer,
+    // and at `cap` numbers), adding the primes found to the list. Every prime below
+    // `sqrt(2 * limit)` is already known, so this never needs more primes than it has.
+    fn sieve_segment(&mut self, cap: u64) {
+        let buf = self.buf.as_mut();
+        let lo = self.limit;
+        let span = (buf.len() as u64).saturating_mul(128);
+        // Stop at the largest prime that fits in a `u64`, since everything past it is composite.
+        let width = lo.min(span).min(cap).min(LARGEST_U64_PRIME + 2 - lo) & !1;
+        let n = (width / 2) as usize;
+
+        mark_composites(&self.lst[1..], lo, n, buf);
+        push_unmarked(lo, n, buf, &mut self.lst);
+        self.limit = lo + width;
+    }
+
+    // Sieves everything up to `n` in buffer-sized segments, spread across threads.
+    #[cfg(all(feature = "parallel", not(feature = "tiny")))]
+    fn par_sieve_to(&mut self, n: u64) {
+        use rayon::prelude::*;
+
+        // Segments end at most at `n + 2`, so the base primes have to reach its square root.
+        while u128::from(self.limit).pow(2) <= u128::from(n) + 2 {
+            self.sieve_segment(u64::MAX);
+        }
+        if self.limit > n {
+            return;
+        }
+
+        let start = self.limit;
+        let words = self.buf.as_mut().len();
+        let span = (words as u64).saturating_mul(128);
+        let count = (n - start) / span + 1;
+        let segment = |i: u64| {
+            let lo = start + i * span;
+            (lo, (span.min(n - lo + 1) + 1) & !1)
+        };
+
+        let base = &self.lst[1..];
+        let found: Vec<Vec<u64>> = (0..count)
+            .into_par_iter()
+            // Each thread marks its segments in one buffer of its own, as big as `self.buf`.
+            .map_init(
+                || vec![0; words],
+                |bits, i| {
+                    let (lo, width) = segment(i);
+                    let n = (width / 2) as usize;
+                    let mut found = Vec::new();
+                    mark_composites(base, lo, n, bits);
+                    push_unmarked(lo, n, bits, &mut found);
+                    found
+                },
+            )
+            .collect();
+
+        self.lst.extend(found.into_iter().flatten());
+        let (lo, width) = segment(count - 1);
+        self.limit = lo + width;
+    }
+}
+
+/// What a call to `Sieve::expand_step` did.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StepResult {
+    /// The step ran, and found this many new primes (possibly none).
+    Found(usize),
+    /// There was nothing left to do: every prime that fits in a `u64` has already been found.
+    Done,
+}
+
+/**
+Marks the odd composites among the `n` odd numbers starting at `lo`, setting their bits in `bits`.
+
+`odd_primes` has to hold every odd prime up to the square root of the end of the segment, in
+order; primes themselves are left unmarked. Offsets are worked out in `u128` so that nothing
+overflows near the top of the range.
+**/
+pub(crate) fn mark_composites(odd_primes: &[u64], lo: u64, n: usize, bits: &mut [u64]) {
+    let bits = &mut bits[..n.div_ceil(64)];
+    bits.iter_mut().for_each(|w| *w = 0);
+
+    #[cfg(feature = "simd")]
+    let done = presieve(odd_primes, lo, n, bits);
+    #[cfg(not(feature = "simd"))]
+    let done = 0;
+
+    let lo = u128::from(lo);
+    let hi = lo + 2 * n as u128;
+    for &p in &odd_primes[done..] {
+        let p = u128::from(p);
+        if p * p >= hi {
+            break;
+        }
+        let mut m = (p * p).max(lo.div_ceil(p) * p);
+        if m.is_multiple_of(2) {
+            m += p;
+        }
+        let mut j = ((m - lo) / 2) as usize;
+        while j < n {
+            bits[j / 64] |= 1 << (j % 64);
+            j += p as usize;
+        }
+    }
+}
+
+/// Words ORed together per step of `presieve`, which the compiler turns into vector instructions.
+#[cfg(feature = "simd")]
+const LANES: usize = 8;
+
+/// The odd primes below this are marked by `presieve`.
+#[cfg(feature = "simd")]
+const PRESIEVE_BELOW: u64 = 256;
+
+/**
+Marks the odd multiples of the odd primes below `PRESIEVE_BELOW` for `mark_composites`, returning
+how many of `odd_primes` that covered.
+
+The multiples of a prime `p` among the odd numbers repeat every `p` bits, so every `p` words, and a
+segment can be marked a whole vector of words at a time by ORing in a pattern that's built once.
+That is where a plain sieve spends most of its time, since the smallest primes have by far the
+most multiples. The pattern also marks `p` itself and its multiples below `p²`, all but the first
+of which are composite anyway, so `p` is unmarked again afterwards.
+**/
+#[cfg(feature = "simd")]
+fn presieve(odd_primes: &[u64], lo: u64, n: usize, bits: &mut [u64]) -> usize {
+    let hi = u128::from(lo) + 2 * n as u128;
+    let mut pattern = [0u64; PRESIEVE_BELOW as usize + LANES];
+    let mut done = 0;
+    for &p in odd_primes.iter().take_while(|&&p| p < PRESIEVE_BELOW && u128::from(p * p) < hi) {
+        // The offset of the first odd multiple of `p` from `lo`: `lo + 2j ≡ 0 (mod p)`.
+        let j = (p - lo % p) % p * p.div_ceil(2) % p;
+        let len = p as usize + LANES;
+        pattern[..len].iter_mut().for_each(|w| *w = 0);
+        for b in (j as usize..len * 64).step_by(p as usize) {
+            pattern[b / 64] |= 1 << (b % 64);
+        }
+
+        for (i, chunk) in bits.chunks_mut(LANES).enumerate() {
+            let start = i * LANES % p as usize;
+            for (w, &m) in chunk.iter_mut().zip(&pattern[start..start + LANES]) {
+                *w |= m;
+            }
+        }
+        if lo <= p && u128::from(p) < hi {
+            let k = ((p - lo) / 2) as usize;
+            bits[k / 64] &= !(1 << (k % 64));
+        }
+        done += 1;
+    }
+    done
+}
+
+// Appends the numbers left unmarked by `mark_composites` to `out`.
+fn push_unmarked(lo: u64, n: usize, bits: &[u64], out: &mut Vec<u64>) {
+    out.extend(unmarked(n, bits).map(|j| lo + 2 * j as u64));
+}
+
+// The positions below `n` whose bits are clear, a word at a time.
+pub(crate) fn unmarked(n: usize, bits: &[u64]) -> impl Iterator<Item = usize> + '_ {
+    bits[..n.div_ceil(64)].iter().enumerate().flat_map(move |(i, &w)| {
+        let mut clear = !w;
+        if (i + 1) * 64 > n {
+            clear &= (1 << (n % 64)) - 1;
+        }
+        std::iter::from_fn(move || {
+            if clear == 0 {
+                return None;
+            }
+            let b = clear.trailing_zeros() as usize;
+            clear &= clear - 1;
+            Some(i * 64 + b)
+        })
+    })
+}
+
+impl Default for Sieve {
+    fn default() -> Sieve {
+        Sieve::new()
+    }
+}
+
+impl<B: AsMut<[u64]>> PrimeSetBasics for Sieve<B> {
+    /// Sieves until at least one more prime is found.
+    ///
+    /// Panics if the largest prime that fits in a `u64` has already been found.
+    fn expand(&mut self) {
+        let len = self.lst.len();
+        while self.lst.len() == len {
+            assert!(
+                self.limit <= LARGEST_U64_PRIME,
+                "no primes above the largest prime that fits in a u64"
+            );
+            self.sieve_segment(u64::MAX);
+        }
+    }
+
+    /// Returns all primes found so far as a slice.
+    fn list(&self) -> &[u64] {
+        &self.lst[..]
+    }
+
+    /// Sieves every segment up to `n`. With the `parallel` feature (and without `tiny`), segments
+    /// are sieved on the rayon thread pool.
+    fn expand_to(&mut self, n: u64) {
+        let n = n.min(LARGEST_U64_PRIME);
+        #[cfg(all(feature = "parallel", not(feature = "tiny")))]
+        self.par_sieve_to(n);
+        // Like the default, this goes on to the first prime at or above `n`, which callers such as
+        // `expand_to_with_progress` rely on.
+        while self.limit <= n || self.lst.last() < Some(&n) {
+            self.sieve_segment(u64::MAX);
+        }
+    }
+}
+
+// Indexes the primes found so far, by position (`pset[3]`) or by range (`pset[..10]`).
+impl<B: AsMut<[u64]>, I: SliceIndex<[u64]>> Index<I> for Sieve<B> {
+    type Output = I::Output;
+    fn index(&self, index: I) -> &I::Output {
+        &self.list()[index]
+    }
+}
@@ -0,0 +1,75 @@
+//! Smooth and rough numbers: those whose prime factors are all small, or all large.
+
+use crate::range::sieve_range;
+use crate::wheel_next;
+
+// The trial divisors 2, 3, 5, 7, 11, 13, ..., everything coprime to 30 after 5.
+fn trial_divisors() -> impl Iterator<Item = u64> {
+    std::iter::successors(Some(2), |&d| Some(if d == 2 { 3 } else { wheel_next(d) }))
+}
+
+/**
+Whether `n` is `bound`-smooth: whether every prime factor of `n` is at most `bound`.
+
+Factors are divided out by trial division, stopping as soon as what is left is at most `bound` (and
+so can't have a larger factor) or the divisors pass `bound` or the square root of what is left. 1 is
+smooth for any bound, and 0 never is.
+**/
+pub fn is_smooth(n: u64, bound: u64) -> bool {
+    if n == 0 {
+        return false;
+    }
+    // 1 has no prime factors at all, so it passes even a bound of 0.
+    let bound = bound.max(1);
+    let mut n = n;
+    for d in trial_divisors() {
+        if n <= bound {
+            return true;
+        }
+        if d > bound || d > n / d {
+            // What's left is a prime above `bound`, or has a prime factor above it.
+            return false;
+        }
+        while n.is_multiple_of(d) {
+            n /= d;
+        }
+    }
+    unreachable!()
+}
+
+/**
+Whether `n` is `bound`-rough: whether every prime factor of `n` is at least `bound`.
+
+Only divisors below `bound` and up to `√n` are tried, and the first that divides `n` settles it.
+1 is rough for any bound, and 0 never is.
+**/
+pub fn is_rough(n: u64, bound: u64) -> bool {
+    // Anything from 2 up to `bound` is its own small factor, or has one.
+    if n == 0 || n > 1 && n < bound {
+        return false;
+    }
+    trial_divisors()
+        .take_while(|&d| d < bound && d <= n / d)
+        .all(|d| !n.is_multiple_of(d))
+}
+
+/**
+Every `bound`-smooth number below `limit`, in ascending order, starting from 1.
+
+This sieves rather than testing each number: every prime up to `bound` is divided out of each of
+its multiples below `limit`, and the numbers reduced to 1 are the smooth ones. That takes a `u64` of
+memory for each number below `limit`.
+**/
+pub fn smooth_numbers_below(limit: u64, bound: u64) -> Vec<u64> {
+    let mut rest: Vec<u64> = (0..limit).collect();
+    sieve_range(0..bound.min(limit).saturating_add(1), |p| {
+        for m in (p..limit).step_by(p as usize) {
+            let r = &mut rest[m as usize];
+            while r.is_multiple_of(p) {
+                *r /= p;
+            }
+        }
+        true
+    });
+    (1..limit).filter(|&i| rest[i as usize] == 1).collect()
+}
@@ -0,0 +1,127 @@
+//! Fast lookups in any sorted list of primes, wherever it came from.
+
+use std::cmp::Ordering;
+
+use crate::PrimeView;
+
+/// Below this ratio of lengths, intersecting walks both lists in step instead of galloping.
+const GALLOP_RATIO: usize = 8;
+
+/**
+A sorted slice of distinct numbers, usually primes, with the lookups a prime list is used for.
+
+The slice can come from `list()` on any prime set, from a `PrimeView`, or from anywhere else, such
+as a table loaded from disk; all this needs is that it's strictly ascending, which `new` checks.
+Lookups are binary searches, and intersections gallop through the longer list when one is much
+shorter than the other.
+**/
+#[derive(Clone, Copy, Debug)]
+pub struct PrimeSlice<'a> {
+    lst: &'a [u64],
+}
+
+impl<'a> PrimeSlice<'a> {
+    /// Wraps `lst`, or returns `None` if it isn't strictly ascending.
+    pub fn new(lst: &'a [u64]) -> Option<PrimeSlice<'a>> {
+        if lst.windows(2).all(|w| w[0] < w[1]) {
+            Some(PrimeSlice { lst })
+        } else {
+            None
+        }
+    }
+
+    /// Wraps `lst` without checking that it's strictly ascending, which lets `primes_table!` build
+    /// one from a static table. A list that isn't ascending only gives wrong answers.
+    pub const fn new_unchecked(lst: &'a [u64]) -> PrimeSlice<'a> {
+        PrimeSlice { lst }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lst.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lst.is_empty()
+    }
+
+    /// The underlying slice.
+    pub fn as_slice(&self) -> &'a [u64] {
+        self.lst
+    }
+
+    /// Whether `n` is in the slice.
+    pub fn contains(&self, n: u64) -> bool {
+        self.lst.binary_search(&n).is_ok()
+    }
+
+    /// The number of entries less than or equal to `n`. For a list of every prime up to at least
+    /// `n`, that's π(n).
+    pub fn rank(&self, n: u64) -> usize {
+        self.lst.partition_point(|&p| p <= n)
+    }
+
+    /// The entry at `index`, counting from zero, so that `rank(select(k)) == k + 1`.
+    pub fn select(&self, index: usize) -> Option<u64> {
+        self.lst.get(index).cloned()
+    }
+
+    /// The entries in both `self` and `other`, in ascending order.
+    pub fn intersect(&self, other: &PrimeSlice) -> Vec<u64> {
+        let (short, long) = if self.len() <= other.len() {
+            (self.lst, other.lst)
+        } else {
+            (other.lst, self.lst)
+        };
+        if short.len().saturating_mul(GALLOP_RATIO) < long.len() {
+            gallop_intersect(short, long)
+        } else {
+            merge_intersect(short, long)
+        }
+    }
+}
+
+impl<'a> From<PrimeView<'a>> for PrimeSlice<'a> {
+    fn from(view: PrimeView<'a>) -> PrimeSlice<'a> {
+        PrimeSlice { lst: view.list() }
+    }
+}
+
+// Looks each entry of `short` up in `long`, searching from where the last one was found: first
+// doubling the step until it overshoots, then binary searching within the last step.
+fn gallop_intersect(short: &[u64], long: &[u64]) -> Vec<u64> {
+    let mut out = Vec::new();
+    let mut rest = long;
+    for &x in short {
+        let mut step = 1;
+        while step < rest.len() && rest[step - 1] < x {
+            step *= 2;
+        }
+        let window = &rest[..step.min(rest.len())];
+        let i = window.partition_point(|&p| p < x);
+        if window.get(i) == Some(&x) {
+            out.push(x);
+        }
+        rest = &rest[i..];
+        if rest.is_empty() {
+            break;
+        }
+    }
+    out
+}
+
+fn merge_intersect(a: &[u64], b: &[u64]) -> Vec<u64> {
+    let mut out = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            Ordering::Less => i += 1,
+            Ordering::Greater => j += 1,
+            Ordering::Equal => {
+                out.push(a[i]);
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    out
+}
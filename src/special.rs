@@ -0,0 +1,636 @@
+/*!
+Tests for primes of special forms, which are much faster than general primality tests on numbers
+of the same size, and the primorials that some of those forms are built from; tests and searches
+for the primes satisfying rare congruences; and iterators over the primes picked out by their
+decimal digits.
+
+```
+use primes::special;
+
+assert!(special::is_mersenne_prime(127));
+assert_eq!(special::mersenne_exponents().take(5).collect::<Vec<_>>(), vec![2, 3, 5, 7, 13]);
+assert_eq!(special::is_fermat_prime(4), Some(true));
+assert!(special::is_proth_prime(3, 41));
+assert_eq!(special::primorial(7), Some(210));
+assert!(special::is_primorial_prime(211));
+assert_eq!(special::wieferich_primes(5000).collect::<Vec<_>>(), vec![1093, 3511]);
+assert_eq!(special::is_wilson_prime(563), Some(true));
+assert_eq!(special::circular_primes().nth(6), Some(17));
+```
+*/
+
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+
+use crate::arith::{is_prime_mr, mul_mod_wide, pow_mod_wide};
+use crate::{global, is_prime, jacobi, mod_pow, next_prime_at_least};
+use crate::{PrimePredicate, PrimeSet, TrialDivision};
+
+#[cfg(feature = "bigint")]
+use crate::product_tree;
+#[cfg(feature = "bigint")]
+use crate::range::sieve_range;
+#[cfg(feature = "bigint")]
+use num_bigint::BigUint;
+
+/**
+Whether the Mersenne number `2^p - 1` is prime, by the Lucas–Lehmer test.
+
+`2^p - 1` can only be prime for prime `p`, so other `p` are rejected straight away. For prime `p`
+the test takes `p - 2` squarings modulo `2^p - 1`, where reducing is just adding the high half of
+the product to the low half, so exponents in the thousands take well under a second.
+**/
+pub fn is_mersenne_prime(p: u32) -> bool {
+    if p == 2 {
+        return true;
+    }
+    if !is_prime(u64::from(p)) {
+        return false;
+    }
+
+    let p = p as usize;
+    let mut s = vec![0u64; p.div_ceil(64)];
+    s[0] = 4;
+    for _ in 0..p - 2 {
+        s = mersenne_reduce(&square(&s), p);
+        sub_two(&mut s, p);
+    }
+    // 0 can also be represented as `2^p - 1` itself.
+    s.iter().all(|&w| w == 0) || is_all_ones(&s, p)
+}
+
+pub struct MersenneExponents {
+    pset: TrialDivision,
+    n: usize,
+}
+
+/// Iterator over the exponents `p` for which `2^p - 1` is prime, in ascending order, testing each
+/// prime `p` in turn with `is_mersenne_prime`. Each test takes longer than the last, so this slows
+/// down markedly past the first twenty or so.
+pub fn mersenne_exponents() -> MersenneExponents {
+    MersenneExponents {
+        pset: TrialDivision::new(),
+        n: 0,
+    }
+}
+
+impl Iterator for MersenneExponents {
+    type Item = u32;
+    fn next(&mut self) -> Option<u32> {
+        loop {
+            let p = u32::try_from(self.pset.get(self.n)).ok()?;
+            self.n += 1;
+            if is_mersenne_prime(p) {
+                return Some(p);
+            }
+        }
+    }
+}
+
+/**
+Whether the Fermat number `2^(2^k) + 1` is prime, by Pépin's test, or `None` if no one knows.
+
+For `k` of at least 1, `F = 2^(2^k) + 1` is prime exactly when `3^((F - 1) / 2) ≡ -1 (mod F)`.
+That takes `2^k - 1` squarings modulo `F`, each reduced by subtracting the high half of the product
+from the low half, which is practical up to `k` of 12. Only `k` up to 4 give primes; past 12 the
+answer comes from the factors and Pépin tests others have found, which settle every `k` up to 32
+as composite. Past that some are known to be composite and others aren't, and the answer is `None`.
+**/
+pub fn is_fermat_prime(k: u32) -> Option<bool> {
+    match k {
+        0 => Some(true),
+        1..=PEPIN_UP_TO => Some(pepin(k)),
+        _ if k <= FERMAT_SETTLED_UP_TO => Some(false),
+        _ => None,
+    }
+}
+
+/// The largest `k` for which `is_fermat_prime` runs Pépin's test.
+const PEPIN_UP_TO: u32 = 12;
+
+/// Every Fermat number past the fourth is composite up to this `k`, and `F_33` is the first whose
+/// status is unknown.
+const FERMAT_SETTLED_UP_TO: u32 = 32;
+
+// Pépin's test on `2^(2^k) + 1`, for `k` from 1 to `PEPIN_UP_TO`.
+fn pepin(k: u32) -> bool {
+    let n = 1usize << k;
+    let mut s = vec![3u64];
+    for _ in 0..n - 1 {
+        s = fermat_reduce(square(&s), n);
+    }
+    // -1 is `2^n`.
+    is_power_of_two(&s, n)
+}
+
+/**
+Whether the Proth number `k 2^n + 1` is prime, by Proth's theorem. Only odd `k` below `2^n` make
+Proth numbers; anything else gives `false`.
+
+A Proth number `N` is prime exactly when `a^((N - 1) / 2) ≡ -1 (mod N)` for any `a` that isn't a
+square modulo `N`, so this finds such an `a` among the small primes and takes one modular power.
+Numbers that don't fit in a `u64` need the `bigint` feature.
+
+Panics if `k 2^n + 1` doesn't fit in a `u64` and the `bigint` feature is off.
+**/
+pub fn is_proth_prime(k: u64, n: u32) -> bool {
+    if k.is_multiple_of(2) || n < 64 && k >> n != 0 {
+        return false;
+    }
+    if n >= 64 || n > k.leading_zeros() {
+        return big_proth(k, n);
+    }
+    let m = (k << n) + 1;
+    // Squares have no non-residues, and `k 2^n + 1` can be one (9 = 2^3 + 1).
+    if m.isqrt().pow(2) == m {
+        return false;
+    }
+    let a = small_nonresidue(|a| jacobi(a, m) == -1);
+    mod_pow(a, m / 2, m) == m - 1
+}
+
+// The smallest odd prime `a` for which `is_nonresidue(a)` holds. The caller makes sure one exists.
+fn small_nonresidue<F: Fn(u64) -> bool>(is_nonresidue: F) -> u64 {
+    let mut pset = TrialDivision::new();
+    pset.iter().skip(1).find(|&a| is_nonresidue(a)).unwrap()
+}
+
+#[cfg(feature = "bigint")]
+fn big_proth(k: u64, n: u32) -> bool {
+    let m = (BigUint::from(k) << n) + 1u32;
+    if m.sqrt().pow(2) == m {
+        return false;
+    }
+    // `n` is well above 2 here, so `m` is 1 mod 4 and `(a/m) = (m mod a / a)` by reciprocity.
+    let a = small_nonresidue(|a| {
+        let r = (&m % a).iter_u64_digits().next().unwrap_or(0);
+        jacobi(r, a) == -1
+    });
+    let exp = &m >> 1u32;
+    BigUint::from(a).modpow(&exp, &m) == &m - 1u32
+}
+
+#[cfg(not(feature = "bigint"))]
+fn big_proth(_: u64, _: u32) -> bool {
+    panic!("Proth numbers that don't fit in a u64 need the bigint feature");
+}
+
+/// The primorial `n#`, the product of all primes up to and including `n`, or `None` if it doesn't
+/// fit in a `u64` (from `n` = 53 on).
+pub fn primorial(n: u64) -> Option<u64> {
+    let mut pset = TrialDivision::new();
+    pset.iter()
+        .take_while(|&p| p <= n)
+        .try_fold(1u64, |acc, p| acc.checked_mul(p))
+}
+
+/// The primorial `n#`, or `None` if it doesn't fit in a `u128` (from `n` = 103 on).
+pub fn primorial_u128(n: u64) -> Option<u128> {
+    let mut pset = TrialDivision::new();
+    pset.iter()
+        .take_while(|&p| p <= n)
+        .try_fold(1u128, |acc, p| acc.checked_mul(u128::from(p)))
+}
+
+/// The primorial `n#`, of any size. The primes are multiplied up in a product tree, so this stays
+/// quick for `n` in the millions.
+#[cfg(feature = "bigint")]
+pub fn primorial_big(n: u64) -> BigUint {
+    let mut primes = Vec::new();
+    sieve_range(0..n.saturating_add(1), |p| {
+        primes.push(BigUint::from(p));
+        true
+    });
+    product_tree(&primes)
+        .pop()
+        .and_then(|root| root.into_iter().next())
+        .unwrap_or_else(|| BigUint::from(1u32))
+}
+
+/**
+Whether `n` is a primorial prime: a prime one more or one less than a primorial `p#`.
+
+There are only a handful of primorials below `u64::MAX`, so the form is checked first and only
+numbers of the right form are tested for primality, by trial division.
+**/
+pub fn is_primorial_prime(n: u64) -> bool {
+    let mut pset = TrialDivision::new();
+    let primorials = pset.iter().scan(1u64, |acc, p| {
+        *acc = acc.checked_mul(p)?;
+        Some(*acc)
+    });
+    is_next_to_any(n, std::iter::once(1).chain(primorials)) && is_prime(n)
+}
+
+/**
+Whether `n` is a factorial prime: a prime one more or one less than a factorial `k!`.
+
+Only factorials up to `20!` fit in a `u64`, so as with `is_primorial_prime` the form is checked
+first and only numbers of the right form are tested for primality.
+**/
+pub fn is_factorial_prime(n: u64) -> bool {
+    let factorials = (1..).scan(1u64, |acc, k| {
+        *acc = acc.checked_mul(k)?;
+        Some(*acc)
+    });
+    is_next_to_any(n, factorials) && is_prime(n)
+}
+
+// Whether `n` is one away from some value of the ascending sequence `seq`.
+fn is_next_to_any<I: Iterator<Item = u64>>(n: u64, seq: I) -> bool {
+    seq.take_while(|&q| q <= n.saturating_add(1))
+        .any(|q| q.checked_add(1) == Some(n) || q.checked_sub(1) == Some(n))
+}
+
+/**
+Whether `p` is a Wieferich prime: a prime with `2^(p-1) ≡ 1 (mod p²)`.
+
+Fermat's little theorem only promises that congruence modulo `p`. The only Wieferich primes known
+are 1093 and 3511, and searches have found no others below 6.7 × 10^17. The power is taken modulo
+`p²`, which goes through `u128` arithmetic for `p` past 2^32.
+**/
+pub fn is_wieferich(p: u64) -> bool {
+    is_prime_mr(p) && pow_mod_wide(2, u128::from(p - 1), u128::from(p) * u128::from(p)) == 1
+}
+
+/**
+Whether `p` is a Wilson prime: a prime with `(p - 1)! ≡ -1 (mod p²)`.
+
+Wilson's theorem gives that congruence modulo `p` for every prime. The only Wilson primes known
+are 5, 13 and 563, and there are no others below 2 × 10^13. Up to 2^32 the factorial is multiplied
+out modulo `p²` one factor at a time, so this takes time in proportion to `p`: billions of
+multiplications near 2^32. Past that, where `p²` no longer fits in a `u64` and each multiplication
+is far slower, primes below 2 × 10^13 are answered from the searches. For larger primes no one
+knows, and the answer is `None`; for everything that isn't prime it is `Some(false)`.
+**/
+pub fn is_wilson_prime(p: u64) -> Option<bool> {
+    if !is_prime_mr(p) {
+        return Some(false);
+    }
+    if p > WILSON_DIRECT_UP_TO {
+        return if p < WILSON_SEARCHED_BELOW { Some(false) } else { None };
+    }
+    let m = u128::from(p) * u128::from(p);
+    Some((2..p).fold(1, |f, k| mul_mod_wide(f, u128::from(k), m)) == m - 1)
+}
+
+/// The largest `p` for which `is_wilson_prime` multiplies out `(p - 1)!`.
+const WILSON_DIRECT_UP_TO: u64 = 1 << 32;
+
+/// Searches have found no Wilson primes past 563 and below this.
+const WILSON_SEARCHED_BELOW: u64 = 20_000_000_000_000;
+
+/// Iterator over the primes below a limit that pass a test, such as the Wieferich primes from
+/// `wieferich_primes` and the Wilson primes from `wilson_primes`.
+#[derive(Clone, Debug)]
+pub struct CongruencePrimes {
+    // Where to look for the next prime from, or `None` once past the last `u64` prime.
+    from: Option<u64>,
+    limit: u64,
+    test: fn(u64) -> bool,
+}
+
+/// Iterator over the Wieferich primes below `limit`, in ascending order, testing each prime in
+/// turn with `is_wieferich`.
+pub fn wieferich_primes(limit: u64) -> CongruencePrimes {
+    CongruencePrimes {
+        from: Some(2),
+        limit,
+        test: is_wieferich,
+    }
+}
+
+/// Iterator over the Wilson primes below `limit`, in ascending order, testing each prime in turn
+/// with `is_wilson_prime`. Each test takes time in proportion to the prime, so the whole search
+/// grows with the square of `limit`, and takes seconds by 10^5. Primes that no one knows about
+/// are left out.
+pub fn wilson_primes(limit: u64) -> CongruencePrimes {
+    CongruencePrimes {
+        from: Some(2),
+        limit,
+        test: |p| is_wilson_prime(p) == Some(true),
+    }
+}
+
+impl Iterator for CongruencePrimes {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        loop {
+            let p = next_prime_at_least(self.from?).filter(|&p| p < self.limit)?;
+            self.from = p.checked_add(1);
+            if (self.test)(p) {
+                return Some(p);
+            }
+        }
+    }
+}
+
+/// Below this, the digit-based searches look primes up in the shared cache from `global()`. With
+/// the `tiny` feature it is the same cap that `global()` keeps its cache under.
+#[cfg(not(feature = "tiny"))]
+const SHARED_LIMIT: u64 = 1 << 24;
+#[cfg(feature = "tiny")]
+const SHARED_LIMIT: u64 = 1 << 16;
+
+/// Every `u64` with at most this many digits fits, which is as far as the digit-based searches go.
+const MAX_DIGITS: u32 = 19;
+
+// Whether `n` is prime. The digit-based searches test many numbers of similar sizes, so the small
+// ones are looked up in the shared cache; past `SHARED_LIMIT` the cache would grow too large, and
+// Miller–Rabin takes over.
+fn is_prime_shared(n: u64) -> bool {
+    if n < SHARED_LIMIT {
+        global().find(n).1 == n
+    } else {
+        is_prime_mr(n)
+    }
+}
+
+// The number of decimal digits in `n`, counting 0 as one digit.
+fn num_digits(n: u64) -> u32 {
+    n.checked_ilog10().unwrap_or(0) + 1
+}
+
+/**
+Iterator over the palindromic primes, the primes that read the same backwards: 2, 3, 5, 7, 11, 101,
+131, and so on, up to the largest that fits in a `u64`.
+
+This is `PrimePredicate::Palindromic.primes()`, which only tries palindromes with an odd number of
+digits, since apart from 11 every other one is a multiple of 11.
+**/
+pub fn palindromic_primes() -> impl Iterator<Item = u64> {
+    PrimePredicate::Palindromic.primes()
+}
+
+pub struct CircularPrimes {
+    // How many of the one-digit primes have been returned.
+    small: usize,
+    // The digits of the next candidate, most significant first, as indices into `[1, 3, 7, 9]`.
+    digits: Vec<usize>,
+}
+
+/**
+Iterator over the circular primes, the primes all of whose digit rotations are prime: 2, 3, 5, 7,
+11, 13, 17, 31, 37, 71, and so on.
+
+Past one digit, a circular prime can only have the digits 1, 3, 7 and 9, since some rotation ends
+in each digit, so only those candidates are tried, in ascending order. There are 55 below a
+million, the last 999,331; beyond that the only ones known are repunits, of which just
+1,111,111,111,111,111,111 fits in a `u64`, so expect iteration past 55 to take a very long time.
+**/
+pub fn circular_primes() -> CircularPrimes {
+    CircularPrimes {
+        small: 0,
+        digits: vec![0, 0],
+    }
+}
+
+impl Iterator for CircularPrimes {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        const DIGITS: [u64; 4] = [1, 3, 7, 9];
+        if let Some(&p) = [2, 3, 5, 7].get(self.small) {
+            self.small += 1;
+            return Some(p);
+        }
+        while self.digits.len() as u32 <= MAX_DIGITS {
+            let n = self.digits.iter().fold(0, |n, &d| n * 10 + DIGITS[d]);
+            // Counts up in base 4, adding a digit when every place carries.
+            match self.digits.iter().rposition(|&d| d < 3) {
+                Some(i) => {
+                    self.digits[i] += 1;
+                    self.digits[i + 1..].iter_mut().for_each(|d| *d = 0);
+                }
+                None => self.digits = vec![0; self.digits.len() + 1],
+            }
+
+            let top = 10u64.pow(num_digits(n) - 1);
+            let mut r = n;
+            let all_prime = (0..num_digits(n)).all(|_| {
+                r = r % top * 10 + r / top;
+                is_prime_shared(r)
+            });
+            if all_prime {
+                return Some(n);
+            }
+        }
+        None
+    }
+}
+
+pub struct TruncatablePrimes {
+    // The truncatable primes with the current number of digits, in ascending order.
+    level: Vec<u64>,
+    pos: usize,
+    left: bool,
+}
+
+/**
+Iterator over the left-truncatable primes, which stay prime as their leading digits are removed
+one by one (with no zero digits, which would make that trivial): 2, 3, 5, 7, 13, 17, 23, and so on.
+
+Each is found by putting a digit in front of a shorter one, in rounds of one more digit each. There
+are 4,260 in all, the largest with 24 digits; this stops after the ones that fit in a `u64`.
+**/
+pub fn left_truncatable_primes() -> TruncatablePrimes {
+    TruncatablePrimes {
+        level: vec![2, 3, 5, 7],
+        pos: 0,
+        left: true,
+    }
+}
+
+/**
+Iterator over the right-truncatable primes, which stay prime as their last digits are removed one
+by one: 2, 3, 5, 7, 23, 29, 31, and so on.
+
+Each is found by adding a digit to the end of a shorter one, in rounds of one more digit each. There
+are only 83, the largest 73,939,133.
+**/
+pub fn right_truncatable_primes() -> TruncatablePrimes {
+    TruncatablePrimes {
+        level: vec![2, 3, 5, 7],
+        pos: 0,
+        left: false,
+    }
+}
+
+impl TruncatablePrimes {
+    // The truncatable primes with one more digit than those in `level`, in ascending order.
+    fn next_level(&self) -> Vec<u64> {
+        let mut next = Vec::new();
+        for &p in &self.level {
+            if self.left {
+                let Some(shift) = 10u64.checked_pow(num_digits(p)) else {
+                    break;
+                };
+                next.extend((1..10).filter_map(|d| shift.checked_mul(d)?.checked_add(p)));
+            } else {
+                next.extend([1, 3, 7, 9].iter().filter_map(|&d| p.checked_mul(10)?.checked_add(d)));
+            }
+        }
+        next.retain(|&c| is_prime_shared(c));
+        next.sort_unstable();
+        next
+    }
+}
+
+impl Iterator for TruncatablePrimes {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        if self.pos == self.level.len() {
+            self.level = self.next_level();
+            self.pos = 0;
+        }
+        let p = *self.level.get(self.pos)?;
+        self.pos += 1;
+        Some(p)
+    }
+}
+
+// `x mod 2^n + 1`, as a value in `0..=2^n`: since `2^n ≡ -1`, the bits from `n` up are subtracted
+// from the bottom.
+fn fermat_reduce(x: Vec<u64>, n: usize) -> Vec<u64> {
+    if bit_len(&x) <= n || is_power_of_two(&x, n) {
+        return x;
+    }
+    let hi = fermat_reduce(shift_right(&x, n), n);
+    let mut lo = x;
+    lo.truncate(n.div_ceil(64));
+    mask_to(&mut lo, n);
+    if cmp(&lo, &hi) == Ordering::Less {
+        // lo + 2^n + 1 - hi
+        let mut f = vec![0u64; n / 64 + 1];
+        f[n / 64] = 1 << (n % 64);
+        f[0] += 1;
+        add_assign(&mut lo, &f);
+    }
+    sub_assign(&mut lo, &hi);
+    lo
+}
+
+// The full product `x * x`, little-endian.
+fn square(x: &[u64]) -> Vec<u64> {
+    let mut out = vec![0u64; 2 * x.len()];
+    for (i, &a) in x.iter().enumerate() {
+        let mut carry = 0u128;
+        for (j, &b) in x.iter().enumerate() {
+            let t = u128::from(a) * u128::from(b) + u128::from(out[i + j]) + carry;
+            out[i + j] = t as u64;
+            carry = t >> 64;
+        }
+        out[i + x.len()] = carry as u64;
+    }
+    out
+}
+
+// `x mod 2^p - 1`, in `p.div_ceil(64)` words: since `2^p ≡ 1`, the bits from `p` up are folded back
+// onto the bottom until nothing is left above bit `p`.
+fn mersenne_reduce(x: &[u64], p: usize) -> Vec<u64> {
+    let mut x = x.to_vec();
+    while bit_len(&x) > p {
+        let hi = shift_right(&x, p);
+        x.truncate(p.div_ceil(64));
+        mask_to(&mut x, p);
+        add_assign(&mut x, &hi);
+    }
+    x.resize(p.div_ceil(64), 0);
+    x
+}
+
+// `x - 2 mod 2^p - 1`, for `x` below `2^p`, with `p` at least 3.
+fn sub_two(x: &mut [u64], p: usize) {
+    if x[0] < 2 && x[1..].iter().all(|&w| w == 0) {
+        // Wrap around to `2^p - 1 - (2 - x)`.
+        let short = 2 - x[0];
+        x.iter_mut().for_each(|w| *w = u64::MAX);
+        mask_to(x, p);
+        x[0] -= short;
+        return;
+    }
+    let mut borrow = 2;
+    for w in x.iter_mut() {
+        let (v, under) = w.overflowing_sub(borrow);
+        *w = v;
+        if !under {
+            break;
+        }
+        borrow = 1;
+    }
+}
+
+// Clears every bit from `p` up.
+fn mask_to(x: &mut [u64], p: usize) {
+    if !p.is_multiple_of(64) {
+        let last = x.len() - 1;
+        x[last] &= (1 << (p % 64)) - 1;
+    }
+}
+
+fn is_all_ones(x: &[u64], p: usize) -> bool {
+    bit_len(x) == p && x.iter().map(|w| w.count_ones() as usize).sum::<usize>() == p
+}
+
+// Whether `x` is exactly `2^n`.
+fn is_power_of_two(x: &[u64], n: usize) -> bool {
+    bit_len(x) == n + 1 && x.iter().map(|w| w.count_ones()).sum::<u32>() == 1
+}
+
+fn bit_len(x: &[u64]) -> usize {
+    x.iter()
+        .rposition(|&w| w != 0)
+        .map_or(0, |i| 64 * i + 64 - x[i].leading_zeros() as usize)
+}
+
+fn shift_right(x: &[u64], bits: usize) -> Vec<u64> {
+    let (words, bits) = (bits / 64, bits % 64);
+    let mut out: Vec<u64> = x[words.min(x.len())..].to_vec();
+    if bits != 0 {
+        for i in 0..out.len() {
+            let next = out.get(i + 1).map_or(0, |&w| w << (64 - bits));
+            out[i] = (out[i] >> bits) | next;
+        }
+    }
+    out
+}
+
+fn cmp(x: &[u64], y: &[u64]) -> Ordering {
+    bit_len(x).cmp(&bit_len(y)).then_with(|| {
+        let len = x.len().max(y.len());
+        (0..len)
+            .rev()
+            .map(|i| x.get(i).unwrap_or(&0).cmp(y.get(i).unwrap_or(&0)))
+            .find(|o| *o != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    })
+}
+
+// `x -= y`, for `x` at least `y`.
+fn sub_assign(x: &mut [u64], y: &[u64]) {
+    let mut borrow = false;
+    for (i, w) in x.iter_mut().enumerate() {
+        let (v, b1) = w.overflowing_sub(y.get(i).cloned().unwrap_or(0));
+        let (v, b2) = v.overflowing_sub(u64::from(borrow));
+        *w = v;
+        borrow = b1 || b2;
+    }
+}
+
+fn add_assign(x: &mut Vec<u64>, y: &[u64]) {
+    if x.len() < y.len() {
+        x.resize(y.len(), 0);
+    }
+    let mut carry = false;
+    for (i, w) in x.iter_mut().enumerate() {
+        let (v, c1) = w.overflowing_add(y.get(i).cloned().unwrap_or(0));
+        let (v, c2) = v.overflowing_add(u64::from(carry));
+        *w = v;
+        carry = c1 || c2;
+        if !carry && i >= y.len() {
+            break;
+        }
+    }
+    if carry {
+        x.push(1);
+    }
+}
@@ -0,0 +1,74 @@
+//! A smallest-prime-factor table, for factorizing many small numbers quickly.
+
+/**
+A table of the smallest prime factor of every number up to a limit.
+
+Building the table takes about as long as sieving to the limit, after which `factor(n)` takes one
+lookup per prime factor of `n`. Factorizing every number up to the limit this way is far cheaper
+than calling `factors` on each.
+
+Every number up to the limit has a `u32` slot, primes included: a composite's holds its smallest
+factor, which is at most the square root of the limit and so always fits, and a prime's holds 0.
+The table takes `4 * (limit + 1)` bytes.
+**/
+#[derive(Clone)]
+pub struct SpfSieve {
+    // The smallest prime factor of each number, or 0 if it is prime (or 0 or 1).
+    spf: Vec<u32>,
+}
+
+impl SpfSieve {
+    /// Builds the table for every number up to and including `limit`.
+    pub fn new(limit: usize) -> SpfSieve {
+        let mut spf = vec![0u32; limit + 1];
+        let mut p = 2;
+        while p <= limit / p {
+            if spf[p] == 0 {
+                for m in (p * p..=limit).step_by(p) {
+                    if spf[m] == 0 {
+                        spf[m] = p as u32;
+                    }
+                }
+            }
+            p += 1;
+        }
+        SpfSieve { spf }
+    }
+
+    /// The largest number the table covers.
+    pub fn limit(&self) -> usize {
+        self.spf.len() - 1
+    }
+
+    /// The smallest prime factor of `n`, or `n` itself if it is prime, 0 or 1.
+    ///
+    /// Panics if `n` is above the limit.
+    pub fn smallest_factor(&self, n: usize) -> usize {
+        match self.spf[n] {
+            0 => n,
+            p => p as usize,
+        }
+    }
+
+    /// Tests whether `n` is prime with a single lookup.
+    ///
+    /// Panics if `n` is above the limit.
+    pub fn is_prime(&self, n: usize) -> bool {
+        n > 1 && self.spf[n] == 0
+    }
+
+    /// The prime factors of `n`, in ascending order and including repeats. Both 0 and 1 have
+    /// none.
+    ///
+    /// Panics if `n` is above the limit.
+    pub fn factor(&self, mut n: usize) -> Vec<u64> {
+        assert!(n <= self.limit(), "{} is above the limit of the table", n);
+        let mut lst = Vec::new();
+        while n > 1 {
+            let p = self.smallest_factor(n);
+            lst.push(p as u64);
+            n /= p;
+        }
+        lst
+    }
+}
@@ -0,0 +1,81 @@
+//! Writing numbers as sums of two squares, and the Gaussian primes that go with it.
+
+use crate::arith::{is_prime_mr, is_prime_wide};
+use crate::divisors::prime_powers;
+use crate::sqrt_mod;
+
+/**
+The `(a, b)` with `a ≤ b` and `a² + b² = p`, for `p` a prime that is 2 or 1 mod 4, or `None` for
+any other `p`.
+
+By Fermat's theorem on sums of two squares there is exactly one such pair. This finds it with the
+Hermite–Serret algorithm: take a square root `x` of -1 modulo `p`, and run Euclid's algorithm on
+`p` and `x` until the remainder drops below `√p`; that remainder is `a`.
+**/
+pub fn two_squares(p: u64) -> Option<(u64, u64)> {
+    if p == 2 {
+        return Some((1, 1));
+    }
+    if p % 4 != 1 || !is_prime_mr(p) {
+        return None;
+    }
+    let root = p.isqrt();
+    let (mut r0, mut r1) = (p, sqrt_mod(p - 1, p)?);
+    while r1 > root {
+        (r0, r1) = (r1, r0 % r1);
+    }
+    let b = (p - r1 * r1).isqrt();
+    Some((r1.min(b), r1.max(b)))
+}
+
+/**
+Some `(a, b)` with `a ≤ b` and `a² + b² = n`, or `None` if there is none.
+
+A representation exists exactly when every prime that is 3 mod 4 divides `n` an even number of
+times. This factors `n`, writes each prime that is 2 or 1 mod 4 as `x² + y²` with `two_squares`,
+and multiplies those together as the Gaussian integers `x + yi`; the primes that are 3 mod 4 just
+scale the result. The factoring is by trial division, so `n` with two large prime factors is slow.
+**/
+pub fn sum_of_two_squares(n: u64) -> Option<(u64, u64)> {
+    if n == 0 {
+        return Some((0, 0));
+    }
+    // Every partial product has a norm dividing `n`, so its parts stay below 2^32.
+    let (mut re, mut im) = (1i128, 0i128);
+    for (p, e) in prime_powers(n) {
+        if p % 4 == 3 {
+            if e % 2 == 1 {
+                return None;
+            }
+            re *= i128::from(p).pow(e / 2);
+            im *= i128::from(p).pow(e / 2);
+            continue;
+        }
+        let (x, y) = two_squares(p)?;
+        let (x, y) = (i128::from(x), i128::from(y));
+        for _ in 0..e {
+            (re, im) = (re * x - im * y, re * y + im * x);
+        }
+    }
+    let (a, b) = (re.unsigned_abs() as u64, im.unsigned_abs() as u64);
+    Some((a.min(b), a.max(b)))
+}
+
+/**
+Whether the Gaussian integer `re + im i` is a Gaussian prime.
+
+That is the case when both parts are nonzero and the norm `re² + im²` is an ordinary prime, or when
+one part is zero and the other is, up to sign, an ordinary prime that is 3 mod 4.
+
+Norms past `u64` are checked by a Miller–Rabin test that is exact up to 3.3 × 10^24 and probable
+beyond it.
+**/
+pub fn is_gaussian_prime(re: i64, im: i64) -> bool {
+    let (a, b) = (re.unsigned_abs(), im.unsigned_abs());
+    if a == 0 || b == 0 {
+        let n = a.max(b);
+        return n % 4 == 3 && is_prime_mr(n);
+    }
+    let norm = u128::from(a).pow(2) + u128::from(b).pow(2);
+    is_prime_wide(norm)
+}
@@ -0,0 +1,75 @@
+/*!
+Statistics of the gaps between consecutive primes, worked out as the primes are sieved, so no list
+of them is ever kept.
+
+Every function here looks at the gaps between consecutive primes that are both below `limit`, and
+goes through them with a segmented sieve in a fixed amount of memory.
+
+```
+use primes::stats;
+
+let histogram = stats::gap_histogram(100);
+assert_eq!(histogram.get(&2), Some(&8));
+assert_eq!(stats::maximal_gaps(1000).last().map(|r| (r.start, r.gap)), Some((887, 20)));
+```
+*/
+
+use std::collections::BTreeMap;
+
+use crate::range::sieve_range;
+
+/// A gap between consecutive primes that is longer than every one before it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GapRecord {
+    /// The prime the gap follows.
+    pub start: u64,
+    /// The distance to the next prime.
+    pub gap: u64,
+}
+
+// Calls `f` with each prime below `limit` that has another one after it below `limit`, and the
+// gap to that one.
+fn for_each_gap<F: FnMut(u64, u64)>(limit: u64, mut f: F) {
+    let mut prev = None;
+    sieve_range(0..limit, |p| {
+        if let Some(q) = prev {
+            f(q, p - q);
+        }
+        prev = Some(p);
+        true
+    });
+}
+
+/// How many times each gap between consecutive primes occurs, by the length of the gap.
+pub fn gap_histogram(limit: u64) -> BTreeMap<u64, u64> {
+    let mut counts = BTreeMap::new();
+    for_each_gap(limit, |_, gap| *counts.entry(gap).or_insert(0) += 1);
+    counts
+}
+
+/// The mean gap between consecutive primes, or `None` if there are fewer than two primes. It
+/// grows like `ln(limit)`, by the prime number theorem.
+pub fn average_gap(limit: u64) -> Option<f64> {
+    let (mut count, mut last) = (0u64, 2);
+    for_each_gap(limit, |p, gap| {
+        count += 1;
+        last = p + gap;
+    });
+    if count == 0 {
+        None
+    } else {
+        Some((last - 2) as f64 / count as f64)
+    }
+}
+
+/// The maximal gaps: each gap between consecutive primes that is longer than all those before
+/// it, in order. They start 1 (after 2), 2 (after 3), 4 (after 7), 6 (after 23), 8 (after 89).
+pub fn maximal_gaps(limit: u64) -> Vec<GapRecord> {
+    let mut records: Vec<GapRecord> = Vec::new();
+    for_each_gap(limit, |start, gap| {
+        if records.last().is_none_or(|r| gap > r.gap) {
+            records.push(GapRecord { start, gap });
+        }
+    });
+    records
+}
@@ -0,0 +1,177 @@
+//! Sums of arithmetic functions up to `n`, in well under `n` steps.
+
+use crate::range::sieve_range;
+
+/// The most values the table of small prefix sums will hold, 64 MiB worth.
+const MAX_TABLE: u64 = 1 << 23;
+
+/**
+Σ φ(k) for `k` in `1..=n`, the number of fractions in lowest terms with denominator at most `n`.
+
+This takes about `n^(2/3)` steps (see `mertens` for how), so `n` of 10^11 or more is practical. The
+sum is exact as long as `n` is below 2^63.
+**/
+pub fn totient_summatory(n: u64) -> u128 {
+    let small = small_table(n, |k| k as i64, |phi, p| {
+        for j in (p..phi.len()).step_by(p) {
+            phi[j] -= phi[j] / p as i64;
+        }
+    });
+    // φ * 1 is the identity, whose prefix sums are the triangular numbers.
+    dirichlet_sum(n, &small, |x| {
+        let x = i128::from(x);
+        x * (x + 1) / 2
+    }) as u128
+}
+
+/**
+The Mertens function M(n): Σ μ(k) for `k` in `1..=n`, where μ is the Möbius function.
+
+This uses Du's sieve: the sums up to about `n^(2/3)` are read from a table, and the rest come from
+the identity Σ M(n/d) = 1 (over `d` in `1..=n`), grouping together the `d` that share a value of
+`n/d`. That takes about `n^(2/3)` steps, so `n` of 10^11 or more is practical.
+**/
+pub fn mertens(n: u64) -> i64 {
+    let small = small_table(n, |_| 1, |mu, p| {
+        for j in (p..mu.len()).step_by(p) {
+            mu[j] = -mu[j];
+        }
+        let pp = p.saturating_mul(p);
+        for j in (pp..mu.len()).step_by(pp) {
+            mu[j] = 0;
+        }
+    });
+    // μ * 1 is 1 at 1 and 0 everywhere else.
+    dirichlet_sum(n, &small, |_| 1) as i64
+}
+
+/**
+An arithmetic function f together with its prefix sums F(x) = f(1) + ... + f(x), as used by
+`hyperbola_sum`.
+
+Any pair of closures `(f, F)` implements this, so `(|_| 1, |x| x as i128)` is the constant
+function 1.
+**/
+pub trait PrefixSummable {
+    /// f(k), for `k` of at least 1.
+    fn value(&self, k: u64) -> i128;
+
+    /// F(x) = f(1) + ... + f(x), which is 0 for `x` of 0.
+    fn prefix_sum(&self, x: u64) -> i128;
+}
+
+impl<V, S> PrefixSummable for (V, S)
+where
+    V: Fn(u64) -> i128,
+    S: Fn(u64) -> i128,
+{
+    fn value(&self, k: u64) -> i128 {
+        (self.0)(k)
+    }
+
+    fn prefix_sum(&self, x: u64) -> i128 {
+        (self.1)(x)
+    }
+}
+
+/**
+Σ (f * g)(k) for `k` in `1..=n`, where `f * g` is the Dirichlet convolution of `f` and `g`, in
+about `2 sqrt(n)` evaluations.
+
+This is Dirichlet's hyperbola method: every pair of `(d, e)` with `d e ≤ n` has `d` or `e` (or
+both) at most `sqrt(n)`, so with `u = sqrt(n)`
+
+```text
+Σ (f * g)(k) = Σ f(d) G(n/d) + Σ g(e) F(n/e) - F(u) G(u)   (over d, e in 1..=u)
+```
+**/
+pub fn hyperbola_sum<F: PrefixSummable, G: PrefixSummable>(f: &F, g: &G, n: u64) -> i128 {
+    let u = n.isqrt();
+    (1..=u)
+        .map(|d| f.value(d) * g.prefix_sum(n / d) + g.value(d) * f.prefix_sum(n / d))
+        .sum::<i128>()
+        - f.prefix_sum(u) * g.prefix_sum(u)
+}
+
+/// Σ d(k) for `k` in `1..n`, where d(k) is the number of divisors of `k`, in about `sqrt(n)`
+/// steps.
+pub fn sum_num_divisors_below(n: u64) -> u64 {
+    // d = 1 * 1, and the constant 1 sums to `x` itself.
+    let one = (|_| 1, |x| i128::from(x));
+    hyperbola_sum(&one, &one, n.saturating_sub(1)) as u64
+}
+
+/**
+The prefix sums F(x) of a multiplicative function f, for `x` from 0 up to the table size for `n`.
+
+The values start out as `init(k)`, then `apply(values, p)` is called for each prime `p` in turn to
+fold in its effect on the multiples of `p`, before everything is summed.
+**/
+fn small_table<I, A>(n: u64, init: I, mut apply: A) -> Vec<i64>
+where
+    I: Fn(usize) -> i64,
+    A: FnMut(&mut [i64], usize),
+{
+    let len = table_len(n);
+    let mut values: Vec<i64> = (0..len).map(|k| if k == 0 { 0 } else { init(k) }).collect();
+    sieve_range(0..len as u64, |p| {
+        apply(&mut values, p as usize);
+        true
+    });
+    for i in 1..len {
+        values[i] += values[i - 1];
+    }
+    values
+}
+
+// How many prefix sums to tabulate for `n`: about `n^(2/3)`, but never fewer than `sqrt(n)`, which
+// `dirichlet_sum` relies on.
+fn table_len(n: u64) -> usize {
+    let cbrt = (n as f64).cbrt() as u64;
+    let len = (cbrt * cbrt).min(MAX_TABLE).max(n.isqrt()).min(n);
+    len as usize + 1
+}
+
+/**
+F(n) = f(1) + ... + f(n), given the prefix sums `small` of f up to at least `sqrt(n)`, and the
+prefix sums `h` of the Dirichlet convolution f * 1.
+
+Every x = n/k is worked out in turn, smallest first, from
+
+```text
+F(x) = H(x) - Σ F(x/d)   (over d in 2..=x)
+```
+
+where the terms that share a value of x/d are added up together. Each F(x/d) is then either in the
+table or an x already worked out.
+**/
+fn dirichlet_sum<H: Fn(u64) -> i128>(n: u64, small: &[i64], h: H) -> i128 {
+    let lim = small.len() as u64;
+    if n < lim {
+        return i128::from(small[n as usize]);
+    }
+
+    // `large[k]` holds F(n/k), for every n/k past the table.
+    let ks = (n / lim) as usize;
+    let mut large = vec![0i128; ks + 1];
+    let f = |x: u64, large: &[i128]| {
+        if x < lim {
+            i128::from(small[x as usize])
+        } else {
+            large[(n / x) as usize]
+        }
+    };
+    for k in (1..=ks).rev() {
+        let x = n / k as u64;
+        let mut sum = h(x);
+        let mut d = 2;
+        while d <= x {
+            let q = x / d;
+            let hi = x / q;
+            sum -= i128::from(hi - d + 1) * f(q, &large);
+            d = hi + 1;
+        }
+        large[k] = sum;
+    }
+    large[1]
+}
@@ -0,0 +1,94 @@
+/*!
+Support for `primes_table!`, which builds a prime table at compile time, and for the `table`
+feature's built-in table.
+
+Everything here is a `const fn`, so the sieve runs inside the compiler and the table ends up in the
+binary as plain static data. The functions are public only so that the macro can reach them from
+other crates.
+*/
+
+/// The first 10,000 primes, up to 104,729, which generators start out with under the `table`
+/// feature.
+#[cfg(feature = "table")]
+pub(crate) static FIRST_PRIMES: [u64; 10_000] = fill::<104_730, 10_000>();
+
+/**
+Embeds a table of every prime up to and including a bound in the binary, and evaluates to a
+`PrimeSlice<'static>` over it.
+
+The table is sieved by the compiler, so nothing is generated at run time, and because it comes
+from a plain sieve of Eratosthenes rather than from stored data there's nothing to get out of date.
+The bound has to be a constant expression; large bounds slow down compilation accordingly.
+
+```
+use primes::primes_table;
+
+let small = primes_table!(up_to = 100_000);
+assert_eq!(small.len(), 9_592);
+assert!(small.contains(99_991));
+```
+**/
+#[cfg(feature = "macros")]
+#[macro_export]
+macro_rules! primes_table {
+    (up_to = $n:expr) => {{
+        const N: usize = $n as usize + 1;
+        const LEN: usize = $crate::table::count::<N>();
+        static TABLE: [u64; LEN] = $crate::table::fill::<N, LEN>();
+        $crate::PrimeSlice::new_unchecked(&TABLE)
+    }};
+}
+
+/// `sieve::<N>()[k]` is whether `k` is composite (or 0 or 1), for `k` below `N`.
+pub const fn sieve<const N: usize>() -> [bool; N] {
+    let mut composite = [false; N];
+    let mut k = 0;
+    while k < N && k < 2 {
+        composite[k] = true;
+        k += 1;
+    }
+    let mut p = 2;
+    while p * p < N {
+        if !composite[p] {
+            let mut m = p * p;
+            while m < N {
+                composite[m] = true;
+                m += p;
+            }
+        }
+        p += 1;
+    }
+    composite
+}
+
+/// The number of primes below `N`.
+pub const fn count<const N: usize>() -> usize {
+    let composite = sieve::<N>();
+    let mut count = 0;
+    let mut k = 0;
+    while k < N {
+        if !composite[k] {
+            count += 1;
+        }
+        k += 1;
+    }
+    count
+}
+
+/// The `LEN` primes below `N`, in order. Fails to compile if there aren't exactly `LEN`.
+pub const fn fill<const N: usize, const LEN: usize>() -> [u64; LEN] {
+    let composite = sieve::<N>();
+    let mut out = [0; LEN];
+    let mut i = 0;
+    let mut k = 0;
+    while k < N {
+        if !composite[k] {
+            assert!(i < LEN, "more primes than the table has room for");
+            out[i] = k as u64;
+            i += 1;
+        }
+        k += 1;
+    }
+    assert!(i == LEN, "fewer primes than the table has room for");
+    out
+}
@@ -0,0 +1,117 @@
+//! Plain-text formats shared with other number theory tools: OEIS b-files and factorization lines.
+
+use std::io::{self, BufRead, Write};
+
+/**
+Writes `values` in OEIS b-file format, one `index value` line each, numbering them from `first`.
+
+```
+let mut out = Vec::new();
+primes::write_bfile(&mut out, 1, [2, 3, 5]).unwrap();
+assert_eq!(out, b"1 2\n2 3\n3 5\n");
+```
+**/
+pub fn write_bfile<W, I>(out: &mut W, first: u64, values: I) -> io::Result<()>
+where
+    W: Write + ?Sized,
+    I: IntoIterator<Item = u64>,
+{
+    for (i, v) in (first..).zip(values) {
+        writeln!(out, "{} {}", i, v)?;
+    }
+    Ok(())
+}
+
+/**
+Reads an OEIS b-file into `(index, value)` pairs.
+
+Blank lines and comments starting with `#` are skipped, as is anything after the value on a line.
+Any other line that isn't two integers is an `InvalidData` error naming its line number. Values
+past `u64::MAX`, which b-files for faster-growing sequences have, are errors too.
+**/
+pub fn read_bfile<R: BufRead>(input: R) -> io::Result<Vec<(u64, u64)>> {
+    let mut out = Vec::new();
+    for (line_no, line) in input.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split_whitespace().map(|f| f.parse::<u64>().ok());
+        match (fields.next().flatten(), fields.next().flatten()) {
+            (Some(i), Some(v)) => out.push((i, v)),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {} is not `index value`", line_no + 1),
+                ))
+            }
+        }
+    }
+    Ok(out)
+}
+
+/**
+Formats a factorization as `n: p1^e1 * p2^e2 * ...`, leaving out exponents of 1.
+
+`factors` are the prime factors of `n` with repeats, in any order, as `factors` returns them. 1 has
+nothing after the colon.
+
+```
+assert_eq!(primes::format_factorization(360, &[2, 2, 2, 3, 3, 5]), "360: 2^3 * 3^2 * 5");
+```
+**/
+pub fn format_factorization(n: u64, factors: &[u64]) -> String {
+    let mut sorted = factors.to_vec();
+    sorted.sort_unstable();
+    let mut terms = Vec::new();
+    let mut rest = &sorted[..];
+    while let Some(&p) = rest.first() {
+        let e = rest.iter().take_while(|&&q| q == p).count();
+        terms.push(if e == 1 {
+            p.to_string()
+        } else {
+            format!("{}^{}", p, e)
+        });
+        rest = &rest[e..];
+    }
+    if terms.is_empty() {
+        format!("{}:", n)
+    } else {
+        format!("{}: {}", n, terms.join(" * "))
+    }
+}
+
+/**
+Parses a line written by `format_factorization` back into `n` and its prime factors, with repeats
+and in ascending order.
+
+Terms may also be separated by spaces alone, as GNU `factor` prints them. Returns `None` if the line
+doesn't parse, `n` is 0, a factor is below 2, or the factors don't multiply to `n`; whether they
+are prime isn't checked. Exponents are bounded by the product before anything is stored, so no
+input can make this allocate more than 63 factors per term.
+**/
+pub fn parse_factorization(line: &str) -> Option<(u64, Vec<u64>)> {
+    let (n, rest) = line.split_once(':')?;
+    let n = n.trim().parse::<u64>().ok().filter(|&n| n > 0)?;
+    let mut factors = Vec::new();
+    let mut product = 1u64;
+    for term in rest.split(|c: char| c == '*' || c.is_whitespace()).filter(|t| !t.is_empty()) {
+        let (p, e) = match term.split_once('^') {
+            Some((p, e)) => (p.parse::<u64>().ok()?, e.parse::<u32>().ok()?),
+            None => (term.parse::<u64>().ok()?, 1),
+        };
+        if p < 2 {
+            return None;
+        }
+        // With `p` at least 2, this fails for any `e` past 63.
+        product = product.checked_mul(p.checked_pow(e)?)?;
+        factors.extend(std::iter::repeat_n(p, e as usize));
+    }
+    factors.sort_unstable();
+    if product == n {
+        Some((n, factors))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,41 @@
+//! Euler's totient and Carmichael's function, worked out from the prime factorization.
+
+use crate::arith::gcd;
+use crate::divisors::prime_powers;
+
+/// Euler's totient φ(n): how many numbers in `1..=n` are coprime to `n`. φ(0) is taken to be 0.
+pub fn euler_phi(n: u64) -> u64 {
+    if n == 0 {
+        return 0;
+    }
+    prime_powers(n)
+        .iter()
+        .map(|&(p, e)| p.pow(e - 1) * (p - 1))
+        .product()
+}
+
+/**
+Carmichael's function λ(n): the smallest `m` such that `a^m ≡ 1 (mod n)` for every `a` coprime to
+`n`. λ(0) is taken to be 0.
+
+λ(n) always divides φ(n), and is the exponent of the multiplicative group modulo `n`, so it is
+what multiplicative orders and RSA private exponents are reduced by.
+**/
+pub fn carmichael_lambda(n: u64) -> u64 {
+    if n == 0 {
+        return 0;
+    }
+    prime_powers(n).iter().fold(1, |acc, &(p, e)| {
+        let l = match (p, e) {
+            (2, 1) => 1,
+            (2, 2) => 2,
+            (2, _) => 1 << (e - 2),
+            _ => p.pow(e - 1) * (p - 1),
+        };
+        lcm(acc, l)
+    })
+}
+
+fn lcm(a: u64, b: u64) -> u64 {
+    a / gcd(a, b) * b
+}
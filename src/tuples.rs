@@ -0,0 +1,63 @@
+//! Prime k-tuples: which patterns of offsets can hold infinitely many primes, and how often.
+
+use crate::range::sieve_range;
+
+// The number of distinct residues modulo `p` among the offsets.
+fn residues(pattern: &[u64], p: u64) -> usize {
+    let mut rs: Vec<u64> = pattern.iter().map(|&h| h % p).collect();
+    rs.sort_unstable();
+    rs.dedup();
+    rs.len()
+}
+
+/**
+Whether `pattern`, a set of offsets such as `[0, 2, 6]`, is admissible: whether `n + h` can be
+prime for every offset `h` at once for more than finitely many `n`.
+
+That is the case unless the offsets cover every residue modulo some prime, which then always
+divides one of `n + h`. Only primes up to the number of offsets can be covered, so this is quick.
+**/
+pub fn is_admissible(pattern: &[u64]) -> bool {
+    let k = residues(pattern, u64::MAX) as u64;
+    let mut ok = true;
+    sieve_range(0..k + 1, |p| {
+        ok = residues(pattern, p) < p as usize;
+        ok
+    });
+    ok
+}
+
+/**
+The Hardy–Littlewood constant for `pattern`, a set of offsets such as `[0, 2]`.
+
+The conjecture is that the number of `n` up to `x` with every `n + h` prime is about this constant
+times `∫ dt / ln(t)^k` (from 2 to `x`), where `k` is the number of offsets. The constant is the
+Euler product
+
+```text
+Π (1 - w(p)/p) / (1 - 1/p)^k   (over all primes p)
+```
+
+where `w(p)` is the number of residues modulo `p` the offsets cover. The product is cut off after
+the primes up to `precision`; the terms approach 1 like `1/p^2`, so the error shrinks roughly like
+`1 / (precision ln precision)`. For twin primes, `[0, 2]`, this is 2C₂ ≈ 1.3203. Patterns that
+aren't admissible get 0.
+**/
+pub fn hardy_littlewood_constant(pattern: &[u64], precision: u64) -> f64 {
+    if !is_admissible(pattern) {
+        return 0.0;
+    }
+    let k = residues(pattern, u64::MAX);
+    let span = pattern.iter().max().unwrap_or(&0) - pattern.iter().min().unwrap_or(&0);
+
+    // Summed as logarithms, which keeps the rounding error of a long product down.
+    let mut log = 0.0;
+    sieve_range(0..precision.saturating_add(1), |p| {
+        // Past the span of the pattern, every offset falls in a residue of its own.
+        let w = if p > span { k } else { residues(pattern, p) };
+        let pf = p as f64;
+        log += (-(w as f64) / pf).ln_1p() - k as f64 * (-1.0 / pf).ln_1p();
+        true
+    });
+    log.exp()
+}
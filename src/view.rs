@@ -0,0 +1,87 @@
+//! Read-only access to the primes a generator has already found.
+
+use std::ops::Range;
+use std::slice;
+
+/**
+A read-only view of the primes a prime set has found so far, from `PrimeSet::view()`.
+
+Nothing here can find more primes, so code that is handed a `PrimeView` is guaranteed by the type
+system not to expand the set; every answer comes from what is already in the list, and questions
+it can't answer return `None`. The view is `Copy`, and several can be alive at once.
+**/
+#[derive(Clone, Copy, Debug)]
+pub struct PrimeView<'a> {
+    lst: &'a [u64],
+}
+
+impl<'a> PrimeView<'a> {
+    pub(crate) fn new(lst: &'a [u64]) -> PrimeView<'a> {
+        PrimeView { lst }
+    }
+
+    /// Number of primes found so far.
+    pub fn len(&self) -> usize {
+        self.lst.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lst.is_empty()
+    }
+
+    /// The largest prime found so far.
+    pub fn last(&self) -> Option<u64> {
+        self.lst.last().cloned()
+    }
+
+    /// All primes found so far, as a slice.
+    pub fn list(&self) -> &'a [u64] {
+        self.lst
+    }
+
+    /// Iterator over the primes found so far.
+    pub fn iter(&self) -> slice::Iter<'a, u64> {
+        self.lst.iter()
+    }
+
+    /// The prime at `index`, if it has been found.
+    pub fn get(&self, index: usize) -> Option<u64> {
+        self.lst.get(index).cloned()
+    }
+
+    /// Find the next largest prime from a number, if it has been found.
+    ///
+    /// Returns `(idx, prime)`
+    pub fn find_vec(&self, n: u64) -> Option<(usize, u64)> {
+        let idx = self.lst.partition_point(|&p| p < n);
+        self.lst.get(idx).map(|&p| (idx, p))
+    }
+
+    /// Whether `n` is prime, if the primes found so far reach it, and `None` if they don't, as
+    /// with `PrimeSet::contains_cached`.
+    pub fn contains_cached(&self, n: u64) -> Option<bool> {
+        if self.last()? < n {
+            return None;
+        }
+        Some(self.lst.binary_search(&n).is_ok())
+    }
+
+    /// The number of primes less than or equal to `n`, π(n), if every prime up to `n` has been
+    /// found.
+    pub fn count_up_to(&self, n: u64) -> Option<usize> {
+        if self.last()? < n {
+            return None;
+        }
+        Some(self.lst.partition_point(|&p| p <= n))
+    }
+
+    /// The primes in `range`, if every prime in it has been found.
+    pub fn primes_in(&self, range: Range<u64>) -> Option<&'a [u64]> {
+        if range.end > 0 && self.last()? < range.end - 1 {
+            return None;
+        }
+        let lo = self.lst.partition_point(|&p| p < range.start);
+        let hi = self.lst.partition_point(|&p| p < range.end);
+        Some(&self.lst[lo..hi.max(lo)])
+    }
+}
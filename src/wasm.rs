@@ -0,0 +1,70 @@
+/*!
+JavaScript bindings, for using the crate from the browser or Node.
+
+Build with `wasm-pack build --features wasm`. Numbers cross over as `BigInt`s, so they are exact
+over the whole `u64` range, and lists of them as `BigUint64Array`s:
+
+```js
+import { isPrime, nextPrime, factors, PrimeStream } from "primes";
+
+isPrime(97n);                  // true
+nextPrime(100n);               // 101n
+factors(600851475143n);        // BigUint64Array [71n, 839n, 1471n, 6857n]
+const stream = new PrimeStream(1000n);
+stream.next();                 // 1009n
+stream.take(3);                // BigUint64Array [1013n, 1019n, 1021n]
+```
+*/
+
+use wasm_bindgen::prelude::*;
+
+use crate::arith::is_prime_mr;
+use crate::{next_prime_at_least, Factorizer};
+
+/// Whether `n` is prime.
+#[wasm_bindgen(js_name = isPrime)]
+pub fn is_prime(n: u64) -> bool {
+    is_prime_mr(n)
+}
+
+/// The smallest prime greater than `n`, or `undefined` if there is none below 2^64.
+#[wasm_bindgen(js_name = nextPrime)]
+pub fn next_prime(n: u64) -> Option<u64> {
+    n.checked_add(1).and_then(next_prime_at_least)
+}
+
+/// The prime factors of `n`, in ascending order and with repeats. Both 0 and 1 have none.
+#[wasm_bindgen]
+pub fn factors(n: u64) -> Vec<u64> {
+    Factorizer::complete().factor(n).primes
+}
+
+/// The primes in order from a starting point, each found as it's asked for, in constant memory
+/// however far along the `u64` range it starts.
+#[wasm_bindgen]
+pub struct PrimeStream {
+    // The number to look for the next prime from, or `None` once past the last one.
+    from: Option<u64>,
+}
+
+#[wasm_bindgen]
+impl PrimeStream {
+    /// A stream of the primes from `start` on.
+    #[wasm_bindgen(constructor)]
+    pub fn new(start: u64) -> PrimeStream {
+        PrimeStream { from: Some(start) }
+    }
+
+    /// The next prime, or `undefined` past the largest prime below 2^64.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<u64> {
+        let p = self.from.and_then(next_prime_at_least);
+        self.from = p.and_then(|p| p.checked_add(1));
+        p
+    }
+
+    /// The next `count` primes, fewer only past the largest prime below 2^64.
+    pub fn take(&mut self, count: usize) -> Vec<u64> {
+        (0..count).map_while(|_| self.next()).collect()
+    }
+}
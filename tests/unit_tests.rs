@@ -28,7 +28,7 @@ fn primeset_iterator() {
 
 #[test]
 fn primeset_find_primes() {
-    let mut pset = TrialDivision::new();
+    let mut pset = TrialDivision::from_primes(vec![2, 3]).unwrap();
 
     // pset is empty, so it needs to generate the primes
     assert_eq!(pset.find_vec(1000), None);
@@ -78,7 +78,7 @@ fn prime_factorization() {
 		let ufacts = factors_unique(n);
 
 		// Get unique factors from the list we made above
-		let mut ufacts_exp: Vec<u64> = v.iter().map(|&x| x).collect();
+		let mut ufacts_exp: Vec<u64> = v.to_vec();
 		ufacts_exp.dedup();
 
 		assert_eq!(ufacts, ufacts_exp);
@@ -87,3 +87,2539 @@ fn prime_factorization() {
 	pset = TrialDivision::new();
 	assert_eq!(pset.prime_factors(12), vec![2, 2, 3]);
 }
+
+#[test]
+fn compressed_matches_trial_division() {
+    let mut pset = TrialDivision::from_primes(vec![2, 3]).unwrap();
+    let mut cset = CompressedPrimeSet::new();
+
+    assert_eq!(cset.get(2000), pset.get(2000));
+    assert_eq!(cset.len(), pset.len());
+    assert!(cset.iter_decoded().eq(pset.iter_vec().cloned()));
+    assert!(cset.iter().take(3000).eq(pset.iter().take(3000)));
+
+    for &n in &[0, 2, 3, 4, 100, 1000, 7919, 7920] {
+        assert_eq!(cset.find(n), pset.find(n));
+        assert_eq!(cset.contains(n), pset.contains(n));
+    }
+    assert_eq!(cset.find_vec(u64::MAX), None);
+    assert_eq!(cset.get(100), pset.get(100));
+    assert_eq!(cset[..10], pset[..10]);
+    assert_eq!(cset[2000], pset[2000]);
+    assert_eq!(cset.prime_factors(7919 * 7907), vec![7907, 7919]);
+    assert!(cset.is_prime_cached(1_000_000_007));
+
+    // expand_to sieves the stretch in one go.
+    cset.expand_to(1_400_000);
+    pset.expand_to(1_400_000);
+    assert_eq!(cset.len(), pset.len());
+    assert!(cset.iter_decoded().eq(pset.iter_vec().cloned()));
+    assert_eq!(cset.find(1_357_202), pset.find(1_357_202));
+
+    // The slice is decoded on demand, and keeps up as the set grows.
+    assert_eq!(cset.list(), pset.list());
+    assert_eq!(cset.find(1_500_000), pset.find(1_500_000));
+    assert_eq!(cset.list(), pset.list());
+}
+
+#[test]
+fn primorial_modulo() {
+    // 13# = 30030
+    assert_eq!(primorial_mod(13, 1_000_000), 30030);
+    assert_eq!(primorial_mod(16, 1_000), 30);
+    assert_eq!(primorial_mod(1, 7), 1);
+    assert_eq!(primorial_mod(100, 1), 0);
+    assert_eq!(product_of_primes_in_mod(5..12, 1_000), 5 * 7 * 11);
+    assert_eq!(product_of_primes_in_mod(24..29, 1_000), 1);
+    // The range is sieved, so it can start anywhere.
+    let far = 1_000_000_000_000..1_000_000_000_100;
+    assert_eq!(product_of_primes_in_mod(far, u64::MAX), 4_036_004_342_411_243_002);
+    assert_eq!(primorial_mod(1_000_000, 30_030), 0);
+}
+
+#[cfg(feature = "bigint")]
+#[test]
+fn batch_gcd_finds_shared_factors() {
+    use num_bigint::BigUint;
+
+    let moduli: Vec<BigUint> = [1009u64 * 1013, 1019 * 1021, 1009 * 1031, 1033 * 1039]
+        .iter()
+        .map(|&n| BigUint::from(n))
+        .collect();
+    let gcds: Vec<BigUint> = [1009u64, 1, 1009, 1].iter().map(|&n| BigUint::from(n)).collect();
+
+    assert_eq!(batch_gcd(&moduli), gcds);
+    assert!(batch_gcd(&[]).is_empty());
+
+    // A 0 shares a factor with everything.
+    let big = |v: &[u64]| -> Vec<BigUint> { v.iter().map(|&n| BigUint::from(n)).collect() };
+    assert_eq!(batch_gcd(&big(&[6, 0, 35])), big(&[6, 210, 35]));
+    assert_eq!(batch_gcd(&big(&[0, 7, 0])), big(&[0, 7, 0]));
+    assert_eq!(batch_gcd(&big(&[0])), big(&[1]));
+}
+
+#[test]
+fn sieve_matches_trial_division() {
+    let mut pset = TrialDivision::new();
+    let mut sieve = Sieve::new();
+
+    assert_eq!(sieve.get(20_000), pset.get(20_000));
+    assert_eq!(sieve.list()[..=20_000], pset.list()[..]);
+    assert_eq!(sieve.find(1000), (168, 1009));
+    assert_eq!(sieve.prime_factors(10_000_000), pset.prime_factors(10_000_000));
+}
+
+#[cfg(feature = "bigint")]
+#[test]
+fn product_and_remainder_trees() {
+    use num_bigint::BigUint;
+
+    let values: Vec<BigUint> = [3u64, 5, 7, 11, 13].iter().map(|&n| BigUint::from(n)).collect();
+    let tree = product_tree(&values);
+
+    assert_eq!(tree.len(), 4);
+    assert_eq!(tree[3], vec![BigUint::from(15015u64)]);
+    assert_eq!(tree[1], vec![BigUint::from(15u64), BigUint::from(77u64), BigUint::from(13u64)]);
+
+    let rems: Vec<BigUint> = [1u64, 0, 6, 10, 12].iter().map(|&n| BigUint::from(n)).collect();
+    assert_eq!(remainder_tree(BigUint::from(1000u64), &tree), rems);
+}
+
+#[test]
+fn primorial_mod_large_modulus() {
+    // Exercises the full 128-bit product in modular multiplication.
+    let m = 18_446_744_073_709_551_557; // largest prime below 2^64
+    let full: u128 = (2..=97u128).filter(|&n| is_prime(n as u64)).product();
+
+    assert_eq!(u128::from(primorial_mod(97, m)), full % u128::from(m));
+    assert_eq!(u128::from(primorial_mod(97, u64::MAX)), full % u128::from(u64::MAX));
+}
+
+#[test]
+fn wheel_trial_division() {
+    let small = vec![2u64, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59];
+    assert_eq!((0..60).filter(|&n| is_prime(n)).collect::<Vec<_>>(), small);
+
+    let mut pset = TrialDivision::new();
+    let mut sieve = Sieve::new();
+    assert_eq!(pset.get(5000), sieve.get(5000));
+    assert!((0..10_000).all(|n| is_prime(n) == (sieve.find(n).1 == n)));
+
+    assert_eq!(factors(25 * 49 * 121), vec![5, 5, 7, 7, 11, 11]);
+}
+
+#[test]
+fn full_u64_range() {
+    assert!(!is_prime(u64::MAX));
+    assert_eq!(factors(u64::MAX), vec![3, 5, 17, 257, 641, 65537, 6700417]);
+    assert_eq!(factors_unique(u64::MAX - 1), vec![2, 7, 73, 127, 337, 92737, 649657]);
+    assert_eq!(factors_v2(1 << 63), vec![2; 63]);
+
+    let mut pset = TrialDivision::from_primes(vec![2, 3]).unwrap();
+    assert_eq!(pset.try_expand(), Some(5));
+    assert_eq!(pset.prime_factors(u64::MAX), factors(u64::MAX));
+}
+
+// Trial division has to run all the way to 2^32 for this, so it is slow in debug builds.
+#[test]
+#[ignore]
+fn largest_u64_prime() {
+    assert!(is_prime(LARGEST_U64_PRIME));
+    assert_eq!(factors(LARGEST_U64_PRIME), vec![LARGEST_U64_PRIME]);
+}
+
+#[test]
+fn near_largest_u64_prime() {
+    let top = LARGEST_U64_PRIME;
+    let mut out = [0u64; 32];
+    let (count, next) = fill_primes(u64::MAX - 1000..u64::MAX, &mut out);
+    assert_eq!((count, next), (21, u64::MAX));
+    assert_eq!(out[0], u64::MAX - 944);
+    assert_eq!(out[20], top);
+    assert!(out[..count].iter().all(|&p| is_prime_const(p)));
+    assert_eq!(fill_primes(top - 1..u64::MAX, &mut out), (1, u64::MAX));
+    assert_eq!(fill_primes(top + 1..u64::MAX, &mut out), (0, u64::MAX));
+
+    // Saved lists that end with the prime before the top. The odd numbers from there to the top
+    // all have a factor below 500, so the small primes are enough to carry on from it.
+    let mut seed = Sieve::from_prefix(vec![2, 3]).unwrap();
+    seed.expand_to(500);
+    let mut seed = seed.list()[..seed.list().partition_point(|&p| p < 500)].to_vec();
+    seed.push(top - 24);
+
+    let mut sieve = Sieve::resume(seed.clone(), vec![0; 4]).unwrap();
+    assert_eq!(sieve.try_expand(), Some(top));
+    assert_eq!(sieve.try_expand(), None);
+    assert_eq!(sieve.expand_step(100), StepResult::Done);
+    assert!(Sieve::resume(vec![2, 3, u64::MAX], vec![0; 4]).is_none());
+
+    let mut trial = TrialDivision::from_primes(seed).unwrap();
+    assert_eq!(trial.try_expand(), Some(top));
+    assert_eq!(trial.try_expand(), None);
+    assert_eq!(trial.find_vec(top - 23), Some((trial.len() - 1, top)));
+}
+
+#[test]
+fn sieve_with_caller_buffers() {
+    let mut segment = [0u64; 3];
+    let mut sieve = Sieve::with_buffers(Vec::with_capacity(10_000), &mut segment[..]);
+    let mut pset = TrialDivision::from_primes(vec![2, 3]).unwrap();
+
+    assert_eq!(sieve.get(5000), pset.get(5000));
+
+    let (primes, _) = sieve.into_buffers();
+    assert!(primes.capacity() >= 10_000);
+    assert_eq!(primes[..=5000], pset.list()[..]);
+}
+
+#[cfg(all(feature = "hugepages", target_os = "linux"))]
+#[test]
+fn sieve_in_huge_pages() {
+    let buf = HugePageBuffer::new(1 << 16).unwrap();
+    assert_eq!(buf.as_ref().len(), 1 << 16);
+    assert!(buf.as_ref().iter().all(|&w| w == 0));
+
+    let mut sieve = Sieve::with_buffers(Vec::new(), buf);
+    assert_eq!(sieve.find(10_000_000), (664_579, 10_000_019));
+}
+
+#[test]
+fn cached_primes_shared_reference() {
+    let cache = CachedPrimes::from(Sieve::from_prefix(vec![2, 3]).unwrap());
+    let (a, b) = (&cache, &cache);
+
+    // Two live iterators over the same cache, each expanding it as needed.
+    for (m, n) in a.iter().zip(b.iter().skip(1)).take(1000) {
+        assert!(m < n);
+    }
+    assert_eq!(a.get(168), 1009);
+    assert_eq!(b.find(1000), (168, 1009));
+    assert!(cache.len() > 1000);
+
+    let cache = CachedPrimes::from(TrialDivision::from_primes(vec![2, 3]).unwrap());
+    assert_eq!(cache.iter().nth(9), Some(29));
+    assert_eq!(cache.into_inner().len(), 10);
+}
+
+#[test]
+fn fill_primes_in_chunks() {
+    let mut pset = Sieve::new();
+    let expected: Vec<u64> = pset.iter().skip_while(|&p| p < 999_000).take_while(|&p| p < 1_100_000).collect();
+
+    let mut out = [0u64; 1000];
+    let mut got = Vec::new();
+    let mut start = 999_000;
+    loop {
+        let (count, next) = fill_primes(start..1_100_000, &mut out);
+        got.extend_from_slice(&out[..count]);
+        if next == 1_100_000 {
+            break;
+        }
+        start = next;
+    }
+    assert_eq!(got, expected);
+
+    assert_eq!(fill_primes(0..30, &mut out), (10, 30));
+    assert_eq!(out[..10], [2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+    assert_eq!(fill_primes(0..30, &mut out[..3]), (3, 7));
+    assert_eq!(fill_primes(24..29, &mut out), (0, 29));
+
+    let t = 1_000_000_000_000;
+    assert_eq!(fill_primes(t..t + 100, &mut out[..3]), (3, t + 91));
+    assert_eq!(out[..3], [t + 39, t + 61, t + 63]);
+}
+
+#[test]
+fn shared_prime_set_across_threads() {
+    let shared = SharedPrimeSet::new();
+    let handles: Vec<_> = (0..4)
+        .map(|t| {
+            let shared = shared.clone();
+            std::thread::spawn(move || shared.get(1000 * (t + 1)))
+        })
+        .collect();
+    let got: Vec<u64> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+    let mut pset = TrialDivision::new();
+    assert_eq!(got, vec![pset.get(1000), pset.get(2000), pset.get(3000), pset.get(4000)]);
+    assert!(shared.len() > 4000);
+    assert_eq!(shared.find(1000), (168, 1009));
+
+    let iter = shared.iter();
+    let first: Vec<u64> = std::thread::spawn(move || iter.take(5).collect()).join().unwrap();
+    assert_eq!(first, vec![2, 3, 5, 7, 11]);
+}
+
+#[test]
+fn global_cache_is_shared() {
+    let p = std::thread::spawn(|| global().get(10_000)).join().unwrap();
+    assert_eq!(p, 104_743);
+    #[cfg(not(feature = "tiny"))]
+    assert!(global().len() > 10_000);
+    assert!(std::ptr::eq(global(), global()));
+}
+
+#[test]
+fn caches_past_the_tiny_cap() {
+    // With the `tiny` feature these go past the cap, so are answered without growing the cache.
+    let expected: Vec<u64> = TrialDivision::new().iter().take(10_001).collect();
+    let shared = SharedPrimeSet::new();
+    assert_eq!(shared.get(10_000), 104_743);
+    assert_eq!(shared.find(104_730), (10_000, 104_743));
+    assert_eq!(shared.iter().take(10_001).collect::<Vec<_>>(), expected);
+    let cached = CachedPrimes::new();
+    assert_eq!(cached.get(10_000), 104_743);
+    assert_eq!(cached.find(104_730), (10_000, 104_743));
+    assert_eq!(cached.iter().take(10_001).collect::<Vec<_>>(), expected);
+    assert_eq!(first_goldbach_pair(1_000_000), Some((17, 999_983)));
+    // A new set may already hold more than that, with the `table` feature.
+    #[cfg(feature = "tiny")]
+    {
+        let cap = TrialDivision::new().len().max(7_000);
+        assert!(shared.len() <= cap, "{}", shared.len());
+        assert!(cached.len() <= cap, "{}", cached.len());
+        assert!(global().len() <= DefaultPrimes::new().len().max(7_000), "{}", global().len());
+    }
+}
+
+#[test]
+fn prime_counting_bounds() {
+    let known = [
+        (0, 0),
+        (2, 1),
+        (100, 25),
+        (355_990, 30_456),
+        (1_000_000, 78_498),
+        (10_000_000, 664_579),
+        (1_000_000_000, 50_847_534),
+        (1_000_000_000_000, 37_607_912_018),
+        (1_000_000_000_000_000_000, 24_739_954_287_740_860),
+    ];
+    for &(x, pi) in known.iter() {
+        let (lo, hi) = pi_bounds(x);
+        assert!(lo <= pi && pi <= hi, "{}: {} <= {} <= {}", x, lo, pi, hi);
+    }
+    assert_eq!(pi_bounds(100), (25, 25));
+
+    // The bounds are tight to within a fraction of a percent at scale.
+    let (lo, hi) = pi_bounds(1_000_000_000_000);
+    assert!(hi - lo < 37_607_912_018 / 100);
+}
+
+#[test]
+fn sieve_expand_to() {
+    let mut sieve = Sieve::from_prefix(vec![2, 3]).unwrap();
+    sieve.expand_to(10_000_000);
+    assert_eq!(sieve.find_vec(9_999_991), Some((664_578, 9_999_991)));
+
+    let mut small = Sieve::from_prefix(vec![2, 3]).unwrap();
+    small.expand_to(100_000);
+    assert_eq!(small.list(), &sieve.list()[..small.len()]);
+
+    let mut pset = TrialDivision::from_primes(vec![2, 3]).unwrap();
+    pset.expand_to(1000);
+    assert_eq!(pset.list().last(), Some(&1009));
+    assert_eq!(pset.len(), 169);
+}
+
+#[test]
+fn nth_prime_bounds_and_verify() {
+    // p_k for k = 10^3, ..., 10^9.
+    let known = [
+        (1_000, 7_919),
+        (100_000, 1_299_709),
+        (1_000_000, 15_485_863),
+        (1_000_000_000, 22_801_763_489),
+    ];
+    for &(k, p) in known.iter() {
+        let (lo, hi) = nth_prime_bounds(k);
+        assert!(lo <= p && p <= hi, "{}: {} <= {} <= {}", k, lo, p, hi);
+    }
+    assert_eq!(nth_prime_bounds(1), (2, 2));
+    assert_eq!(nth_prime_bounds(26), (101, 101));
+
+    assert!(verify_nth_prime(1, 2));
+    assert!(verify_nth_prime(1_000, 7_919));
+    assert!(verify_nth_prime(1_000_000, 15_485_863));
+    assert!(!verify_nth_prime(1_000_000, 15_485_867));
+    assert!(!verify_nth_prime(1_000_000, 15_485_861));
+    assert!(!verify_nth_prime(0, 2));
+    assert!(!verify_nth_prime(1_000_000_000, 1_000));
+    // Only the window above the lower bound is sieved, so this is quick.
+    #[cfg(not(feature = "tiny"))]
+    {
+        assert!(verify_nth_prime(1_000_000_000, 22_801_763_489));
+        assert!(!verify_nth_prime(1_000_000_000, 22_801_763_513));
+    }
+    // Either side of where the bounds stop being exact.
+    for k in [1, 2, 1_000, 39_016, 39_017] {
+        assert!(verify_nth_prime(k, quick::nth(k as usize - 1)), "{}", k);
+    }
+}
+
+#[test]
+fn batch_primality() {
+    let candidates: Vec<u64> = (0..1000).collect();
+    let flags = are_prime(&candidates);
+    assert_eq!(flags.len(), 1000);
+    for (&n, &prime) in candidates.iter().zip(flags.iter()) {
+        assert_eq!(prime, is_prime(n), "{}", n);
+    }
+    assert!(are_prime(&[]).is_empty());
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn parallel_filter_primes() {
+    let found = par_filter_primes(0..10_000u64);
+    let mut pset = TrialDivision::new();
+    let expected: Vec<u64> = pset.iter().take_while(|&p| p < 10_000).collect();
+    assert_eq!(found, expected);
+}
+
+#[test]
+fn quick_facade() {
+    assert_eq!(quick::nth(0), 2);
+    assert_eq!(quick::nth(999), 7_919);
+    assert_eq!(quick::below(2), Vec::<u64>::new());
+    assert_eq!(quick::below(30), vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+    assert_eq!(quick::below(1_000_000).len(), 78_498);
+    assert_eq!(quick::factor(1), Vec::<u64>::new());
+    assert_eq!(quick::factor(360), vec![2, 2, 2, 3, 3, 5]);
+    assert!(quick::is_prime(7_919));
+    assert!(!quick::is_prime(7_917));
+    assert!(quick::is_prime(1_000_000_007));
+    assert!(!quick::is_prime(0));
+    // The square of the largest prime below 2^32, which trial division would take seconds over.
+    assert!(!quick::is_prime(18_446_744_030_759_878_681));
+    assert_eq!(quick::factor(18_446_744_030_759_878_681), vec![4_294_967_291, 4_294_967_291]);
+    assert!(quick::is_prime(LARGEST_U64_PRIME));
+}
+
+#[test]
+fn spf_sieve_factor() {
+    let spf = SpfSieve::new(100_000);
+    assert_eq!(spf.limit(), 100_000);
+    for n in 0..=100_000 {
+        assert_eq!(spf.factor(n), factors(n as u64), "{}", n);
+    }
+    assert!(spf.is_prime(99_991));
+    assert!(!spf.is_prime(1));
+    assert_eq!(spf.smallest_factor(99_991), 99_991);
+    assert_eq!(spf.smallest_factor(91), 7);
+}
+
+#[test]
+fn factor_iter_is_lazy() {
+    for n in 0..10_000 {
+        assert_eq!(factor_iter(n).collect::<Vec<_>>(), factors(n), "{}", n);
+    }
+    assert_eq!(factor_iter(LARGEST_U64_PRIME - 1).next(), Some(2));
+    // 7-smooth: no factor above 7.
+    assert!(factor_iter(2 * 3 * 5 * 7 * 7).all(|p| p <= 7));
+    assert!(!factor_iter(2 * 1_000_003).all(|p| p <= 7));
+}
+
+#[test]
+fn default_primes_guarantees() {
+    let mut pset = DefaultPrimes::new();
+    let mut reference = TrialDivision::new();
+    let found: Vec<u64> = pset.iter().take(5_000).collect();
+    let expected: Vec<u64> = reference.iter().take(5_000).collect();
+    assert_eq!(found, expected);
+}
+
+#[test]
+fn quick_past_cache() {
+    // Large enough to go past the cache with the `tiny` feature.
+    assert_eq!(quick::nth(9_999), 104_729);
+    let below = quick::below(200_000);
+    assert_eq!(below.len(), 17_984);
+    assert_eq!(below.last(), Some(&199_999));
+}
+
+#[test]
+fn divisor_functions() {
+    assert_eq!(divisors(0), Vec::<u64>::new());
+    assert_eq!(divisors(1), vec![1]);
+    assert_eq!(divisors(60), vec![1, 2, 3, 4, 5, 6, 10, 12, 15, 20, 30, 60]);
+    assert_eq!(divisors(1_000_000_007), vec![1, 1_000_000_007]);
+
+    for n in 0..2_000u64 {
+        let naive: Vec<u64> = (1..=n).filter(|d| n % d == 0).collect();
+        assert_eq!(divisors(n), naive, "{}", n);
+        assert_eq!(num_divisors(n), naive.len() as u64, "{}", n);
+        assert_eq!(sigma(n, 0), Some(naive.len() as u64), "{}", n);
+        assert_eq!(sigma(n, 1), Some(naive.iter().sum()), "{}", n);
+        assert_eq!(sigma(n, 2), Some(naive.iter().map(|d| d * d).sum()), "{}", n);
+    }
+
+    assert_eq!(sigma(1 << 40, 2), None);
+    assert_eq!(sigma(u64::MAX, 1), None);
+}
+
+#[test]
+fn aliquot_sums_and_abundance() {
+    assert_eq!(aliquot_sum(1), Some(0));
+    assert_eq!(aliquot_sum(12), Some(16));
+    assert_eq!(aliquot_sum(97), Some(1));
+
+    assert_eq!(abundance(1), Abundance::Deficient);
+    assert_eq!(abundance(8), Abundance::Deficient);
+    assert_eq!(abundance(12), Abundance::Abundant);
+    let perfect: Vec<u64> = (1..10_000)
+        .filter(|&n| abundance(n) == Abundance::Perfect)
+        .collect();
+    assert_eq!(perfect, vec![6, 28, 496, 8128]);
+    // The first odd abundant number.
+    assert_eq!((1..1_000).step_by(2).find(|&n| abundance(n) == Abundance::Abundant), Some(945));
+    // 3p, whose divisors sum past u64::MAX though its proper divisors only sum to p + 4.
+    let p = 5_000_000_000_000_000_003;
+    assert_eq!(aliquot_sum(3 * p), Some(p + 4));
+    assert_eq!(abundance(3 * p), Abundance::Deficient);
+    // 2^4 3^2 5 7 ... 47, whose proper divisors sum past u64::MAX.
+    assert_eq!(aliquot_sum(14_757_354_782_123_793_840), None);
+    assert_eq!(abundance(14_757_354_782_123_793_840), Abundance::Abundant);
+
+    assert_eq!(aliquot_sequence(10, 100).collect::<Vec<_>>(), vec![10, 8, 7, 1, 0]);
+    assert_eq!(aliquot_sequence(6, 3).collect::<Vec<_>>(), vec![6, 6, 6, 6]);
+    assert_eq!(aliquot_sequence(220, 2).collect::<Vec<_>>(), vec![220, 284, 220]);
+    assert_eq!(aliquot_sequence(0, 5).collect::<Vec<_>>(), vec![0]);
+    assert_eq!(aliquot_sequence(12, 0).collect::<Vec<_>>(), vec![12]);
+}
+
+#[test]
+fn find_at_or_above_reports_exactness() {
+    let mut pset = TrialDivision::new();
+    assert_eq!(
+        pset.find_at_or_above(13),
+        FindResult { prime: 13, index: 5, was_exact: true }
+    );
+    assert_eq!(
+        pset.find_at_or_above(14),
+        FindResult { prime: 17, index: 6, was_exact: false }
+    );
+    assert!(!pset.find_at_or_above(0).was_exact);
+    assert_eq!(pset.find_at_or_above(0).prime, 2);
+}
+
+#[test]
+fn totient_and_carmichael() {
+    fn gcd(a: u64, b: u64) -> u64 {
+        if b == 0 { a } else { gcd(b, a % b) }
+    }
+    fn pow_mod(a: u64, e: u64, n: u64) -> u64 {
+        (0..e).fold(1 % n, |acc, _| acc * a % n)
+    }
+
+    assert_eq!(euler_phi(0), 0);
+    assert_eq!(carmichael_lambda(0), 0);
+    for n in 1..500u64 {
+        let units: Vec<u64> = (1..=n).filter(|&a| gcd(a, n) == 1).collect();
+        assert_eq!(euler_phi(n), units.len() as u64, "{}", n);
+
+        let lambda = carmichael_lambda(n);
+        assert_eq!(euler_phi(n) % lambda, 0, "{}", n);
+        let smallest = (1..=n)
+            .find(|&m| units.iter().all(|&a| pow_mod(a, m, n) == 1 % n))
+            .unwrap();
+        assert_eq!(lambda, smallest, "{}", n);
+    }
+    // 561 = 3 * 11 * 17 is the smallest Carmichael number.
+    assert_eq!(carmichael_lambda(561), 80);
+    assert_eq!(euler_phi(1_000_000_007), 1_000_000_006);
+}
+
+#[test]
+fn ensure_primes_below_warms_cache() {
+    let mut pset = Sieve::from_prefix(vec![2, 3]).unwrap();
+    let added = pset.ensure_primes_below(100_000);
+    assert_eq!(added, pset.len() - 2);
+    assert!(pset.len() >= 9_592);
+    for m in [0, 2, 99_990, 99_999, 100_000] {
+        assert!(pset.find_vec(m).is_some(), "{}", m);
+    }
+    assert_eq!(pset.ensure_primes_below(50_000), 0);
+
+    let mut pset = TrialDivision::from_primes(vec![2, 3]).unwrap();
+    assert_eq!(pset.ensure_primes_below(100), 24);
+    assert_eq!(pset.find_vec(98), Some((25, 101)));
+}
+
+#[test]
+fn orders_and_primitive_roots() {
+    assert_eq!(multiplicative_order(2, 7), Some(3));
+    assert_eq!(multiplicative_order(3, 7), Some(6));
+    assert_eq!(multiplicative_order(10, 7), Some(6));
+    assert_eq!(multiplicative_order(1, 1), Some(1));
+    assert_eq!(multiplicative_order(2, 4), None);
+    assert_eq!(multiplicative_order(5, 0), None);
+    for n in 2..120u64 {
+        for a in 1..n {
+            // No power of `a` comes to 1 unless `a` is coprime to `n`.
+            let naive = (1..=n).find(|&k| (0..k).fold(1, |acc, _| acc * a % n) == 1);
+            assert_eq!(multiplicative_order(a, n), naive, "{} mod {}", a, n);
+        }
+    }
+    assert_eq!(multiplicative_order(2, 1_000_000_007), Some(500_000_003));
+
+    let roots: Vec<Option<u64>> = (0..12).map(primitive_root).collect();
+    assert_eq!(
+        roots,
+        vec![None, None, Some(1), Some(2), Some(3), Some(2), Some(5), Some(3), None, Some(2), Some(3), Some(2)]
+    );
+    assert_eq!(primitive_root(1_000_000_007), Some(5));
+    assert_eq!(primitive_root(2 * 3u64.pow(5)), Some(5));
+    assert_eq!(primitive_root(15), None);
+}
+
+#[test]
+fn prime_view_is_read_only() {
+    let mut pset = TrialDivision::from_primes(vec![2, 3]).unwrap();
+    pset.ensure_primes_below(100);
+    let view = pset.view();
+
+    assert_eq!(view.len(), pset.len());
+    assert_eq!(view.last(), Some(101));
+    assert_eq!(view.get(3), Some(7));
+    assert_eq!(view.get(1_000), None);
+    assert_eq!(view.find_vec(90), Some((24, 97)));
+    assert_eq!(view.find_vec(97), Some((24, 97)));
+    assert_eq!(view.find_vec(102), None);
+    for n in 0..102 {
+        assert_eq!(view.find_vec(n), pset.find_vec(n), "{}", n);
+        assert_eq!(view.contains_cached(n), Some(is_prime(n)), "{}", n);
+    }
+    assert_eq!(view.contains_cached(103), None);
+
+    assert_eq!(view.count_up_to(100), Some(25));
+    assert_eq!(view.count_up_to(1), Some(0));
+    assert_eq!(view.count_up_to(1_000), None);
+    assert_eq!(view.primes_in(10..30), Some(&[11, 13, 17, 19, 23, 29][..]));
+    assert_eq!(view.primes_in(24..29), Some(&[][..]));
+    assert_eq!(view.primes_in(90..200), None);
+    assert_eq!(view.iter().count(), view.len());
+}
+
+#[test]
+fn summatory_functions() {
+    let mut phi_sum = 0u128;
+    let mut m = 0i64;
+    for n in 1..3_000u64 {
+        phi_sum += u128::from(euler_phi(n));
+        let fs = factors(n);
+        let squarefree = fs.windows(2).all(|w| w[0] != w[1]);
+        m += if !squarefree { 0 } else if fs.len().is_multiple_of(2) { 1 } else { -1 };
+        assert_eq!(totient_summatory(n), phi_sum, "{}", n);
+        assert_eq!(mertens(n), m, "{}", n);
+    }
+    assert_eq!(totient_summatory(0), 0);
+    assert_eq!(mertens(0), 0);
+
+    // OEIS A002321 and A064018.
+    assert_eq!(mertens(1_000_000_000), -222);
+    assert_eq!(totient_summatory(1_000_000_000), 303_963_551_173_008_414);
+}
+
+#[test]
+fn modular_arithmetic() {
+    assert_eq!(mod_pow(2, 10, 1_000), 24);
+    assert_eq!(mod_pow(5, 0, 1), 0);
+    assert_eq!(mod_pow(3, LARGEST_U64_PRIME - 1, LARGEST_U64_PRIME), 1);
+
+    assert_eq!(mod_inv(3, 7), Some(5));
+    assert_eq!(mod_inv(10, 7), Some(5));
+    assert_eq!(mod_inv(4, 8), None);
+    assert_eq!(mod_inv(1, 0), None);
+    assert_eq!(mod_inv(5, 1), Some(0));
+    let p = LARGEST_U64_PRIME;
+    assert_eq!(mod_inv(12_345, p), Some(mod_pow(12_345, p - 2, p)));
+
+    for &m in &[1u64, 3, 101, 1_000_000_007, (1 << 63) + 1, LARGEST_U64_PRIME, u64::MAX] {
+        let mont = Montgomery::new(m);
+        assert_eq!(mont.modulus(), m);
+        for &(a, b) in &[(0u64, 5u64), (2, 3), (u64::MAX, u64::MAX - 1), (m - 1, m - 1)] {
+            let (x, y) = (mont.to_montgomery(a), mont.to_montgomery(b));
+            assert_eq!(mont.from_montgomery(x), a % m);
+            let expected = (u128::from(a) * u128::from(b) % u128::from(m)) as u64;
+            assert_eq!(mont.from_montgomery(mont.mul(x, y)), expected, "{} * {} mod {}", a, b, m);
+            assert_eq!(mont.from_montgomery(mont.pow(x, 65_537)), mod_pow(a, 65_537, m));
+        }
+    }
+}
+
+#[test]
+fn hyperbola_method() {
+    let mut total = 0;
+    for n in 0..2_000u64 {
+        assert_eq!(sum_num_divisors_below(n), total, "{}", n);
+        total += num_divisors(n);
+    }
+    // OEIS A006218: Σ d(k) for k up to 10^9.
+    assert_eq!(sum_num_divisors_below(1_000_000_001), 20_877_697_634);
+
+    // σ = id * 1, so summing it by the hyperbola method matches summing σ directly.
+    let id = (|k| i128::from(k), |x: u64| i128::from(x) * i128::from(x + 1) / 2);
+    let one = (|_| 1, |x| i128::from(x));
+    let direct: i128 = (1..=1_000).map(|k| i128::from(sigma(k, 1).unwrap())).sum();
+    assert_eq!(hyperbola_sum(&id, &one, 1_000), direct);
+    assert_eq!(hyperbola_sum(&id, &one, 0), 0);
+}
+
+#[test]
+fn jacobi_and_legendre_symbols() {
+    for p in [3u64, 5, 7, 11, 13, 101, 997] {
+        for a in 0..2 * p {
+            let square = (1..p).any(|x| x * x % p == a % p);
+            let expected = if a % p == 0 { 0 } else if square { 1 } else { -1 };
+            assert_eq!(legendre(a, p), expected, "({}/{})", a, p);
+        }
+    }
+    // The Jacobi symbol is multiplicative in the modulus.
+    for n in (1..200u64).step_by(2) {
+        for a in 0..50 {
+            let product: i8 = factors(n).iter().map(|&p| jacobi(a, p)).product();
+            assert_eq!(jacobi(a, n), product, "({}/{})", a, n);
+        }
+    }
+    assert_eq!(jacobi(2, 15), 1);
+    assert_eq!(jacobi(5, 1), 1);
+    // -1 is a square modulo primes that are 1 mod 4, like 2^64 - 59.
+    assert_eq!(legendre(LARGEST_U64_PRIME - 1, LARGEST_U64_PRIME), 1);
+}
+
+#[test]
+fn prime_tuples() {
+    assert!(is_admissible(&[]));
+    assert!(is_admissible(&[0]));
+    assert!(is_admissible(&[0, 2]));
+    assert!(is_admissible(&[0, 2, 6]));
+    assert!(is_admissible(&[0, 4, 6, 10, 12, 16]));
+    assert!(!is_admissible(&[0, 1]));
+    assert!(!is_admissible(&[0, 2, 4]));
+
+    // Twice the twin prime constant, 2 * 0.6601618158...
+    let twins = hardy_littlewood_constant(&[0, 2], 1_000_000);
+    assert!((twins - 1.320_323_631_7).abs() < 1e-6, "{}", twins);
+    // Prime triplets (p, p + 2, p + 6): 2.8582485957...
+    let triplets = hardy_littlewood_constant(&[0, 2, 6], 1_000_000);
+    assert!((triplets - 2.858_248_595_7).abs() < 1e-5, "{}", triplets);
+    assert_eq!(hardy_littlewood_constant(&[0, 2, 4], 1_000_000), 0.0);
+    assert_eq!(hardy_littlewood_constant(&[5], 1_000), 1.0);
+}
+
+#[test]
+fn modular_square_roots() {
+    for p in [2u64, 3, 5, 7, 13, 17, 97, 257, 65_537] {
+        for a in 0..p.min(500) {
+            let root = (0..p).find(|&x| x * x % p == a);
+            assert_eq!(sqrt_mod(a, p), root, "sqrt({}) mod {}", a, p);
+        }
+    }
+    assert_eq!(sqrt_mod(5, 0), None);
+    assert_eq!(sqrt_mod(5, 1), None);
+    // Composite moduli are turned away, even where a root exists: 4 is 2^2 modulo 15.
+    assert_eq!(sqrt_mod(4, 15), None);
+    assert_eq!(sqrt_mod(0, 9), None);
+
+    // 2^64 - 59 is 5 mod 8, and 998244353 = 119 * 2^23 + 1 takes the Cipolla path.
+    for &p in &[LARGEST_U64_PRIME, 998_244_353, 1_000_000_007] {
+        for a in [2u64, 3, 10, 12_345, p - 1] {
+            match sqrt_mod(a, p) {
+                Some(x) => {
+                    assert_eq!((u128::from(x) * u128::from(x) % u128::from(p)) as u64, a);
+                    assert!(x <= p - x);
+                }
+                None => assert_eq!(legendre(a, p), -1, "{} mod {}", a, p),
+            }
+        }
+    }
+}
+
+#[test]
+fn chinese_remainder() {
+    assert_eq!(crt(&[]), Some((0, 1)));
+    assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+    assert_eq!(crt(&[(10, 3)]), Some((1, 3)));
+    // Overlapping moduli that agree, and ones that don't.
+    assert_eq!(crt(&[(2, 4), (4, 6)]), Some((10, 12)));
+    assert_eq!(crt(&[(1, 4), (2, 6)]), None);
+    assert_eq!(crt(&[(1, 0)]), None);
+    assert_eq!(crt(&[(0, 1 << 40), (0, 3 << 40)]), Some((0, 3 << 40)));
+    assert_eq!(crt(&[(0, 1 << 40), (0, (1 << 40) - 1)]), None);
+
+    let p = LARGEST_U64_PRIME;
+    assert_eq!(crt(&[(p - 1, p), (0, 1)]), Some((p - 1, p)));
+    for a in 0..12u64 {
+        for b in 0..18u64 {
+            let brute = (0..36).find(|x| x % 12 == a && x % 18 == b);
+            assert_eq!(crt(&[(a, 12), (b, 18)]), brute.map(|x| (x, 36)), "{} {}", a, b);
+        }
+    }
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn sample_primes_uniformly() {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    let mut rng = StdRng::seed_from_u64(7);
+    let sample = sample_primes_below(1_000_000, 100, &mut rng);
+    assert_eq!(sample.len(), 100);
+    assert!(sample.windows(2).all(|w| w[0] < w[1]));
+    assert!(sample.iter().all(|&p| p < 1_000_000 && is_prime(p)));
+
+    assert_eq!(sample_primes_below(30, 10, &mut rng), vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+    assert!(sample_primes_below(2, 0, &mut rng).is_empty());
+
+    // Each of the 25 primes below 100 should come up about 4000 times in 10000 draws of 10.
+    let mut hits = [0u32; 100];
+    for _ in 0..10_000 {
+        for p in sample_primes_below(100, 10, &mut rng) {
+            hits[p as usize] += 1;
+        }
+    }
+    for p in (0..100).filter(|&p| is_prime(p)) {
+        assert!((3_700..4_300).contains(&hits[p as usize]), "{}: {}", p, hits[p as usize]);
+    }
+}
+
+#[test]
+fn discrete_logarithms() {
+    for m in 1..80u64 {
+        for b in 0..m {
+            let mut powers = Vec::new();
+            let mut x = 1 % m;
+            for _ in 0..2 * m {
+                powers.push(x);
+                x = x * b % m;
+            }
+            for t in 0..m {
+                let brute = powers.iter().position(|&p| p == t).map(|i| i as u64);
+                assert_eq!(discrete_log(b, t, m), brute, "{}^x = {} mod {}", b, t, m);
+            }
+        }
+    }
+    assert_eq!(discrete_log(2, 3, 0), None);
+
+    let p = 1_000_000_007;
+    let x = discrete_log(5, 123_456_789, p).unwrap();
+    assert_eq!(mod_pow(5, x, p), 123_456_789);
+    // 998244353 - 1 = 2^23 * 7 * 17, which Pohlig-Hellman makes short work of.
+    let p = 998_244_353;
+    assert_eq!(discrete_log(3, mod_pow(3, 876_543_210, p), p), Some(876_543_210 % (p - 1)));
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn sample_from_prime_stream() {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    let mut rng = StdRng::seed_from_u64(11);
+    let mut pset = Sieve::new();
+    let sample = sample_from_stream(pset.iter().take_while(|&p| p < 10_000_000), 50, &mut rng);
+    assert_eq!(sample.len(), 50);
+    assert!(sample.windows(2).all(|w| w[0] < w[1]));
+    assert!(sample.iter().all(|&p| p < 10_000_000 && is_prime(p)));
+
+    // Straight off a prime set's own iterator, whose `nth` skips ahead in bulk.
+    let mut pset = TrialDivision::new();
+    let sample = sample_from_stream(pset.iter().take(1_000_000), 20, &mut rng);
+    assert_eq!(sample.len(), 20);
+    assert!(sample.windows(2).all(|w| w[0] < w[1]));
+    assert!(sample.iter().all(|&p| p <= 15_485_863 && is_prime_const(p)));
+    assert_eq!(pset.list()[999_999], 15_485_863);
+    let mut hits = [0u32; 20];
+    for _ in 0..20_000 {
+        for p in sample_from_stream(pset.iter().take(20), 5, &mut rng) {
+            hits[pset.find(p).0] += 1;
+        }
+    }
+    assert!(hits.iter().all(|&h| (4_700..5_300).contains(&h)), "{:?}", hits);
+
+    assert_eq!(sample_from_stream(vec![5, 3, 2], 10, &mut rng), vec![2, 3, 5]);
+    assert!(sample_from_stream(0..100, 0, &mut rng).is_empty());
+
+    // Every item of a stream of 20 should land in a sample of 5 about a quarter of the time.
+    let mut hits = [0u32; 20];
+    for _ in 0..20_000 {
+        for x in sample_from_stream(0..20, 5, &mut rng) {
+            hits[x as usize] += 1;
+        }
+    }
+    assert!(hits.iter().all(|&h| (4_700..5_300).contains(&h)), "{:?}", hits);
+}
+
+#[test]
+fn prime_iter_nth() {
+    let mut pset = TrialDivision::new();
+    let mut iter = pset.iter();
+    assert_eq!(iter.nth(999), Some(7_919));
+    assert_eq!(iter.next(), Some(7_927));
+}
+
+#[test]
+fn mersenne_primes() {
+    let known = [2u32, 3, 5, 7, 13, 17, 19, 31, 61, 89, 107, 127, 521, 607, 1279];
+    assert_eq!(special::mersenne_exponents().take(12).collect::<Vec<_>>(), &known[..12]);
+    for &p in known.iter() {
+        assert!(special::is_mersenne_prime(p), "{}", p);
+    }
+    for p in [0, 1, 4, 11, 23, 29, 37, 64, 67, 127 * 2, 1277] {
+        assert!(!special::is_mersenne_prime(p), "{}", p);
+    }
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn weighted_prime_sampling() {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    let mut rng = StdRng::seed_from_u64(3);
+    assert_eq!(sample_prime_weighted(24..29, Weight::Uniform, &mut rng), None);
+    assert_eq!(sample_prime_weighted(23..24, Weight::LogInverse, &mut rng), Some(23));
+
+    // 3 and 1009: under 1/ln p weighting, 3 is ln(1009)/ln(3) ≈ 6.3 times as likely.
+    let mut small = [0u32; 2];
+    let mut uniform = [0u32; 2];
+    for _ in 0..20_000 {
+        for (weight, counts) in [(Weight::LogInverse, &mut small), (Weight::Uniform, &mut uniform)] {
+            let p = sample_prime_weighted(3..1010, weight, &mut rng).unwrap();
+            assert!(is_prime(p) && (3..1010).contains(&p));
+            match p {
+                3 => counts[0] += 1,
+                1009 => counts[1] += 1,
+                _ => (),
+            }
+        }
+    }
+    let ratio = f64::from(small[0]) / f64::from(small[1].max(1));
+    assert!((4.5..9.0).contains(&ratio), "{:?}", small);
+    let ratio = f64::from(uniform[0]) / f64::from(uniform[1].max(1));
+    assert!((0.5..2.0).contains(&ratio), "{:?}", uniform);
+}
+
+#[cfg(feature = "bigint")]
+#[test]
+fn exact_euler_products() {
+    use num_bigint::BigInt;
+    use num_rational::Ratio;
+
+    let r = |n: i64, d: i64| Ratio::new(BigInt::from(n), BigInt::from(d));
+    assert_eq!(euler_product_exact(10, |p| (p - 1, p)), r(8, 35));
+    assert_eq!(euler_product_exact(10, |p| (p, p - 1)), r(35, 8));
+    assert_eq!(euler_product_exact(5, |p| (-1, p as i64)), r(-1, 30));
+    assert_eq!(euler_product_exact(1, |p| (p, 1)), r(1, 1));
+
+    // Π p/(p - 1) over p ≤ x grows like e^γ ln x (Mertens' third theorem).
+    let big = euler_product_exact(100_000, |p| (p, p - 1));
+    let approx = big.numer().bits() as f64 - big.denom().bits() as f64;
+    let expected = (1.781_072 * (100_000f64).ln()).log2();
+    assert!((approx - expected).abs() < 1.5, "{} vs {}", approx, expected);
+}
+
+#[test]
+fn fermat_and_proth_primes() {
+    let fermat: Vec<u32> = (0..10).filter(|&k| special::is_fermat_prime(k) == Some(true)).collect();
+    assert_eq!(fermat, vec![0, 1, 2, 3, 4]);
+    // Past Pépin's test the answer comes from what is known, and past that no one knows.
+    assert_eq!(special::is_fermat_prime(20), Some(false));
+    assert_eq!(special::is_fermat_prime(32), Some(false));
+    assert_eq!(special::is_fermat_prime(33), None);
+    assert_eq!(special::is_fermat_prime(u32::MAX), None);
+
+    // Every Proth prime below 1000 (OEIS A080076), against trial division.
+    let mut proth = Vec::new();
+    for n in 1..10 {
+        for k in (1..1u64 << n).step_by(2) {
+            let m = (k << n) + 1;
+            if m < 1_000 && special::is_proth_prime(k, n) {
+                proth.push(m);
+            }
+        }
+    }
+    proth.sort_unstable();
+    let expected: Vec<u64> = (3..1_000u64)
+        .filter(|&m| {
+            let n = (m - 1).trailing_zeros();
+            is_prime(m) && (m - 1) >> n < 1 << n
+        })
+        .collect();
+    assert_eq!(proth, expected);
+
+    assert!(!special::is_proth_prime(4, 5));
+    assert!(!special::is_proth_prime(33, 5));
+    assert!(!special::is_proth_prime(1, 3));
+    assert!(special::is_proth_prime(1, 16));
+    assert!(!special::is_proth_prime(1, 32));
+
+    // 3 2^n + 1 is prime for these n (OEIS A002253), all that fit in a u64.
+    let threes: Vec<u32> = (2..63).filter(|&n| special::is_proth_prime(3, n)).collect();
+    assert_eq!(threes, vec![2, 5, 6, 8, 12, 18, 30, 36, 41]);
+}
+
+#[cfg(feature = "bigint")]
+#[test]
+fn big_proth_primes() {
+    let threes: Vec<u32> = (63..220)
+        .filter(|&n| special::is_proth_prime(3, n))
+        .collect();
+    assert_eq!(threes, vec![66, 189, 201, 209]);
+}
+
+#[test]
+fn analytic_sums() {
+    assert!((chebyshev_theta(10) - 210f64.ln()).abs() < 1e-12);
+    assert!((chebyshev_psi(10) - 2520f64.ln()).abs() < 1e-12);
+    assert!((chebyshev_psi_with::<DoubleDouble>(10) - 2520f64.ln()).abs() < 1e-12);
+
+    let twins = [(3, 5), (5, 7), (11, 13), (17, 19), (29, 31), (41, 43), (59, 61), (71, 73)];
+    let brun: f64 = twins.iter().map(|&(p, q)| 1.0 / p as f64 + 1.0 / q as f64).sum();
+    assert!((brun_sum(100) - brun).abs() < 1e-12);
+    assert_eq!(brun_sum(4), 0.0);
+
+    let fast = prime_zeta(2.0, 1_000_000);
+    let exact = prime_zeta_with::<DoubleDouble>(2.0, 1_000_000);
+    assert!((exact - 0.452_247_420_041).abs() < 1e-7);
+    assert!((fast - exact).abs() < 1e-12);
+    let theta = chebyshev_theta_with::<DoubleDouble>(1_000_000);
+    assert!((theta - chebyshev_theta(1_000_000)).abs() < 1e-6);
+    assert!((theta / 1e6 - 1.0).abs() < 0.01);
+
+    // Terms far below the rounding step of an f64 sum are kept.
+    let mut dd = DoubleDouble::default();
+    let mut f = 0.0;
+    dd.add(1e16);
+    f.add(1e16);
+    for _ in 0..1000 {
+        dd.add(1.0);
+        f.add(1.0);
+    }
+    assert_eq!(dd.value(), 1e16 + 1000.0);
+    assert_eq!(f, 1e16);
+}
+
+#[test]
+fn primorial_and_factorial_primes() {
+    assert_eq!(special::primorial(0), Some(1));
+    assert_eq!(special::primorial(12), Some(2310));
+    assert_eq!(special::primorial(52), Some(614_889_782_588_491_410));
+    assert_eq!(special::primorial(53), None);
+    assert_eq!(special::primorial_u128(52), Some(614_889_782_588_491_410));
+    assert!(special::primorial_u128(102).is_some());
+    assert_eq!(special::primorial_u128(103), None);
+
+    // Primorial primes below 10^15 (OEIS A228486).
+    let primorial_primes: Vec<u64> = (0..42)
+        .filter_map(special::primorial)
+        .flat_map(|q| vec![q.saturating_sub(1), q + 1])
+        .filter(|&n| special::is_primorial_prime(n))
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    assert_eq!(
+        primorial_primes,
+        vec![2, 3, 5, 7, 29, 31, 211, 2309, 2311, 30029, 200_560_490_131, 304_250_263_527_209]
+    );
+    assert!(!special::is_primorial_prime(30031));
+    assert!(!special::is_primorial_prime(13));
+
+    // Factorial primes (OEIS A088054).
+    let factorial_primes: Vec<u64> = (1..=14u64)
+        .scan(1u64, |f, k| {
+            *f *= k;
+            Some(*f)
+        })
+        .flat_map(|f| vec![f - 1, f + 1])
+        .filter(|&n| special::is_factorial_prime(n))
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    assert_eq!(
+        factorial_primes,
+        vec![2, 3, 5, 7, 23, 719, 5039, 39_916_801, 479_001_599, 87_178_291_199]
+    );
+    assert!(!special::is_factorial_prime(721));
+    assert!(!special::is_factorial_prime(11));
+}
+
+#[cfg(feature = "bigint")]
+#[test]
+fn big_primorial() {
+    use num_bigint::BigUint;
+
+    assert_eq!(special::primorial_big(0), BigUint::from(1u32));
+    assert_eq!(special::primorial_big(102), BigUint::from(special::primorial_u128(102).unwrap()));
+    assert_eq!(special::primorial_big(1_000).bits(), 1_380);
+}
+
+#[test]
+fn prime_modulus() {
+    const P: PrimeModulus = PrimeModulus::from_prime(1_000_000_007);
+    assert_eq!(u64::from(P), 1_000_000_007);
+
+    for n in 0..10_000 {
+        assert_eq!(PrimeModulus::new(n).is_some(), is_prime(n), "{}", n);
+    }
+    // Carmichael numbers and strong pseudoprimes to several small bases.
+    for &n in &[561, 41_041, 3_215_031_751, 3_825_123_056_546_413_051, u64::MAX] {
+        assert_eq!(PrimeModulus::new(n), None, "{}", n);
+    }
+    for &p in &[(1 << 61) - 1, 4_294_967_311, LARGEST_U64_PRIME] {
+        assert_eq!(PrimeModulus::new(p).map(PrimeModulus::get), Some(p));
+    }
+
+    let m = PrimeModulus::new(13).unwrap();
+    assert_eq!(m.inv(5), Some(8));
+    assert_eq!(m.inv(26), None);
+    assert_eq!(m.sqrt(10).map(|r| r * r % 13), Some(10));
+    assert_eq!(m.sqrt(5), None);
+}
+
+#[test]
+fn goldbach_partitions() {
+    assert_eq!(goldbach_pairs(4), vec![(2, 2)]);
+    assert_eq!(goldbach_pairs(10), vec![(3, 7), (5, 5)]);
+    assert_eq!(goldbach_pairs(100).len(), 6);
+    assert!(goldbach_pairs(2).is_empty());
+    assert!(goldbach_pairs(15).is_empty());
+
+    for n in (4..2_000).step_by(2) {
+        let pairs = goldbach_pairs(n);
+        assert!(!pairs.is_empty(), "{}", n);
+        assert_eq!(first_goldbach_pair(n), pairs.first().cloned());
+        for &(p, q) in &pairs {
+            assert!(p <= q && p + q == n && is_prime(p) && is_prime(q));
+        }
+    }
+    assert_eq!(first_goldbach_pair(9), None);
+
+    let n = 1_000_000_000_000_000_000;
+    let (p, q) = first_goldbach_pair(n).unwrap();
+    assert_eq!(p + q, n);
+    assert!(is_prime(p) && PrimeModulus::new(q).is_some());
+}
+
+#[test]
+fn prime_slice() {
+    let mut pset = Sieve::new();
+    pset.expand_to(100_000);
+    let all = PrimeSlice::from(pset.view());
+    assert!(all.contains(99_991) && !all.contains(99_993));
+    assert_eq!(all.rank(100), 25);
+    assert_eq!(all.rank(1), 0);
+    assert_eq!(all.select(0), Some(2));
+    assert_eq!(all.select(24), Some(97));
+    assert_eq!(all.rank(all.select(500).unwrap()), 501);
+    assert_eq!(all.select(all.len()), None);
+
+    assert_eq!(PrimeSlice::new(&[2, 3, 3]).map(|s| s.len()), None);
+    assert_eq!(PrimeSlice::new(&[5, 3]).map(|s| s.len()), None);
+    assert!(PrimeSlice::new(&[]).unwrap().is_empty());
+
+    // Skewed sizes gallop, similar sizes merge; both should agree with a plain filter.
+    let twins: Vec<u64> =
+        all.as_slice().iter().cloned().filter(|&p| all.contains(p + 2)).collect();
+    let few = [3, 4, 5, 6, 97, 1_000, 99_989, 99_991, 100_003];
+    let twins = PrimeSlice::new(&twins).unwrap();
+    for other in [&few[..], twins.as_slice()] {
+        let other = PrimeSlice::new(other).unwrap();
+        let expected: Vec<u64> =
+            other.as_slice().iter().cloned().filter(|&p| all.contains(p)).collect();
+        assert_eq!(all.intersect(&other), expected);
+        assert_eq!(other.intersect(&all), expected);
+    }
+}
+
+#[test]
+fn divisibility_filter() {
+    let divisors = [2, 3, 5, 7, 11, 13, 64, 96, 1_000_003, LARGEST_U64_PRIME];
+    let filter = DivisibilityFilter::new(&divisors);
+    assert_eq!(filter.len(), divisors.len());
+
+    let samples = (0..5_000u64)
+        .chain((0..5_000).map(|i| u64::MAX - i))
+        .chain((1..5_000u64).map(|i| i.wrapping_mul(0x9e37_79b9_7f4a_7c15)))
+        .chain(divisors.iter().flat_map(|&d| vec![d, d.wrapping_mul(2), d.wrapping_mul(d)]));
+    for n in samples {
+        for &d in &divisors {
+            let single = DivisibilityFilter::new(&[d]);
+            assert_eq!(single.passes(n), !n.is_multiple_of(d), "{} {}", n, d);
+        }
+        assert_eq!(filter.passes(n), divisors.iter().all(|&d| !n.is_multiple_of(d)), "{}", n);
+    }
+
+    let mut grown = DivisibilityFilter::default();
+    assert!(grown.is_empty() && grown.passes(12));
+    grown.push(3);
+    assert!(!grown.passes(12) && grown.passes(13));
+
+    let mut pset = TrialDivision::new();
+    let mut sieve = Sieve::new();
+    assert_eq!(pset.get(10_000), sieve.get(10_000));
+}
+
+#[test]
+fn sums_of_two_squares() {
+    assert_eq!(two_squares(2), Some((1, 1)));
+    assert_eq!(two_squares(13), Some((2, 3)));
+    assert_eq!(two_squares(7), None);
+    assert_eq!(two_squares(25), None);
+    let p = 1_000_000_000_000_000_009;
+    assert_eq!(p % 4, 1);
+    let (a, b) = two_squares(p).unwrap();
+    assert_eq!(u128::from(a).pow(2) + u128::from(b).pow(2), u128::from(p));
+
+    for n in 0..3_000u64 {
+        let brute = (0..=n.isqrt()).find(|&a| {
+            let b = (n - a * a).isqrt();
+            a <= b && a * a + b * b == n
+        });
+        match sum_of_two_squares(n) {
+            Some((a, b)) => assert!(a <= b && a * a + b * b == n, "{}", n),
+            None => assert_eq!(brute, None, "{}", n),
+        }
+        assert_eq!(sum_of_two_squares(n).is_some(), brute.is_some(), "{}", n);
+        if is_prime(n) {
+            assert_eq!(two_squares(n), sum_of_two_squares(n), "{}", n);
+        }
+    }
+    // 3^2 7^2 13, where 13 = 2^2 + 3^2.
+    assert_eq!(sum_of_two_squares(5_733), Some((42, 63)));
+    // 2 7^2 73 127 337 92737 649657 has 127 to an odd power.
+    assert_eq!(sum_of_two_squares(u64::MAX - 1), None);
+}
+
+#[test]
+fn sieve_resume() {
+    let mut fresh = Sieve::new();
+    fresh.expand_to(1_000_000);
+
+    let mut first = Sieve::new();
+    first.get(5_000);
+    let (saved, _) = first.into_buffers();
+    let mut resumed = Sieve::from_prefix(saved.clone()).unwrap();
+    assert_eq!(resumed.list(), &saved[..]);
+    resumed.expand_to(1_000_000);
+    // Segments may end in different places, so only compare up to the bound.
+    let upto = |lst: &[u64]| lst[..lst.partition_point(|&p| p <= 1_000_000)].to_vec();
+    assert_eq!(upto(resumed.list()), upto(fresh.list()));
+
+    // A prefix ending anywhere works, not just on a segment boundary.
+    let prefix = fresh.list()[..1_234].to_vec();
+    let mut resumed = Sieve::resume(prefix, vec![0u64; 3]).unwrap();
+    assert_eq!(resumed.get(20_000), fresh.get(20_000));
+
+    assert!(Sieve::from_prefix(vec![]).is_none());
+    assert!(Sieve::from_prefix(vec![3, 5]).is_none());
+    assert!(Sieve::from_prefix(vec![2, 3, 7, 5]).is_none());
+    assert!(Sieve::from_prefix(vec![2, 3]).is_some());
+    // A composite would be used as a base prime, and its multiples struck as if they were its.
+    assert!(Sieve::from_prefix(vec![2, 3, 5, 7, 9, 11]).is_none());
+    assert!(Sieve::resume(vec![2, 3, 5, 7, 11, 13, 91], vec![0u64; 3]).is_none());
+}
+
+#[test]
+fn gaussian_primes() {
+    assert!(is_gaussian_prime(1, 1));
+    assert!(is_gaussian_prime(2, -1));
+    assert!(is_gaussian_prime(0, 3));
+    assert!(is_gaussian_prime(-7, 0));
+    assert!(!is_gaussian_prime(5, 0));
+    assert!(!is_gaussian_prime(2, 0));
+    assert!(!is_gaussian_prime(0, 0));
+    assert!(!is_gaussian_prime(1, 0));
+    assert!(!is_gaussian_prime(3, 3));
+
+    // Each prime that is 1 mod 4 splits into the Gaussian primes a ± bi from its two squares.
+    for p in (5..2_000u64).filter(|&p| p % 4 == 1 && is_prime(p)) {
+        let (a, b) = two_squares(p).unwrap();
+        let (a, b) = (a as i64, b as i64);
+        assert!(is_gaussian_prime(a, b) && is_gaussian_prime(b, -a), "{}", p);
+        assert!(!is_gaussian_prime(p as i64, 0));
+    }
+    // 2^31 - 1 and 2^32 + 15 are primes that are 3 mod 4; 10^18 + 9 is one that is 1 mod 4.
+    assert!(is_gaussian_prime(0, 2_147_483_647));
+    assert!(is_gaussian_prime(4_294_967_311, 0));
+    assert!(!is_gaussian_prime(1_000_000_000_000_000_009, 0));
+    assert!(is_gaussian_prime(1_000_000_000, 3));
+
+    // Norms past u64 are tested in full: (2^63 - 1)^2 + 92^2 is prime, the norm with 1 is not,
+    // and the product of two Gaussian primes with norms near 2^40 never is.
+    assert!(is_gaussian_prime(i64::MAX, 92));
+    assert!(!is_gaussian_prime(i64::MAX, 1));
+    assert!(!is_gaussian_prime(i64::MIN, -1));
+    let (a, b) = two_squares(1_099_511_627_689).unwrap();
+    let (c, d) = two_squares(1_099_511_627_873).unwrap();
+    let (a, b, c, d) = (a as i64, b as i64, c as i64, d as i64);
+    assert!(!is_gaussian_prime(a * c - b * d, a * d + b * c));
+}
+
+#[test]
+fn pseudoprimes() {
+    // Carmichael numbers below 10^5 (OEIS A002997).
+    let carmichael: Vec<u64> = (0..100_000).filter(|&n| is_carmichael(n)).collect();
+    assert_eq!(
+        carmichael,
+        vec![
+            561, 1105, 1729, 2465, 2821, 6601, 8911, 10585, 15841, 29341, 41041, 46657, 52633,
+            62745, 63973, 75361,
+        ]
+    );
+    // Large inputs are settled quickly: primes and most composites never get factored.
+    assert!(!is_carmichael(LARGEST_U64_PRIME));
+    assert!(!is_carmichael(4_294_967_291 * 4_294_967_279));
+    // Chernick's form (6k + 1)(12k + 1)(18k + 1), with k = 200_116.
+    assert!(is_carmichael(1_200_697 * 2_401_393 * 3_602_089));
+
+    // Fermat and strong pseudoprimes to base 2 (OEIS A001567, A001262).
+    let fermat: Vec<u64> = (0..3_000).filter(|&n| is_pseudoprime_base(n, 2)).collect();
+    assert_eq!(fermat, vec![341, 561, 645, 1105, 1387, 1729, 1905, 2047, 2465, 2701, 2821]);
+    let strong: Vec<u64> = (0..50_000).filter(|&n| is_strong_pseudoprime_base(n, 2)).collect();
+    assert_eq!(strong, vec![2047, 3277, 4033, 4681, 8321, 15841, 29341, 42799, 49141]);
+
+    for n in (3..5_000u64).step_by(2) {
+        for a in 2..6 {
+            if is_strong_pseudoprime_base(n, a) {
+                assert!(is_pseudoprime_base(n, a), "{} {}", n, a);
+            }
+        }
+    }
+    assert!(!is_pseudoprime_base(7, 2));
+    assert!(!is_strong_pseudoprime_base(7, 2));
+    // A strong pseudoprime to all nine prime bases up to 23.
+    let n = 3_825_123_056_546_413_051;
+    assert!([2, 3, 5, 7, 11, 13, 17, 19, 23].iter().all(|&a| is_strong_pseudoprime_base(n, a)));
+    assert!(!is_strong_pseudoprime_base(n, 41));
+}
+
+#[test]
+fn sieve_expand_step() {
+    let mut stepped = Sieve::from_prefix(vec![2, 3]).unwrap();
+    let mut total = 0;
+    while stepped.list().last().unwrap() < &100_000 {
+        match stepped.expand_step(100) {
+            StepResult::Found(n) => total += n,
+            StepResult::Done => unreachable!(),
+        }
+    }
+    assert_eq!(stepped.len(), total + 2);
+    let mut fresh = Sieve::from_prefix(vec![2, 3]).unwrap();
+    fresh.expand_to(*stepped.list().last().unwrap());
+    assert_eq!(stepped.list(), &fresh.list()[..stepped.len()]);
+
+    // Steps cover at most the requested number of odd candidates.
+    let mut small = Sieve::from_prefix(vec![2, 3]).unwrap();
+    assert_eq!(small.expand_step(1), StepResult::Found(1));
+    assert_eq!(small.list(), &[2, 3, 5]);
+    assert_eq!(small.expand_step(0), StepResult::Found(0));
+    assert_eq!(small.list(), &[2, 3, 5]);
+    assert_eq!(small.expand_step(1), StepResult::Found(1));
+    assert_eq!(small.list(), &[2, 3, 5, 7]);
+    assert_eq!(small.expand_step(1), StepResult::Found(0));
+
+    let mut top = Sieve::from_prefix(vec![2, 3, LARGEST_U64_PRIME]).unwrap();
+    assert_eq!(top.expand_step(1_000), StepResult::Done);
+    assert_eq!(top.expand_step(0), StepResult::Done);
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn generate_constrained_primes() {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    let mut rng = StdRng::seed_from_u64(11);
+    for bits in 2..=64 {
+        let p = gen_prime(bits, &mut rng);
+        assert!(PrimeModulus::new(p).is_some());
+        assert_eq!(64 - p.leading_zeros(), bits, "{}", p);
+    }
+
+    let blum = PrimeConstraints::new().congruent_to(3, 4).not_congruent_to(2, 5);
+    for bits in [10, 30, 64] {
+        for _ in 0..20 {
+            let p = gen_prime_with(bits, &blum, &mut rng).unwrap();
+            assert!(PrimeModulus::new(p).is_some() && p % 4 == 3 && p % 5 != 2, "{}", p);
+        }
+    }
+    let ends_in_one = PrimeConstraints::new().filter(|p| p % 10 == 1);
+    let p = gen_prime_with(48, &ends_in_one, &mut rng).unwrap();
+    assert_eq!(p % 10, 1);
+
+    // Every 4-bit prime that is 1 mod 4 is 13, and small ranges are enumerated exactly.
+    let one_mod_four = PrimeConstraints::new().congruent_to(1, 4);
+    assert_eq!(gen_prime_with(4, &one_mod_four, &mut rng), Some(13));
+    assert_eq!(gen_prime_with(3, &one_mod_four, &mut rng), Some(5));
+    let impossible = PrimeConstraints::new().congruent_to(0, 4);
+    assert_eq!(gen_prime_with(12, &impossible, &mut rng), None);
+    assert_eq!(gen_prime_with(40, &impossible, &mut rng), None);
+
+    // Small primes come up about equally often.
+    let mut counts = std::collections::HashMap::new();
+    for _ in 0..6_000 {
+        *counts.entry(gen_prime(4, &mut rng)).or_insert(0) += 1;
+    }
+    assert_eq!(counts.len(), 2);
+    assert!(counts.values().all(|&c| c > 2_700));
+}
+
+#[test]
+fn pratt_certificates() {
+    for n in 0..2_000 {
+        let cert = prime_certificate(n);
+        assert_eq!(cert.is_some(), is_prime(n), "{}", n);
+        if let Some(cert) = cert {
+            assert_eq!(cert.prime(), n);
+            assert!(cert.verify(), "{}", n);
+        }
+    }
+
+    // The largest u64 prime is 2^2 11 137 547 5594472617641 + 1.
+    let cert = prime_certificate(LARGEST_U64_PRIME).unwrap();
+    assert!(cert.verify());
+    let primes: Vec<u64> = cert.factors().iter().map(|(_, c)| c.prime()).collect();
+    assert_eq!(primes, vec![2, 11, 137, 547, 5_594_472_617_641]);
+    // p - 1 = 2 (2^31 - 1) 2147484239, which trial division would take seconds over.
+    let cert = prime_certificate(9_223_374_570_885_479_267).unwrap();
+    assert!(cert.verify());
+    let primes: Vec<u64> = cert.factors().iter().map(|(_, c)| c.prime()).collect();
+    assert_eq!(primes, vec![2, 2_147_483_647, 2_147_484_239]);
+    let cert = prime_certificate(1_000_000_007).unwrap();
+    assert!(cert.verify());
+    assert_eq!(cert.witness(), 5);
+    assert_eq!(prime_certificate(561), None);
+
+    // Tampered certificates don't verify.
+    let parts = cert.factors().to_vec();
+    let rebuilt = PrattCertificate::from_parts(cert.prime(), cert.witness(), parts);
+    assert!(rebuilt.verify());
+    let wrong_witness = PrattCertificate::from_parts(cert.prime(), 4, cert.factors().to_vec());
+    assert!(!wrong_witness.verify());
+    let missing = PrattCertificate::from_parts(cert.prime(), 5, cert.factors()[1..].to_vec());
+    assert!(!missing.verify());
+    let two = prime_certificate(2).unwrap();
+    let composite = PrattCertificate::from_parts(561, 2, vec![(4, two.clone())]);
+    assert!(!composite.verify());
+    let seven = PrattCertificate::from_parts(7, 3, vec![]);
+    let fake_factor = PrattCertificate::from_parts(15, 2, vec![(1, two), (1, seven)]);
+    assert!(!fake_factor.verify());
+}
+
+#[cfg(feature = "aks")]
+#[test]
+fn aks_primality() {
+    for n in 0..200 {
+        assert_eq!(aks::is_prime(n), is_prime(n), "{}", n);
+    }
+    assert!(aks::is_prime(211));
+    assert!(!aks::is_prime(221));
+
+    let powers: Vec<u64> = (0..100).filter(|&n| aks::is_perfect_power(n)).collect();
+    assert_eq!(powers, vec![4, 8, 9, 16, 25, 27, 32, 36, 49, 64, 81]);
+    assert!(aks::is_perfect_power(3_u64.pow(40)));
+    assert!(aks::is_perfect_power(1 << 62));
+    assert!(!aks::is_perfect_power(u64::MAX));
+
+    // (X + 1)^3 = X^3 + 3X^2 + 3X + 1, which modulo X^2 - 1 and 5 is 4X + 4.
+    let p = aks::Poly::monomial_plus(1, 1, 2, 5).pow(3);
+    assert_eq!(p.coeffs(), &[4, 4]);
+    assert!(aks::congruence_holds(7, 5, 2));
+    assert!(!aks::congruence_holds(9, 5, 2));
+
+    // Coefficients near a modulus above 2^63 must not overflow when they're summed:
+    // (X + n - 1)^2 = X^2 + 2(n - 1)X + 1, which modulo X^2 - 1 and n is (n - 2)X + 2.
+    let n = LARGEST_U64_PRIME;
+    let p = aks::Poly::monomial_plus(1, n - 1, 2, n);
+    assert_eq!(p.mul(&p).coeffs(), &[2, n - 2]);
+}
+
+#[cfg(feature = "macros")]
+#[test]
+fn compile_time_prime_table() {
+    let table = primes_table!(up_to = 100_000);
+    let mut pset = Sieve::new();
+    pset.expand_to(100_000);
+    assert_eq!(table.as_slice(), &pset.list()[..table.len()]);
+    assert_eq!(table.select(table.len() - 1), Some(99_991));
+
+    assert_eq!(primes_table!(up_to = 97).as_slice().last(), Some(&97));
+    assert!(primes_table!(up_to = 1).is_empty());
+    assert_eq!(primes_table!(up_to = 2).as_slice(), &[2]);
+}
+
+#[test]
+fn fermat_and_squfof_factoring() {
+    let is_split =
+        |n: u64, f: Option<u64>| f.is_some_and(|f| f > 1 && f < n && n.is_multiple_of(f));
+    for n in 4..5_000u64 {
+        if is_prime(n) {
+            assert_eq!(fermat_factor(n), None);
+            assert_eq!(squfof(n), None);
+        } else {
+            assert!(is_split(n, fermat_factor(n)), "{}", n);
+            assert!(is_split(n, squfof(n)), "{}", n);
+        }
+    }
+    assert_eq!(fermat_factor(3), None);
+    assert_eq!(squfof(1), None);
+
+    // Two primes close together, which Fermat's method finds at once.
+    let (p, q) = (4_000_000_007, 4_000_000_009);
+    assert_eq!(fermat_factor(p * q), Some(p));
+
+    // Semiprimes of 40 to 62 bits, with factors of unequal size, for SQUFOF.
+    let semiprimes = [
+        (1_000_003, 1_000_033),
+        (65_537, 4_294_967_291),
+        (999_983, 1_000_000_007),
+        (2_147_483_647, 1_000_000_007),
+        (1_073_741_789, 2_147_483_629),
+    ];
+    for &(p, q) in &semiprimes {
+        let f = squfof(p * q);
+        assert!(f == Some(p) || f == Some(q), "{} {} {:?}", p, q, f);
+    }
+}
+
+#[test]
+fn prime_bucket_counts() {
+    assert_eq!(bucket_counts(0..100, 10), vec![4, 4, 2, 2, 3, 2, 2, 3, 2, 1]);
+    assert_eq!(bucket_counts(0..25, 10), vec![4, 4, 1]);
+    assert_eq!(bucket_counts(10..10, 3), Vec::<u64>::new());
+    assert_eq!(bucket_counts(5..6, 100), vec![1]);
+
+    let counts = bucket_counts(1_000_000..2_000_000, 1_000);
+    assert_eq!(counts.len(), 1_000);
+    assert_eq!(counts.iter().sum::<u64>(), 148_933 - 78_498);
+
+    let lo = 1_000_000_000_000;
+    let counts = bucket_counts(lo..lo + 10_000, 64);
+    let mut primes = [0; 1_000];
+    let (found, _) = fill_primes(lo..lo + 10_000, &mut primes);
+    assert_eq!(counts.len(), 157);
+    assert_eq!(counts.iter().sum::<u64>(), found as u64);
+    for (i, &c) in counts.iter().enumerate() {
+        let bucket = lo + 64 * i as u64..lo + 64 * (i as u64 + 1);
+        assert_eq!(c, primes[..found].iter().filter(|p| bucket.contains(p)).count() as u64);
+    }
+}
+
+#[test]
+fn factorizer_pipeline() {
+    use std::time::Duration;
+
+    let full = Factorizer::new().trial_division(100).rho(1_000_000).squfof();
+    for n in (0..3_000).chain(1_000_000_000..1_000_000_500) {
+        let result = full.factor(n);
+        assert!(result.is_complete(), "{}", n);
+        assert_eq!(result.primes, factors(n), "{}", n);
+    }
+    let (p, q) = (4_000_000_007, 4_000_000_009);
+    assert_eq!(full.factor(p * q).primes, vec![p, q]);
+    assert_eq!(full.factor(LARGEST_U64_PRIME).primes, vec![LARGEST_U64_PRIME]);
+    let n = 2 * 3 * 1_000_003 * 1_000_033;
+    assert_eq!(Factorizer::new().rho(1_000_000).factor(n).primes, vec![2, 3, 1_000_003, 1_000_033]);
+
+    // Stages that can't finish leave composite cofactors behind.
+    let trial = Factorizer::new().trial_division(100);
+    let result = trial.factor(4 * 1_000_003 * 1_000_033);
+    assert_eq!(result.primes, vec![2, 2]);
+    assert_eq!(result.composites, vec![1_000_003 * 1_000_033]);
+    assert!(!result.is_complete());
+    let result = Factorizer::new().factor(12);
+    assert_eq!(result.composites, vec![12]);
+
+    // 65537 - 1 = 2^16 is smooth, 4294967291 - 1 = 2 5 19 22605091 isn't.
+    let n = 65_537 * 4_294_967_291;
+    let smooth = Factorizer::new().p_minus_one(70_000);
+    assert_eq!(smooth.factor(n).primes, vec![65_537, 4_294_967_291]);
+    assert!(!Factorizer::new().p_minus_one(10).factor(n).is_complete());
+
+    // The complete pipeline never leaves anything behind.
+    let result = Factorizer::complete().factor(u64::MAX - 1);
+    assert!(result.is_complete());
+    assert_eq!(result.primes, factors(u64::MAX - 1));
+    assert_eq!(Factorizer::complete().factor(p * q).primes, vec![p, q]);
+
+    let expired = full.clone().time_limit(Duration::ZERO).factor(p * q);
+    assert_eq!(expired.composites, vec![p * q]);
+    assert!(expired.primes.is_empty());
+}
+
+#[test]
+fn text_formats() {
+    let mut pset = Sieve::new();
+    let first: Vec<u64> = pset.iter().take(1_000).collect();
+    let mut buf = Vec::new();
+    write_bfile(&mut buf, 1, first.iter().cloned()).unwrap();
+    let text = String::from_utf8(buf.clone()).unwrap();
+    assert!(text.starts_with("1 2\n2 3\n3 5\n"));
+
+    let read = read_bfile(&buf[..]).unwrap();
+    assert_eq!(read.len(), 1_000);
+    assert!(read.iter().zip(1..).all(|(&(i, _), j)| i == j));
+    assert_eq!(read.iter().map(|&(_, p)| p).collect::<Vec<_>>(), first);
+
+    let with_comments = "# A000040\n\n1 2\n2 3   \n  3 5 extra\n";
+    assert_eq!(read_bfile(with_comments.as_bytes()).unwrap(), vec![(1, 2), (2, 3), (3, 5)]);
+    let err = read_bfile("1 2\n2 x\n".as_bytes()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("line 2"));
+
+    for n in (1..2_000).chain([u64::MAX, 1 << 63]) {
+        let line = format_factorization(n, &factors(n));
+        assert_eq!(parse_factorization(&line), Some((n, factors(n))), "{}", line);
+    }
+    assert_eq!(format_factorization(1, &[]), "1:");
+    assert_eq!(format_factorization(12, &[3, 2, 2]), "12: 2^2 * 3");
+    assert_eq!(parse_factorization("12: 2 2 3"), Some((12, vec![2, 2, 3])));
+    assert_eq!(parse_factorization("12: 2^2 * 5"), None);
+    assert_eq!(parse_factorization("12 2^2 * 3"), None);
+    assert_eq!(parse_factorization("12: 2^x"), None);
+    // Degenerate factors and huge exponents are turned away without allocating.
+    assert_eq!(parse_factorization("1:"), Some((1, vec![])));
+    assert_eq!(parse_factorization("1: 1^400000000"), None);
+    assert_eq!(parse_factorization("0: 0^4294967295"), None);
+    assert_eq!(parse_factorization("0:"), None);
+    assert_eq!(parse_factorization("4: 2^2 * 1"), None);
+    assert_eq!(parse_factorization("8: 2^4294967295"), None);
+}
+
+#[test]
+fn smooth_and_rough_numbers() {
+    let largest = |n: u64| factors(n).last().cloned().unwrap_or(0);
+    let smallest = |n: u64| factors(n).first().cloned().unwrap_or(u64::MAX);
+    for bound in [0, 1, 2, 3, 5, 7, 10, 30, 97, 1_000] {
+        let expected: Vec<u64> = (1..3_000).filter(|&n| largest(n) <= bound).collect();
+        assert_eq!(smooth_numbers_below(3_000, bound), expected, "{}", bound);
+        for n in 1..3_000 {
+            assert_eq!(is_smooth(n, bound), largest(n) <= bound, "{} {}", n, bound);
+            assert_eq!(is_rough(n, bound), smallest(n) >= bound, "{} {}", n, bound);
+        }
+    }
+    assert!(!is_smooth(0, 100) && !is_rough(0, 2));
+    assert!(is_smooth(1 << 63, 2));
+    assert!(is_smooth(u64::MAX, 6_700_417) && !is_smooth(u64::MAX, 6_700_416));
+    assert!(is_rough(LARGEST_U64_PRIME, 1_000_000));
+    assert!(!is_rough(u64::MAX, 4));
+    assert_eq!(smooth_numbers_below(10, 100), vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    assert!(smooth_numbers_below(1, 5).is_empty());
+}
+
+#[test]
+fn prime_predicates() {
+    let mut pset = Sieve::new();
+    let mut below = |pred: PrimePredicate, n: u64| -> Vec<u64> {
+        pred.filter(pset.iter().take_while(|&p| p < n)).collect()
+    };
+    assert_eq!(below(PrimePredicate::Twin, 50), vec![3, 5, 7, 11, 13, 17, 19, 29, 31, 41, 43]);
+    assert_eq!(below(PrimePredicate::SafePrime, 100), vec![5, 7, 11, 23, 47, 59, 83]);
+    let sophie_germain = vec![2, 3, 5, 11, 23, 29, 41, 53, 83, 89];
+    assert_eq!(below(PrimePredicate::SophieGermain, 100), sophie_germain);
+    let palindromic = vec![2, 3, 5, 7, 11, 101, 131, 151, 181, 191];
+    assert_eq!(below(PrimePredicate::Palindromic, 200), palindromic);
+    assert_eq!(below(PrimePredicate::Mod(1, 4), 40), vec![5, 13, 17, 29, 37]);
+    assert_eq!(below(PrimePredicate::Mod(9, 4), 40), vec![5, 13, 17, 29, 37]);
+    fn has_seven(p: u64) -> bool {
+        p.to_string().contains('7')
+    }
+    assert_eq!(below(PrimePredicate::Custom(has_seven), 80), vec![7, 17, 37, 47, 67, 71, 73, 79]);
+
+    let both = PrimePredicate::SophieGermain.and(PrimePredicate::SafePrime);
+    assert_eq!(below(both, 100), vec![5, 11, 23, 83]);
+    let either = PrimePredicate::Palindromic.or(PrimePredicate::Mod(0, 13));
+    assert_eq!(below(either, 110), vec![2, 3, 5, 7, 11, 13, 101]);
+
+    assert!(PrimePredicate::Twin.matches(LARGEST_U64_PRIME) == is_prime(LARGEST_U64_PRIME - 2));
+    assert!(!PrimePredicate::SophieGermain.matches(LARGEST_U64_PRIME));
+
+    // Straight from a prime set, or from all primes in the palindromes' case.
+    let safe: Vec<u64> = pset.iter_matching(PrimePredicate::SafePrime).take(7).collect();
+    assert_eq!(safe, vec![5, 7, 11, 23, 47, 59, 83]);
+    let wanted = PrimePredicate::Palindromic.and(PrimePredicate::Mod(3, 4));
+    let big: Vec<u64> = wanted.primes().skip_while(|&p| p < 1_000_000_000_000).take(2).collect();
+    assert!(big.iter().all(|&p| p % 4 == 3 && is_prime_const(p)), "{:?}", big);
+    assert_eq!(PrimePredicate::Twin.primes().take(4).collect::<Vec<_>>(), vec![3, 5, 7, 11]);
+}
+
+#[test]
+fn squarefree_and_radical() {
+    for n in 1..5_000u64 {
+        let mut unique = factors(n);
+        let len = unique.len();
+        unique.dedup();
+        assert_eq!(is_squarefree(n), unique.len() == len, "{}", n);
+        assert_eq!(radical(n), unique.iter().product::<u64>(), "{}", n);
+    }
+    assert!(!is_squarefree(0));
+    assert_eq!(radical(0), 0);
+    assert_eq!(radical(360), 30);
+
+    // Squares of large primes, and squarefree products of them, without factoring either.
+    let (p, q) = (4_294_967_291u64, 4_294_967_279u64);
+    assert!(!is_squarefree(p * p));
+    assert!(is_squarefree(p * q));
+    assert!(!is_squarefree(4 * 1_000_003 * 1_000_003));
+    assert!(is_squarefree(u64::MAX));
+    assert_eq!(radical(1 << 63), 2);
+    assert_eq!(radical(u64::MAX), u64::MAX);
+}
+
+#[test]
+fn semiprimes() {
+    for n in 0..3_000u64 {
+        assert_eq!(is_semiprime(n), factors(n).len() == 2, "{}", n);
+    }
+    let (p, q) = (4_294_967_291u64, 4_294_967_279u64);
+    assert!(is_semiprime(p * q) && is_semiprime(p * p));
+    assert!(!is_semiprime(LARGEST_U64_PRIME) && !is_semiprime(3 * p * 5));
+    assert!(is_semiprime(2 * p) && !is_semiprime(4 * p));
+
+    #[cfg(feature = "rand")]
+    {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mut rng = StdRng::seed_from_u64(12);
+        let factorizer = Factorizer::new().trial_division(100).rho(1 << 24);
+        for bits in 4..=64 {
+            let n = gen_semiprime(bits, &mut rng);
+            assert_eq!(64 - n.leading_zeros(), bits, "{}", n);
+            let found = factorizer.factor(n);
+            assert!(found.is_complete() && found.primes.len() == 2, "{}", n);
+            let lens: Vec<u32> = found.primes.iter().map(|p| 64 - p.leading_zeros()).collect();
+            assert!(lens[1] - lens[0] <= 2, "{} {:?}", n, found.primes);
+        }
+    }
+}
+
+#[test]
+fn cached_primality() {
+    let mut pset = TrialDivision::from_primes(vec![2, 3]).unwrap();
+    for n in 0..5_000 {
+        assert_eq!(pset.is_prime_cached(n), is_prime(n), "{}", n);
+    }
+    // Only the primes up to the square root were needed.
+    assert_eq!(pset.list().last(), Some(&71));
+    assert!(pset.is_prime_cached(LARGEST_U64_PRIME));
+    assert!(pset.list().last().is_some_and(|&last| last <= CACHED_TRIAL_BOUND + 100));
+
+    let (p, q) = (4_294_967_291u64, 4_294_967_279u64);
+    let mut pset = Sieve::from_prefix(vec![2, 3]).unwrap();
+    assert!(pset.is_prime_cached(p) && pset.is_prime_cached(LARGEST_U64_PRIME));
+    assert!(!pset.is_prime_cached(p * q) && !pset.is_prime_cached(u64::MAX));
+    assert!(pset.list().last().is_some_and(|&last| last < 100_000));
+
+    // A larger cache is still only divided by up to the bound, and Miller–Rabin agrees with it.
+    pset.expand_to(70_000);
+    assert!(pset.is_prime_cached(p) && !pset.is_prime_cached(65_537 * 65_521));
+    assert!(!pset.is_prime_cached(1_000_003 * 1_000_033));
+}
+
+#[test]
+fn prime_set_contains() {
+    let mut pset = TrialDivision::from_primes(vec![2, 3]).unwrap();
+    assert_eq!(pset.contains_cached(3), Some(true));
+    assert_eq!(pset.contains_cached(4), None);
+    assert!(pset.contains(97) && !pset.contains(91) && !pset.contains(0) && !pset.contains(1));
+    assert_eq!(pset.list().last(), Some(&97));
+    for n in 0..=97 {
+        assert_eq!(pset.contains_cached(n), Some(is_prime(n)), "{}", n);
+    }
+    assert_eq!(pset.contains_cached(98), None);
+
+    let mut pset = Sieve::from_prefix(vec![2, 3]).unwrap();
+    assert!(pset.contains(1_000_003) && !pset.contains(1_000_001));
+    assert_eq!(pset.contains_cached(999_983), Some(true));
+}
+
+#[test]
+fn index_by_range() {
+    let mut pset = TrialDivision::from_primes(vec![2, 3]).unwrap();
+    pset.expand_to(30);
+    assert_eq!(pset[3], 7);
+    assert_eq!(&pset[2..5], &[5, 7, 11]);
+    assert_eq!(&pset[..4], &[2, 3, 5, 7]);
+    assert_eq!(&pset[8..], &[23, 29, 31]);
+    assert_eq!(pset.slice(0..3), &[2, 3, 5]);
+
+    let mut sieve = Sieve::from_prefix(vec![2, 3]).unwrap();
+    sieve.expand_to(1_000);
+    assert_eq!(&sieve[..11], pset.list());
+    assert_eq!(sieve.slice(165..168), &[983, 991, 997]);
+    assert_eq!(sieve[167], 997);
+}
+
+#[test]
+fn prime_set_iter_hints_and_ends() {
+    let mut pset = TrialDivision::from_primes(vec![2, 3]).unwrap();
+    pset.expand_to(30);
+    {
+        let mut iter = pset.iter();
+        assert_eq!(iter.size_hint(), (11, None));
+        assert_eq!(iter.next_back(), Some(31));
+        assert_eq!(iter.size_hint(), (10, Some(10)));
+        assert_eq!(iter.nth(2), Some(5));
+        let rest: Vec<u64> = iter.rev().collect();
+        assert_eq!(rest, vec![29, 23, 19, 17, 13, 11, 7]);
+    }
+    assert_eq!(pset.list().last(), Some(&31));
+
+    // Meeting in the middle, nothing is returned twice.
+    let mut iter = pset.iter();
+    let (a, b, c) = (iter.next(), iter.next_back(), iter.next());
+    assert_eq!((a, b, c), (Some(2), Some(31), Some(3)));
+    assert_eq!(iter.count(), 8);
+
+    // `nth` far past the cache expands straight to it.
+    let mut pset = TrialDivision::from_primes(vec![2, 3]).unwrap();
+    assert_eq!(pset.iter().nth(9_999), Some(104_729));
+    assert!(pset.len() >= 10_000 && pset.list()[9_999] == 104_729);
+    let mut sieve = Sieve::from_prefix(vec![2, 3]).unwrap();
+    assert_eq!(sieve.iter().nth(999_999), Some(15_485_863));
+    assert_eq!(sieve.generator().size_hint().1, None);
+}
+
+#[test]
+fn iterate_found_primes_only() {
+    let mut pset = Sieve::from_prefix(vec![2, 3]).unwrap();
+    pset.expand_to(100);
+    let len = pset.len();
+
+    let mut found = pset.iter_found();
+    assert_eq!(found.len(), len);
+    assert_eq!(found.next(), Some(2));
+    assert_eq!(found.next_back(), pset.list().last().cloned());
+    assert_eq!(found.len(), len - 2);
+    assert_eq!(found.nth(2), Some(7));
+    assert!(pset.iter_found().eq(pset.iter_vec().cloned()));
+    assert_eq!(pset.iter_found().rev().count(), len);
+    let fresh = TrialDivision::from_primes(vec![2, 3]).unwrap();
+    assert_eq!(fresh.iter_found().collect::<Vec<_>>(), vec![2, 3]);
+}
+
+#[test]
+fn seed_trial_division() {
+    let mut sieve = Sieve::new();
+    sieve.expand_to(10_000);
+    let table = sieve.list().to_vec();
+
+    let mut pset = TrialDivision::from_primes(table[..100].to_vec()).unwrap();
+    assert_eq!(pset.len(), 100);
+    pset.extend(table[100..].iter().cloned());
+    assert_eq!(pset.list(), &table[..]);
+    pset.expand_to(20_000);
+    sieve.expand_to(20_000);
+    assert_eq!(pset.list(), &sieve.list()[..pset.len()]);
+
+    assert!(TrialDivision::from_primes(vec![2, 3, 5]).is_some());
+    assert!(TrialDivision::from_primes(vec![3, 5, 7]).is_none());
+    assert!(TrialDivision::from_primes(vec![2, 3, 7, 5]).is_none());
+    assert!(TrialDivision::from_primes(vec![2, 3, 5, 9]).is_none());
+    assert!(TrialDivision::from_primes(Vec::new()).is_none());
+
+    let collected: TrialDivision = table.iter().cloned().take(50).collect();
+    assert_eq!(collected.list(), &table[..50]);
+}
+
+#[cfg(feature = "table")]
+#[test]
+fn generators_start_from_built_in_table() {
+    let mut sieve = Sieve::new();
+    let mut pset = TrialDivision::new();
+    assert_eq!(sieve.len(), 10_000);
+    assert_eq!(pset.list(), sieve.list());
+    assert_eq!(sieve.list().last(), Some(&104_729));
+    assert!(sieve.list().iter().all(|&p| is_prime(p)));
+    sieve.expand();
+    assert_eq!(sieve[10_000], 104_743);
+    assert_eq!(pset.try_expand(), Some(104_743));
+    assert_eq!(Sieve::with_buffers(vec![7], vec![0; 4]).len(), 10_000);
+}
+
+#[test]
+fn const_primality_and_tables() {
+    const TABLE: [u64; 1_000] = first_n_primes::<1_000>();
+    const IS_LARGEST_PRIME: bool = is_prime_const(LARGEST_U64_PRIME);
+    let mut pset = Sieve::new();
+    pset.expand_to(10_000);
+    assert_eq!(&TABLE[..], &pset.list()[..1_000]);
+    assert_eq!(first_n_primes::<0>(), []);
+    assert_eq!(first_n_primes::<1>(), [2]);
+    assert!(IS_LARGEST_PRIME && !is_prime_const(u64::MAX));
+    for n in 0..5_000 {
+        assert_eq!(is_prime_const(n), is_prime(n), "{}", n);
+    }
+}
+
+#[test]
+fn prime_newtype() {
+    use std::convert::TryFrom;
+
+    for n in 0..2_000 {
+        assert_eq!(Prime::new(n).is_some(), is_prime(n), "{}", n);
+        let expected = Prime::new(n).map(Prime::get).ok_or(NotPrime(n));
+        assert_eq!(Prime::try_from(n).map(u64::from), expected);
+    }
+    assert_eq!(NotPrime(91).to_string(), "91 is not prime");
+    assert_eq!(Prime::from_prime(97).to_string(), "97");
+
+    let two = Prime::from_prime(2);
+    assert_eq!(two.prev(), None);
+    assert_eq!(two.next().and_then(Prime::next).map(Prime::get), Some(5));
+    assert_eq!(Prime::from_prime(LARGEST_U64_PRIME).next(), None);
+    assert_eq!(Prime::from_prime(LARGEST_U64_PRIME).prev().map(u64::from), Some(u64::MAX - 82));
+    assert_eq!(PrimeModulus::from(Prime::from_prime(13)).inv(5), Some(8));
+
+    let exp: Vec<(u64, u32)> = factors_exp(360).into_iter().map(|(p, e)| (p.get(), e)).collect();
+    assert_eq!(exp, vec![(2, 3), (3, 2), (5, 1)]);
+    assert!(factors_exp(1).is_empty() && factors_exp(0).is_empty());
+}
+
+#[test]
+fn prime_field_arithmetic() {
+    let p = PrimeModulus::from_prime(101);
+    for a in 0..101 {
+        let x = Fp::new(a, p);
+        for b in [0, 1, 2, 50, 100] {
+            let y = Fp::new(b, p);
+            assert_eq!((x + y).value(), (a + b) % 101);
+            assert_eq!((x - y).value(), (a + 101 - b) % 101);
+            assert_eq!((x * y).value(), a * b % 101);
+            if b != 0 {
+                assert_eq!(x / y * y, x);
+            }
+        }
+        assert_eq!(x.inv().map(|i| (i * x).value()), if a == 0 { None } else { Some(1) });
+        assert_eq!((-x + x), Fp::new(0, p));
+    }
+    let mut z = Fp::new(3, p);
+    z += Fp::new(4, p);
+    z *= Fp::new(10, p);
+    z -= Fp::new(80, p);
+    z /= Fp::new(5, p);
+    // -10 / 5 = -2
+    assert_eq!(z.value(), 99);
+    assert_eq!(z.to_string(), "99 (mod 101)");
+
+    // Near the top of the range, products and sums still don't overflow.
+    let big = PrimeModulus::from_prime(LARGEST_U64_PRIME);
+    let x = Fp::new(u64::MAX, big);
+    assert_eq!(x.value(), 58);
+    let y = Fp::new(LARGEST_U64_PRIME - 1, big);
+    assert_eq!((y * y).value(), 1);
+    assert_eq!((y + y).value(), LARGEST_U64_PRIME - 2);
+    assert_eq!(y.pow(LARGEST_U64_PRIME - 1).value(), 1);
+    assert!(Fp::new(0, big).inv().is_none() && Fp::new(LARGEST_U64_PRIME, big).is_zero());
+}
+
+#[test]
+fn primes_for_table_sizes() {
+    for n in 0..3_000 {
+        let next = (n..).find(|&m| is_prime(m));
+        let prev = (0..=n).rev().find(|&m| is_prime(m));
+        assert_eq!(next_prime_at_least(n), next, "{}", n);
+        assert_eq!(prev_prime_at_most(n), prev, "{}", n);
+    }
+    assert_eq!(next_prime_at_least(LARGEST_U64_PRIME - 23), Some(LARGEST_U64_PRIME));
+    assert_eq!(next_prime_at_least(LARGEST_U64_PRIME + 1), None);
+    assert_eq!(prev_prime_at_most(u64::MAX), Some(LARGEST_U64_PRIME));
+
+    assert_eq!(&CAPACITY_PRIMES[..8], &[7, 13, 29, 53, 97, 193, 389, 769]);
+    for (k, w) in CAPACITY_PRIMES.windows(2).enumerate() {
+        assert!(is_prime_const(w[0]) && w[1] > w[0] * 3 / 2 && w[1] < w[0] * 5 / 2, "{}", k);
+        // The smallest prime above 1.5 times a power of two.
+        assert!(w[1] > 3 << (k + 2) && prev_prime_at_most(w[1] - 1) <= Some(3 << (k + 2)));
+    }
+    assert_eq!(prime_capacity_for(0), Some(7));
+    assert_eq!(prime_capacity_for(100), Some(193));
+    assert_eq!(prime_capacity_for(1 << 20), Some(1_572_869));
+    let top = *CAPACITY_PRIMES.last().unwrap();
+    assert_eq!(prime_capacity_for(top + 1), next_prime_at_least(top + 1));
+    assert_eq!(prime_capacity_for(u64::MAX), None);
+}
+
+#[test]
+fn analytic_approximations() {
+    // li(x) to nine or more significant figures, against published values.
+    let li = [(10, 6.165_599_5), (1_000, 177.609_657_9), (1_000_000, 78_627.549_159_5)];
+    for &(x, expected) in &li {
+        let got = prime_pi_approx(x);
+        assert!((got - expected).abs() < expected * 1e-9, "{} {}", x, got);
+    }
+    assert!((prime_pi_approx(1_000_000_000) - 50_849_234.957).abs() < 1e-2);
+    assert!((prime_pi_approx(u64::MAX) / 4.253e17 - 1.0).abs() < 1e-3);
+    assert_eq!(prime_pi_approx(1), 0.0);
+
+    // li(nth_prime_approx(n)) = n, and the estimate is close to the true prime.
+    let mut pset = Sieve::new();
+    for &(n, error) in &[(1, 0.05), (10, 0.5), (1_000, 0.02), (100_000, 2e-3), (1_000_000, 5e-4)] {
+        let x = nth_prime_approx(n);
+        assert!((prime_pi_approx(x as u64) - n as f64).abs() <= 1.0, "{} {}", n, x);
+        let p = pset.get(n as usize - 1) as f64;
+        assert!((x - p).abs() < p * error, "{} {} {}", n, x, p);
+    }
+
+    // The exact counts sit below li(x), and θ(x) is close to x.
+    let exact = pset.iter().take_while(|&p| p <= 1_000_000).count() as f64;
+    assert!(exact < prime_pi_approx(1_000_000));
+    assert!((chebyshev_theta(1_000_000) / 1e6 - 1.0).abs() < 2e-3);
+    assert_eq!(mertens(1_000), 2);
+}
+
+#[test]
+fn ramanujan_primes() {
+    // OEIS A104272.
+    let known = vec![
+        2, 11, 17, 29, 41, 47, 59, 67, 71, 97, 101, 107, 127, 149, 151, 167, 179, 181, 227, 229,
+        233, 239, 241, 263, 269, 281, 307, 311, 347, 349, 367, 373, 401, 409, 419, 431, 433, 439,
+    ];
+    assert_eq!(ramanujan_primes_below(440), known);
+    assert_eq!(ramanujan_primes_below(439), known[..known.len() - 1].to_vec());
+    assert!(ramanujan_primes_below(2).is_empty() && ramanujan_primes_below(0).is_empty());
+    for (n, &r) in known.iter().enumerate() {
+        assert_eq!(nth_ramanujan_prime(n as u64 + 1), r);
+    }
+
+    // Checked directly from the definition: π(x) - π(x/2) never drops below n again from R_n.
+    let n = 1_000;
+    let r = nth_ramanujan_prime(n);
+    let mut pset = Sieve::new();
+    pset.expand_to(3 * r);
+    let pi = |x: u64| pset.list().partition_point(|&p| p <= x) as u64;
+    assert!((r..3 * r).all(|x| pi(x) - pi(x / 2) >= n));
+    assert!(pi(r - 1) - pi((r - 1) / 2) < n);
+    assert_eq!(ramanujan_primes_below(r + 1).len() as u64, n);
+}
+
+#[test]
+fn digit_defined_primes() {
+    let palindromic: Vec<u64> = special::palindromic_primes().take(21).collect();
+    let expected = vec![
+        2, 3, 5, 7, 11, 101, 131, 151, 181, 191, 313, 353, 373, 383, 727, 757, 787, 797, 919, 929,
+        10_301,
+    ];
+    assert_eq!(palindromic, expected);
+    // 5,953 palindromic primes have at most 9 digits (OEIS A050251).
+    let mut palindromic = special::palindromic_primes();
+    assert_eq!(palindromic.by_ref().take_while(|&p| p < 1_000_000_000).count(), 5_953);
+    assert!(palindromic.take(3).all(|p| p.to_string().len() == 11));
+
+    let circular: Vec<u64> = special::circular_primes().take(55).collect();
+    assert_eq!(&circular[..14], &[2, 3, 5, 7, 11, 13, 17, 31, 37, 71, 73, 79, 97, 113]);
+    assert_eq!(circular.last(), Some(&999_331));
+
+    let right: Vec<u64> = special::right_truncatable_primes().collect();
+    assert_eq!(right.len(), 83);
+    assert_eq!(&right[..10], &[2, 3, 5, 7, 23, 29, 31, 37, 53, 59]);
+    assert_eq!(right.last(), Some(&73_939_133));
+
+    let left: Vec<u64> = special::left_truncatable_primes().take(20).collect();
+    assert_eq!(
+        left,
+        vec![2, 3, 5, 7, 13, 17, 23, 37, 43, 47, 53, 67, 73, 83, 97, 113, 137, 167, 173, 197]
+    );
+    // Counts by number of digits, from OEIS A050986.
+    let mut counts = vec![0; 10];
+    for p in special::left_truncatable_primes().take_while(|&p| p < 1_000_000_000) {
+        counts[p.to_string().len()] += 1;
+    }
+    assert_eq!(counts, vec![0, 4, 11, 39, 99, 192, 326, 429, 521, 545]);
+}
+
+#[test]
+fn cunningham_chain_search() {
+    let first: Vec<_> = cunningham_chains(CunninghamKind::First, 6)
+        .take_while(|chain| chain[0] < 500_000)
+        .map(|chain| (chain[0], chain.len()))
+        .collect();
+    assert_eq!(first, vec![(89, 6), (63419, 6), (127139, 6), (405269, 6)]);
+
+    let second: Vec<_> = cunningham_chains(CunninghamKind::Second, 5)
+        .take_while(|chain| chain[0] < 20_000)
+        .map(|chain| chain[0])
+        .collect();
+    assert_eq!(second, vec![1531, 6841, 15391, 16651]);
+
+    // Chains are complete, so 5 and 11 don't start their own after 2.
+    let mut all = cunningham_chains(CunninghamKind::First, 1);
+    assert_eq!(all.next(), Some(vec![2, 5, 11, 23, 47]));
+    assert_eq!(all.next(), Some(vec![3, 7]));
+    assert_eq!(all.next(), Some(vec![13]));
+    assert_eq!(cunningham_chains(CunninghamKind::Second, 1).next(), Some(vec![2, 3, 5]));
+}
+
+#[test]
+fn stream_primes_below() {
+    let mut seen = Vec::new();
+    for_each_prime_below(100, |p| seen.push(p));
+    assert_eq!(seen, Sieve::new().iter().take_while(|&p| p < 100).collect::<Vec<_>>());
+
+    let mut count = 0;
+    for_each_prime_below(0, |_| count += 1);
+    for_each_prime_below(2, |_| count += 1);
+    assert_eq!(count, 0);
+    for_each_prime_below(3, |_| count += 1);
+    assert_eq!(count, 1);
+
+    #[cfg(feature = "parallel")]
+    {
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        let (count, sum) = (AtomicU64::new(0), AtomicU64::new(0));
+        par_for_each_prime_below(50_000_000, |p| {
+            count.fetch_add(1, Ordering::Relaxed);
+            sum.fetch_add(p, Ordering::Relaxed);
+        });
+        let (mut expect_count, mut expect_sum) = (0, 0);
+        for_each_prime_below(50_000_000, |p| {
+            expect_count += 1;
+            expect_sum += p;
+        });
+        assert_eq!(count.into_inner(), expect_count);
+        assert_eq!(sum.into_inner(), expect_sum);
+    }
+}
+
+#[test]
+fn bounded_prime_set_regenerates_evicted_blocks() {
+    let mut pset = BoundedPrimeSet::new(1 << 20);
+    let mut reference = Sieve::new();
+    let far = pset.get(200_000);
+    assert_eq!(far, reference.get(200_000));
+    assert!(pset.memory_used() <= pset.max_bytes());
+    assert!(pset.len() > 200_000);
+
+    // The blocks at the start have been evicted, and come back exact.
+    let head: Vec<u64> = pset.iter().take(1000).collect();
+    assert_eq!(head, reference.iter().take(1000).collect::<Vec<_>>());
+    assert_eq!(pset.find(far - 1), (200_000, far));
+    assert_eq!(pset.find(1_000_000), reference.find(1_000_000));
+    assert!(pset.is_prime(far) && !pset.is_prime(far + 1));
+    assert!(pset.is_prime(LARGEST_U64_PRIME));
+    assert!(pset.memory_used() <= pset.max_bytes());
+
+    // A cap smaller than one block still keeps the block in use.
+    let mut tiny = BoundedPrimeSet::new(0);
+    assert_eq!(tiny.get(100_000), reference.get(100_000));
+    assert!(tiny.memory_used() > 0);
+}
+
+#[test]
+fn checked_get_and_find() {
+    let mut pset = TrialDivision::new();
+    assert_eq!(pset.try_get(10), Some(31));
+    assert_eq!(pset.try_find(100), Ok((25, 101)));
+    assert_eq!(pset.try_find(LARGEST_U64_PRIME + 1), Err(PrimeError::Overflow));
+    assert_eq!(pset.try_get(usize::MAX), None);
+    assert_eq!(pset.work_limit(), None);
+
+    let mut limited = Sieve::new().with_work_limit(10_000);
+    assert_eq!(limited.work_limit(), Some(10_000));
+    assert_eq!(limited.try_get(1228), Some(9973));
+    assert_eq!(limited.try_get(50_000), None);
+    assert_eq!(limited.try_find(9_000), Ok((1117, 9001)));
+    assert_eq!(limited.try_find(1_000_000), Err(PrimeError::LimitExceeded));
+    assert!(limited.list().last().unwrap() < &611_953);
+
+    // Limits nest, and the unchecked methods go on regardless.
+    let mut nested = limited.with_work_limit(1_000_000);
+    assert_eq!(nested.work_limit(), Some(10_000));
+    assert_eq!(nested.get(5000), 48_619);
+    assert_eq!(nested.try_get(5000), Some(48_619));
+    assert_eq!(PrimeError::LimitExceeded.to_string(), "prime is past the work limit");
+}
+
+#[test]
+fn progress_and_cancellation() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    assert_eq!(prime_pi(0), 0);
+    assert_eq!(prime_pi(2), 1);
+    assert_eq!(prime_pi(1_000_000), 78_498);
+    let primes = quick::below(10_001);
+    for x in 0..=10_000 {
+        assert_eq!(prime_pi(x), primes.partition_point(|&p| p <= x) as u64, "{}", x);
+    }
+    #[cfg(not(feature = "tiny"))]
+    assert_eq!(prime_pi(10_000_000_000), 455_052_511);
+
+    let mut last = 0.0;
+    let mut progress = Progress::new(|done| {
+        assert!(done >= last);
+        last = done;
+        true
+    });
+    assert_eq!(prime_pi_with_progress(10_000_000, &mut progress), Ok(664_579));
+    drop(progress);
+    assert_eq!(last, 1.0);
+
+    // Cancelled part of the way, keeping what was found.
+    let mut pset = Sieve::new();
+    let mut calls = 0;
+    let mut progress = Progress::new(|_| {
+        calls += 1;
+        calls < 3
+    });
+    let result = pset.expand_to_with_progress(100_000_000, &mut progress);
+    assert_eq!(result, Err(PrimeError::Cancelled));
+    assert!(progress.is_cancelled());
+    let found = *pset.list().last().unwrap();
+    assert!(found > 1000 && found < 100_000_000);
+    assert_eq!(pset.expand_to_with_progress(found + 1000, &mut Progress::default()), Ok(()));
+
+    let flag = AtomicBool::new(false);
+    let factorizer = Factorizer::new().trial_division(100).rho(1 << 40);
+    let n = 1_000_000_007 * 998_244_353 * 4;
+    let done = factorizer.factor_with_progress(n, &mut Progress::cancel_flag(&flag));
+    assert_eq!(done.primes, vec![2, 2, 998_244_353, 1_000_000_007]);
+    flag.store(true, Ordering::Relaxed);
+    let stopped = factorizer.factor_with_progress(n, &mut Progress::cancel_flag(&flag));
+    assert_eq!((stopped.primes, stopped.composites), (vec![], vec![n]));
+    let mut after_trial_division = Progress::new(|done| done == 0.0);
+    let stopped = factorizer.factor_with_progress(n, &mut after_trial_division);
+    assert_eq!(stopped.primes, vec![2, 2]);
+    assert_eq!(stopped.composites, vec![1_000_000_007 * 998_244_353]);
+}
+
+#[test]
+fn trial_division_shows_one_prime_per_expand() {
+    let mut pset = TrialDivision::new();
+    let start = pset.len();
+    pset.expand();
+    assert_eq!(pset.len(), start + 1);
+    pset.expand_to(1_000_000);
+    assert_eq!(pset.list().last(), Some(&1_000_003));
+    let len = pset.len();
+    pset.expand();
+    assert_eq!(pset.list()[len], 1_000_033);
+    assert_eq!(pset.find_vec(1_000_034), None);
+    // The gap of 132 after 1,357,201 swallows whole blocks under the `tiny` feature.
+    pset.expand_to(1_357_202);
+    assert_eq!(pset.list().last(), Some(&1_357_333));
+
+    // Primes struck off but not shown yet make way for extended ones.
+    let mut small = TrialDivision::from_primes(vec![2, 3, 5, 7]).unwrap();
+    small.expand();
+    small.extend(vec![13, 17]);
+    assert_eq!(small.list(), &[2, 3, 5, 7, 11, 13, 17]);
+    assert_eq!(small.get(7), 19);
+}
+
+#[test]
+#[should_panic(expected = "15 is not prime")]
+fn trial_division_extend_rejects_composites() {
+    let mut pset = TrialDivision::from_primes(vec![2, 3, 5, 7, 11, 13]).unwrap();
+    pset.extend(vec![15]);
+}
+
+#[test]
+fn incremental_sieve_matches_sieve() {
+    let mut incremental = IncrementalSieve::new();
+    let mut sieve = Sieve::new();
+    let a: Vec<u64> = incremental.iter().take(100_000).collect();
+    let b: Vec<u64> = sieve.iter().take(100_000).collect();
+    assert_eq!(a, b);
+    assert_eq!(incremental.len(), 100_000);
+    incremental.expand();
+    assert_eq!(incremental[100_000], sieve.get(100_000));
+    assert_eq!(incremental.find(1_000_000), (78_498, 1_000_003));
+    assert!(incremental.contains(999_983) && !incremental.contains(999_981));
+}
+
+#[test]
+fn atkin_sieve_matches_sieve() {
+    let mut atkin = AtkinSieve::with_limit(1_000_000);
+    assert_eq!(atkin.limit(), 1_000_000);
+    assert_eq!(atkin.len(), 78_498);
+    let mut sieve = Sieve::new();
+    sieve.expand_to(2_000_000);
+    assert_eq!(atkin.list(), &sieve.list()[..78_498]);
+
+    // Past the limit it sieves again, further out.
+    assert_eq!(atkin.find(1_000_000), (78_498, 1_000_003));
+    assert!(atkin.limit() >= 2_000_000);
+    assert_eq!(atkin.get(100_000), sieve.get(100_000));
+
+    let mut small = AtkinSieve::new();
+    let first: Vec<u64> = small.iter().take(10).collect();
+    assert_eq!(first, vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+}
+
+#[test]
+fn segments_at_every_small_offset() {
+    // Segments that start below, at and among the smallest primes, which the `simd` feature marks
+    // by pattern rather than one multiple at a time.
+    for lo in 0..300 {
+        let mut out = [0u64; 400];
+        let (count, _) = fill_primes(lo..lo + 2000, &mut out);
+        let expected: Vec<u64> = (lo..lo + 2000).filter(|&n| is_prime(n)).collect();
+        assert_eq!(&out[..count], &expected[..], "from {}", lo);
+    }
+    let mut sieve = Sieve::new();
+    sieve.expand_to(3_000_000);
+    assert_eq!(sieve.find(3_000_000), (216_816, 3_000_017));
+}
+
+#[cfg(feature = "ffi")]
+#[test]
+fn c_interface() {
+    use primes::ffi::*;
+
+    assert!(primes_is_prime(LARGEST_U64_PRIME) && !primes_is_prime(1));
+    assert_eq!(primes_next_prime(0), 2);
+    assert_eq!(primes_next_prime(13), 17);
+    assert_eq!(primes_next_prime(LARGEST_U64_PRIME), 0);
+
+    let mut out = [0u64; 64];
+    let n = 1_000_000_007 * 998_244_353 * 12;
+    let count = unsafe { primes_factorize(n, out.as_mut_ptr(), out.len()) };
+    assert_eq!(&out[..count], &[2, 2, 3, 998_244_353, 1_000_000_007]);
+    assert_eq!(unsafe { primes_factorize(1 << 63, std::ptr::null_mut(), 0) }, 63);
+    let mut short = [0u64; 2];
+    assert_eq!(unsafe { primes_factorize(n, short.as_mut_ptr(), 2) }, 5);
+    assert_eq!(short, [2, 2]);
+
+    // Every exported function has its prototype in the header, spelled in C, and nothing else does.
+    let header = include_str!("../include/primes.h");
+    let c_type = |t: &str| match t.trim() {
+        "u64" => "uint64_t",
+        "usize" => "size_t",
+        "bool" => "bool",
+        "*mut u64" => "uint64_t *",
+        t => panic!("no C type for {}", t),
+    };
+    let source = include_str!("../src/ffi.rs");
+    let mut exported = 0;
+    for line in source.lines().filter(|l| l.contains("extern \"C\" fn ")) {
+        let (_, rest) = line.split_once("fn ").unwrap();
+        let (name, rest) = rest.split_once('(').unwrap();
+        let (params, rest) = rest.split_once(')').unwrap();
+        let ret = rest.trim_start_matches(" -> ").trim_end_matches(" {");
+        let params: Vec<String> = params
+            .split(", ")
+            .map(|param| {
+                let (arg, t) = param.split_once(':').unwrap();
+                let t = c_type(t);
+                let sep = if t.ends_with('*') { "" } else { " " };
+                format!("{}{}{}", t, sep, arg)
+            })
+            .collect();
+        let prototype = format!("{} {}({});", c_type(ret), name, params.join(", "));
+        assert!(header.contains(&prototype), "{} is missing from the header", prototype);
+        exported += 1;
+    }
+    assert_eq!(header.matches(" primes_").count(), exported);
+}
+
+#[cfg(feature = "wasm")]
+#[test]
+fn javascript_bindings() {
+    use primes::wasm;
+
+    assert!(wasm::is_prime(97) && !wasm::is_prime(91));
+    assert_eq!(wasm::next_prime(100), Some(101));
+    assert_eq!(wasm::next_prime(LARGEST_U64_PRIME), None);
+    assert_eq!(wasm::factors(600_851_475_143), vec![71, 839, 1471, 6857]);
+    assert_eq!(wasm::factors(1), Vec::<u64>::new());
+
+    let mut stream = wasm::PrimeStream::new(1000);
+    assert_eq!(stream.next(), Some(1009));
+    assert_eq!(stream.take(3), vec![1013, 1019, 1021]);
+    let mut last = wasm::PrimeStream::new(LARGEST_U64_PRIME - 100);
+    assert_eq!(last.take(10).last(), Some(&LARGEST_U64_PRIME));
+    assert_eq!(last.next(), None);
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn command_line_tool() {
+    use std::process::Command;
+
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_primes"))
+            .args(args)
+            .output()
+            .unwrap();
+        (output.status.code(), String::from_utf8(output.stdout).unwrap())
+    };
+    let listed = run(&["list", "--below", "20"]);
+    assert_eq!(listed, (Some(0), String::from("2\n3\n5\n7\n11\n13\n17\n19\n")));
+    let listed = run(&["list", "--from", "1e9", "--below", "1_000_000_010"]);
+    assert_eq!(listed.1, "1000000007\n1000000009\n");
+    assert_eq!(run(&["factor", "600851475143"]).1, "600851475143: 71 839 1471 6857\n");
+    let checked = run(&["check", "2305843009213693951", "91"]);
+    assert_eq!(checked.1, "2305843009213693951 is prime\n91 is not prime\n");
+    assert_eq!(run(&["pi", "1e6"]).1, "78498\n");
+    assert_eq!(run(&["pi", "1e20"]).0, Some(2));
+    assert_eq!(run(&["list"]).0, Some(2));
+    let filtered = run(&["list", "--below", "200", "--filter", "palindromic", "--filter", "3mod4"]);
+    assert_eq!(filtered.1, "3\n7\n11\n131\n151\n191\n");
+    assert_eq!(run(&["list", "--below", "20", "--filter", "1mod0"]).0, Some(2));
+    assert_eq!(run(&["list", "--below", "20", "--filter", "square"]).0, Some(2));
+}
+
+#[test]
+fn enumerate_primes_with_indexes() {
+    let mut pset = TrialDivision::new();
+    let first: Vec<(usize, u64)> = pset.enumerate_primes().take(4).collect();
+    assert_eq!(first, vec![(0, 2), (1, 3), (2, 5), (3, 7)]);
+    assert_eq!(pset.enumerate_primes().nth(1000), Some((1000, pset.get(1000))));
+
+    // Mid-stream, the indexes carry on from the primes already found.
+    let found = pset.len();
+    let (i, p) = pset.generator().indexed().next().unwrap();
+    assert_eq!((i, p), (found, pset.get(found)));
+    assert_eq!(pset.find(p), (i, p));
+
+    let mut both = pset.enumerate_primes();
+    assert_eq!(both.next(), Some((0, 2)));
+    assert_eq!(both.next_back(), Some((found, p)));
+}
+
+#[test]
+fn find_many_at_once() {
+    let mut pset = Sieve::new();
+    let queries = [0, 2, 4, 4, 90, 1000, 7919, 100_000, 1_000_000];
+    let answers = pset.find_many(&queries);
+    let mut reference = TrialDivision::new();
+    let expected: Vec<(usize, u64)> = queries.iter().map(|&n| reference.find(n)).collect();
+    assert_eq!(answers, expected);
+
+    // Out of order, answered all the same.
+    let shuffled = [1_000_000, 4, 7919, 0];
+    let answers = TrialDivision::new().find_many(&shuffled);
+    assert_eq!(answers, vec![(78_498, 1_000_003), (2, 5), (999, 7919), (0, 2)]);
+    assert_eq!(pset.find_many(&[]), vec![]);
+}
+
+#[test]
+fn prime_gap_statistics() {
+    let histogram = stats::gap_histogram(100);
+    let counts: Vec<(u64, u64)> = histogram.into_iter().collect();
+    assert_eq!(counts, vec![(1, 1), (2, 8), (4, 7), (6, 7), (8, 1)]);
+    assert!(stats::gap_histogram(3).is_empty());
+
+    assert_eq!(stats::average_gap(2), None);
+    assert_eq!(stats::average_gap(4), Some(1.0));
+    let mean = stats::average_gap(1_000_000).unwrap();
+    assert!((mean - (999_983 - 2) as f64 / 78_497.0).abs() < 1e-9);
+
+    let records: Vec<(u64, u64)> =
+        stats::maximal_gaps(1_000_000).iter().map(|r| (r.start, r.gap)).collect();
+    assert_eq!(records, vec![
+        (2, 1), (3, 2), (7, 4), (23, 6), (89, 8), (113, 14), (523, 18), (887, 20), (1129, 22),
+        (1327, 34), (9551, 36), (15683, 44), (19609, 52), (31397, 72), (155921, 86),
+        (360653, 96), (370261, 112), (492113, 114),
+    ]);
+    // A gap counts only once the prime ending it is below the limit.
+    assert_eq!(stats::maximal_gaps(1327 + 34).last().unwrap().gap, 22);
+}
+
+#[test]
+fn quadratic_sieve_factoring() {
+    let cases = [
+        1_000_003u128 * 1_000_033,
+        4_294_967_291 * 4_294_967_279,
+        1_000_000_007 * 998_244_353 * 1_000_000_009,
+        1_000_000_000_039 * 1_000_000_000_000_037,
+    ];
+    for n in cases {
+        let f = quadratic_sieve(n).unwrap();
+        assert!(f > 1 && f < n && n % f == 0, "{} is not a factor of {}", f, n);
+    }
+    assert_eq!(quadratic_sieve(1_000_003u128.pow(2)), Some(1_000_003));
+    assert_eq!(quadratic_sieve(1_000_003u128.pow(3)), None);
+    assert_eq!(quadratic_sieve(18_446_744_073_709_551_557), None);
+    assert_eq!(quadratic_sieve(1 << 126), None);
+
+    let factorizer = Factorizer::new().trial_division(100).quadratic_sieve();
+    let n = 3 * 4_294_967_291 * 1_000_003;
+    assert_eq!(factorizer.factor(n).primes, vec![3, 1_000_003, 4_294_967_291]);
+}
+
+#[test]
+fn lucas_sequences() {
+    // Fibonacci and Lucas numbers, then Pell numbers and their companions.
+    assert_eq!(lucas_uv(1, -1, 0, 1000), (0, 2));
+    assert_eq!(lucas_uv(1, -1, 10, 1000), (55, 123));
+    let fibonacci = (2_880_067_194_370_816_120, 6_440_026_026_380_244_498);
+    assert_eq!(lucas_uv(1, -1, 90, u64::MAX), fibonacci);
+    assert_eq!(lucas_uv(2, -1, 40, 1000), (672, 474));
+    // Other parameters, and an even modulus.
+    assert_eq!(lucas_uv(3, 5, 50, 1024), (899, 255));
+    assert_eq!(lucas_uv(3, 5, 50, 1), (0, 0));
+
+    // Every prime passes the Lucas test, with D = 5 not a square modulo it.
+    for p in [7u64, 13, 17, 1_000_003, 18_446_744_073_709_551_557] {
+        assert_eq!(lucas_uv(1, -1, p + 1, p).0, 0);
+    }
+    // 323 = 17 × 19 is the smallest Lucas pseudoprime for these parameters.
+    assert_eq!(lucas_uv(1, -1, 324, 323).0, 0);
+}
+
+#[test]
+fn wieferich_and_wilson_primes() {
+    assert!(special::is_wieferich(1093));
+    assert!(special::is_wieferich(3511));
+    assert!(!special::is_wieferich(1091));
+    assert!(!special::is_wieferich(0));
+    // 1093² fools Fermat's test to base 2, but only primes count.
+    assert!(!special::is_wieferich(1093 * 1093));
+    // The modulus p² is past 2^64 here.
+    assert!(!special::is_wieferich(18_446_744_073_709_551_557));
+    let found: Vec<u64> = special::wieferich_primes(100_000).collect();
+    assert_eq!(found, vec![1093, 3511]);
+
+    assert_eq!(special::is_wilson_prime(563), Some(true));
+    assert_eq!(special::is_wilson_prime(2), Some(false));
+    assert_eq!(special::is_wilson_prime(561), Some(false));
+    let found: Vec<u64> = special::wilson_primes(1000).collect();
+    assert_eq!(found, vec![5, 13, 563]);
+    assert_eq!(special::wilson_primes(13).collect::<Vec<_>>(), vec![5]);
+    // Past 2^32 the answer comes from the searches, which reach 2 × 10^13, and past those no one
+    // knows.
+    assert_eq!(special::is_wilson_prime(4_294_967_311), Some(false));
+    assert_eq!(special::is_wilson_prime(19_999_999_999_997), Some(false));
+    assert_eq!(special::is_wilson_prime(20_000_000_000_021), None);
+    assert_eq!(special::is_wilson_prime(20_000_000_000_023), Some(false));
+    assert_eq!(special::is_wilson_prime(u64::MAX), Some(false));
+}
+
+#[test]
+fn miller_rabin_with_chosen_witnesses() {
+    // The smallest composites that pass with the first k primes as witnesses (OEIS A014233).
+    let bounds = [
+        (1, 2_047),
+        (2, 1_373_653),
+        (3, 25_326_001),
+        (4, 3_215_031_751),
+        (5, 2_152_302_898_747),
+        (6, 3_474_749_660_383),
+        (8, 341_550_071_728_321),
+        (11, 3_825_123_056_546_413_051),
+    ];
+    for (k, n) in bounds {
+        let test = MillerRabin::first_primes(k);
+        assert_eq!(test.witnesses().len(), k);
+        assert!(test.is_prime(n), "{} should fool {} witnesses", n, k);
+        assert!(!MillerRabin::first_primes(k + 1).is_prime(n));
+        assert!(!is_prime_with_witnesses(n, &SINCLAIR_WITNESSES));
+    }
+    let base_two = MillerRabin::first_primes(1);
+    let below: Vec<u64> = (0..2_047).filter(|&n| base_two.is_prime(n)).collect();
+    assert_eq!(below, (0..2_047).filter(|&n| is_prime(n)).collect::<Vec<_>>());
+
+    // Sinclair's witnesses agree with is_prime_const, also where some witness is a multiple of n.
+    let sinclair = MillerRabin::new(&SINCLAIR_WITNESSES);
+    for n in (0..20_000).chain(u64::MAX - 2_000..=u64::MAX) {
+        assert_eq!(sinclair.is_prime(n), is_prime_const(n), "{}", n);
+    }
+    assert!(is_prime_with_witnesses(5, &[5, 10]));
+    assert!(!is_prime_with_witnesses(25, &[5]));
+    assert!(is_prime_with_witnesses(1_000_003, &[]));
+}